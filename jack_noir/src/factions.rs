@@ -0,0 +1,89 @@
+use std::collections::BTreeMap;
+
+/// How far reputation can drift from a relation's base value either way.
+pub const MAX_REPUTATION: i32 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FactionId(pub u32);
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Faction {
+	pub id: FactionId,
+	pub name: String,
+}
+
+/// Every faction, and how they feel about each other.
+///
+/// Relations are one-way, so imps can hate consorts more than consorts hate imps. Each one is a
+/// base value plus reputation that builds up or wears down as things happen in the game.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "bevy", derive(bevy::prelude::Resource))]
+pub struct FactionRegistry {
+	factions: Vec<Faction>,
+	relations: BTreeMap<(FactionId, FactionId), i32>,
+	reputation: BTreeMap<(FactionId, FactionId), i32>,
+}
+
+impl FactionRegistry {
+	/// Adds a new faction, even if one already goes by the same name.
+	pub fn add(&mut self, name: impl Into<String>) -> FactionId {
+		let id = FactionId(self.factions.len() as u32);
+		self.factions.push(Faction {
+			id,
+			name: name.into(),
+		});
+		id
+	}
+
+	pub fn get(&self, id: FactionId) -> Option<&Faction> {
+		self.factions.get(id.0 as usize)
+	}
+
+	/// The first faction with this name.
+	pub fn find(&self, name: &str) -> Option<FactionId> {
+		self.factions
+			.iter()
+			.find(|faction| faction.name == name)
+			.map(|faction| faction.id)
+	}
+
+	pub fn iter(&self) -> impl Iterator<Item = &Faction> {
+		self.factions.iter()
+	}
+
+	pub fn set_relation(&mut self, from: FactionId, to: FactionId, relation: i32) {
+		self.relations.insert((from, to), relation);
+	}
+
+	/// How much `from` likes `to`. Factions like themselves and ignore everyone else unless told
+	/// otherwise.
+	pub fn relation(&self, from: FactionId, to: FactionId) -> i32 {
+		let base = self
+			.relations
+			.get(&(from, to))
+			.copied()
+			.unwrap_or(if from == to { 1 } else { 0 });
+		base + self.reputation(from, to)
+	}
+
+	pub fn reputation(&self, from: FactionId, to: FactionId) -> i32 {
+		self.reputation.get(&(from, to)).copied().unwrap_or(0)
+	}
+
+	pub fn change_reputation(&mut self, from: FactionId, to: FactionId, change: i32) {
+		let reputation = self.reputation.entry((from, to)).or_default();
+		*reputation = (*reputation + change).clamp(-MAX_REPUTATION, MAX_REPUTATION);
+	}
+
+	/// Every reputation that isn't zero.
+	pub fn reputations(&self) -> impl Iterator<Item = (FactionId, FactionId, i32)> + '_ {
+		self.reputation
+			.iter()
+			.filter(|(_, reputation)| **reputation != 0)
+			.map(|((from, to), reputation)| (*from, *to, *reputation))
+	}
+
+	pub fn reset_reputation(&mut self) {
+		self.reputation.clear();
+	}
+}
@@ -0,0 +1,697 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use hashbrown::HashMap;
+use lazy_static::lazy_static;
+
+pub use self::factions::{Faction, FactionId, FactionRegistry, MAX_REPUTATION};
+pub use self::planning::{Plan, PLAN_DEPTH};
+pub use self::utility::{Consideration, Curve, UtilityCurve};
+
+mod factions;
+mod planning;
+#[cfg(feature = "bevy")]
+pub mod plugin;
+mod utility;
+
+/// Facts about the world as far as a [`Jack`] knows, kept in order so plans come out the same
+/// every time.
+pub type WorldState = BTreeMap<String, bool>;
+
+#[derive(Debug, Clone)]
+pub struct Jack {
+	pub potential_targets: Vec<Arc<Target>>,
+	pub personal_values: Vec<PersonalValue>,
+	pub allegiences: HashMap<FactionId, i32>,
+	pub state: WorldState,
+	/// What [`Jack::next_action`] plans toward, if it isn't already true.
+	pub goal: WorldState,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActionType {
+	pub name: String,
+	pub beneficial_to_target: Option<bool>,
+	/// Facts that have to hold before this can be done.
+	pub preconditions: WorldState,
+	/// Facts that hold once this is done.
+	pub effects: WorldState,
+	pub cost: f32,
+	/// How the target affects the weight, for targeted actions.
+	pub utility_curves: Vec<UtilityCurve>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Action {
+	pub action_type: Arc<ActionType>,
+	pub target: Option<Arc<Target>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Target {
+	pub name: String,
+	pub allegiences: HashMap<FactionId, i32>,
+	pub distance: f32,
+	/// How much health the target has left, from 0 to 1.
+	pub health: f32,
+	pub threat: f32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PersonalValue {
+	pub name: String,
+	pub weights: Vec<ActionWeight>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActionWeight {
+	pub action_type: Arc<ActionType>,
+	pub weight: i32,
+}
+
+impl ActionType {
+	pub fn new(name: impl Into<String>, beneficial_to_target: Option<bool>) -> Self {
+		Self {
+			name: name.into(),
+			beneficial_to_target,
+			preconditions: WorldState::new(),
+			effects: WorldState::new(),
+			cost: 0.0,
+			utility_curves: Vec::new(),
+		}
+	}
+
+	pub fn with_precondition(mut self, fact: impl Into<String>, value: bool) -> Self {
+		self.preconditions.insert(fact.into(), value);
+		self
+	}
+
+	pub fn with_effect(mut self, fact: impl Into<String>, value: bool) -> Self {
+		self.effects.insert(fact.into(), value);
+		self
+	}
+
+	pub fn with_cost(mut self, cost: f32) -> Self {
+		self.cost = cost;
+		self
+	}
+
+	pub fn with_utility_curve(mut self, utility_curve: UtilityCurve) -> Self {
+		self.utility_curves.push(utility_curve);
+		self
+	}
+
+	pub fn is_possible(&self, state: &WorldState) -> bool {
+		satisfies(state, &self.preconditions)
+	}
+
+	pub fn apply(&self, state: &WorldState) -> WorldState {
+		let mut state = state.clone();
+		state.extend(self.effects.clone());
+		state
+	}
+}
+
+impl Action {
+	pub fn nothing() -> Self {
+		Self {
+			action_type: NOTHING.clone(),
+			target: None,
+		}
+	}
+}
+
+impl Target {
+	pub fn new(name: impl Into<String>, allegiences: HashMap<FactionId, i32>) -> Self {
+		Self {
+			name: name.into(),
+			allegiences,
+			distance: 0.0,
+			health: 1.0,
+			threat: 0.0,
+		}
+	}
+}
+
+/// Whether every fact in `conditions` holds in `state`. Missing facts count as false.
+pub fn satisfies(state: &WorldState, conditions: &WorldState) -> bool {
+	conditions
+		.iter()
+		.all(|(fact, value)| state.get(fact).copied().unwrap_or(false) == *value)
+}
+
+impl Jack {
+	pub fn next_action(&self, factions: &FactionRegistry) -> Action {
+		if !satisfies(&self.state, &self.goal) {
+			if let Some(action) = self
+				.plan(&self.goal, PLAN_DEPTH, factions)
+				.and_then(|plan| plan.actions.into_iter().next())
+			{
+				return action;
+			}
+		}
+
+		self.weighted_actions(&self.state, factions)
+			.into_iter()
+			.next()
+			.map(|(action, _)| action)
+			.unwrap_or_else(Action::nothing)
+	}
+
+	/// Every action that could be done in `state`, best first.
+	///
+	/// Ties go to the action type that comes first by name, then to the target that comes first
+	/// by name, so the same situation always gets the same answer.
+	pub fn weighted_actions(
+		&self,
+		state: &WorldState,
+		factions: &FactionRegistry,
+	) -> Vec<(Action, f32)> {
+		let mut action_types: Vec<Arc<ActionType>> = vec![];
+		for action_type in self
+			.personal_values
+			.iter()
+			.flat_map(|v| v.weights.iter().map(|w| w.action_type.clone()))
+		{
+			if !action_types.contains(&action_type) {
+				action_types.push(action_type);
+			}
+		}
+
+		let mut weights = action_types
+			.into_iter()
+			.filter(|v| v.is_possible(state))
+			.flat_map(|v| -> Box<dyn Iterator<Item = Action>> {
+				if v.beneficial_to_target.is_some() {
+					Box::new(self.potential_targets.iter().map(move |t| Action {
+						action_type: v.clone(),
+						target: Some(t.clone()),
+					}))
+				} else {
+					Box::new(std::iter::once(Action {
+						action_type: v,
+						target: None,
+					}))
+				}
+			})
+			.map(|a| {
+				let weight = self.weight(&a, factions);
+				(a, weight)
+			})
+			.collect::<Vec<_>>();
+
+		weights.sort_by(|(a, a_weight), (b, b_weight)| {
+			b_weight
+				.total_cmp(a_weight)
+				.then_with(|| a.action_type.name.cmp(&b.action_type.name))
+				.then_with(|| target_name(a).cmp(target_name(b)))
+		});
+		weights
+	}
+
+	fn weight(&self, a: &Action, factions: &FactionRegistry) -> f32 {
+		let personal_value_weight: i32 = self
+			.personal_values
+			.iter()
+			.flat_map(|v| v.weights.iter())
+			.filter(|w| w.action_type == a.action_type)
+			.map(|w| w.weight)
+			.sum();
+
+		let (allegience_weight, utility_weight) =
+			match (&a.target, a.action_type.beneficial_to_target) {
+				(Some(target), Some(beneficial_to_target)) => {
+					let allegience_weight = self.allegience_to(target, factions)
+						* if beneficial_to_target { 1 } else { -1 };
+					let utility_weight = a
+						.action_type
+						.utility_curves
+						.iter()
+						.map(|curve| curve.evaluate(target))
+						.sum::<f32>();
+					(allegience_weight, utility_weight)
+				}
+				_ => (0, 0.0),
+			};
+
+		(personal_value_weight + allegience_weight) as f32 + utility_weight - a.action_type.cost
+	}
+
+	/// How much Jack likes the target, going by how each of his factions feels about each of
+	/// theirs.
+	fn allegience_to(&self, target: &Target, factions: &FactionRegistry) -> i32 {
+		self.allegiences
+			.iter()
+			.flat_map(|(from, allegience)| {
+				target
+					.allegiences
+					.iter()
+					.map(move |(to, target_allegience)| {
+						allegience * target_allegience * factions.relation(*from, *to)
+					})
+			})
+			.sum()
+	}
+}
+
+fn target_name(action: &Action) -> &str {
+	action
+		.target
+		.as_ref()
+		.map(|target| target.name.as_str())
+		.unwrap_or_default()
+}
+
+lazy_static! {
+	pub static ref NOTHING: Arc<ActionType> = Arc::new(ActionType::new("Nothing", None));
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[allow(dead_code)]
+	struct TestInfo {
+		stab: Arc<ActionType>,
+		do_midnight_crew_things: Arc<ActionType>,
+		factions: FactionRegistry,
+		midnight_crew: FactionId,
+		derse: FactionId,
+		jack_faction: FactionId,
+		carapacian: FactionId,
+		diamonds_droog: Arc<Target>,
+		carapacian_35: Arc<Target>,
+		jack: Jack,
+	}
+
+	fn test_info() -> TestInfo {
+		let stab = Arc::new(ActionType::new("Stab", Some(false)));
+		let do_midnight_crew_things =
+			Arc::new(ActionType::new("Do midnight crew things", Some(true)));
+
+		let mut factions = FactionRegistry::default();
+		let midnight_crew = factions.add("Midnight Crew");
+		let derse = factions.add("Derse");
+		let jack_faction = factions.add("Jack");
+		// Droog and #35 are both carapacians
+		let carapacian = factions.add("Carapacian");
+
+		let diamonds_droog = Arc::new(Target {
+			name: "Diamonds Droog".to_string(),
+			allegiences: {
+				let mut map = HashMap::new();
+				map.insert(midnight_crew, 1);
+				map.insert(derse, 1);
+				map.insert(jack_faction, 1);
+				map.insert(carapacian, 5);
+				map
+			},
+			distance: 2.0,
+			health: 1.0,
+			threat: 1.0,
+		});
+		let carapacian_35 = Arc::new(Target {
+			name: "Carapacian #35".to_string(),
+			allegiences: {
+				let mut map = HashMap::new();
+				map.insert(midnight_crew, -1);
+				map.insert(derse, 1);
+				map.insert(jack_faction, -1);
+				map.insert(carapacian, 5);
+				map
+			},
+			distance: 5.0,
+			health: 1.0,
+			threat: 1.0,
+		});
+
+		let jack = Jack {
+			potential_targets: vec![diamonds_droog.clone(), carapacian_35.clone()],
+			personal_values: vec![
+				PersonalValue {
+					name: "Stabbing".to_string(),
+					weights: vec![ActionWeight {
+						action_type: stab.clone(),
+						weight: 1,
+					}],
+				},
+				PersonalValue {
+					name: "Midnight Crew Succeed".to_string(),
+					weights: vec![ActionWeight {
+						action_type: do_midnight_crew_things.clone(),
+						weight: 1,
+					}],
+				},
+			],
+			allegiences: {
+				let mut map = HashMap::new();
+				map.insert(midnight_crew, 1);
+				map.insert(derse, 1);
+				map.insert(jack_faction, 5);
+				map.insert(carapacian, -1);
+				map
+			},
+			state: WorldState::new(),
+			goal: WorldState::new(),
+		};
+
+		TestInfo {
+			stab,
+			do_midnight_crew_things,
+			factions,
+			midnight_crew,
+			derse,
+			jack_faction,
+			carapacian,
+			diamonds_droog,
+			carapacian_35,
+			jack,
+		}
+	}
+
+	#[test]
+	fn jack_stabs_carapacian_by_default() {
+		let test_info = test_info();
+
+		assert_eq!(
+			test_info.jack.next_action(&test_info.factions),
+			Action {
+				action_type: test_info.stab.clone(),
+				target: Some(test_info.carapacian_35.clone()),
+			}
+		);
+	}
+
+	#[test]
+	fn jack_wants_midnight_crew_to_succeed_when_theres_no_carapacians_to_stab() {
+		let mut test_info = test_info();
+		test_info.jack.potential_targets = vec![test_info.diamonds_droog.clone()];
+
+		assert_eq!(
+			test_info.jack.next_action(&test_info.factions),
+			Action {
+				action_type: test_info.do_midnight_crew_things.clone(),
+				target: Some(test_info.diamonds_droog.clone()),
+			}
+		);
+	}
+
+	#[test]
+	fn jack_does_nothing_when_theres_no_one_to_stab() {
+		let mut test_info = test_info();
+		test_info.jack.potential_targets = vec![];
+
+		assert_eq!(
+			test_info.jack.next_action(&test_info.factions),
+			Action {
+				action_type: NOTHING.clone(),
+				target: None,
+			}
+		);
+	}
+
+	/// Makes Jack draw his knife before he can stab anyone.
+	fn with_knife(test_info: &mut TestInfo) -> Arc<ActionType> {
+		let draw_knife =
+			Arc::new(ActionType::new("Draw knife", None).with_effect("knife drawn", true));
+		test_info.stab = Arc::new(
+			ActionType::new("Stab", Some(false))
+				.with_precondition("knife drawn", true)
+				.with_effect("stabbed someone", true),
+		);
+		test_info.jack.personal_values[0].weights[0].action_type = test_info.stab.clone();
+		test_info.jack.personal_values.push(PersonalValue {
+			name: "Preparedness".to_string(),
+			weights: vec![ActionWeight {
+				action_type: draw_knife.clone(),
+				weight: 0,
+			}],
+		});
+		draw_knife
+	}
+
+	fn carapacian(test_info: &TestInfo, name: &str, distance: f32) -> Arc<Target> {
+		Arc::new(Target {
+			name: name.to_string(),
+			distance,
+			..(*test_info.carapacian_35).clone()
+		})
+	}
+
+	#[test]
+	fn jack_considers_untargeted_actions() {
+		let mut test_info = test_info();
+		let brood = Arc::new(ActionType::new("Brood", None));
+		test_info.jack.personal_values.push(PersonalValue {
+			name: "Noir".to_string(),
+			weights: vec![ActionWeight {
+				action_type: brood.clone(),
+				weight: 20,
+			}],
+		});
+
+		assert_eq!(
+			test_info.jack.next_action(&test_info.factions),
+			Action {
+				action_type: brood,
+				target: None,
+			}
+		);
+	}
+
+	#[test]
+	fn jack_wont_stab_without_his_knife() {
+		let mut test_info = test_info();
+		with_knife(&mut test_info);
+
+		assert_eq!(
+			test_info.jack.next_action(&test_info.factions),
+			Action {
+				action_type: test_info.do_midnight_crew_things.clone(),
+				target: Some(test_info.diamonds_droog.clone()),
+			}
+		);
+	}
+
+	#[test]
+	fn jack_draws_his_knife_to_stab_someone() {
+		let mut test_info = test_info();
+		let draw_knife = with_knife(&mut test_info);
+		test_info.jack.goal = WorldState::from([("stabbed someone".to_string(), true)]);
+
+		assert_eq!(
+			test_info
+				.jack
+				.plan(&test_info.jack.goal, PLAN_DEPTH, &test_info.factions),
+			Some(Plan {
+				actions: vec![
+					Action {
+						action_type: draw_knife.clone(),
+						target: None,
+					},
+					Action {
+						action_type: test_info.stab.clone(),
+						target: Some(test_info.carapacian_35.clone()),
+					},
+				],
+				cost: 0.0,
+			})
+		);
+		assert_eq!(
+			test_info.jack.next_action(&test_info.factions),
+			Action {
+				action_type: draw_knife,
+				target: None,
+			}
+		);
+	}
+
+	#[test]
+	fn jack_stabs_once_his_knife_is_drawn() {
+		let mut test_info = test_info();
+		with_knife(&mut test_info);
+		test_info.jack.goal = WorldState::from([("stabbed someone".to_string(), true)]);
+		test_info.jack.state = WorldState::from([("knife drawn".to_string(), true)]);
+
+		assert_eq!(
+			test_info.jack.next_action(&test_info.factions),
+			Action {
+				action_type: test_info.stab.clone(),
+				target: Some(test_info.carapacian_35.clone()),
+			}
+		);
+	}
+
+	#[test]
+	fn jack_takes_the_cheaper_plan() {
+		let mut test_info = test_info();
+		let draw_knife = with_knife(&mut test_info);
+		let draw_knife = Arc::new(ActionType {
+			cost: 3.0,
+			..(*draw_knife).clone()
+		});
+		let pick_up_knife = Arc::new(
+			ActionType::new("Pick up knife", None)
+				.with_effect("knife drawn", true)
+				.with_cost(1.0),
+		);
+		test_info.jack.personal_values[2].weights = vec![
+			ActionWeight {
+				action_type: draw_knife,
+				weight: 0,
+			},
+			ActionWeight {
+				action_type: pick_up_knife.clone(),
+				weight: 0,
+			},
+		];
+		test_info.jack.goal = WorldState::from([("stabbed someone".to_string(), true)]);
+
+		let plan = test_info
+			.jack
+			.plan(&test_info.jack.goal, PLAN_DEPTH, &test_info.factions)
+			.unwrap();
+		assert_eq!(plan.actions[0].action_type, pick_up_knife);
+		assert_eq!(plan.cost, 1.0);
+	}
+
+	#[test]
+	fn jack_gives_up_on_unreachable_goals() {
+		let mut test_info = test_info();
+		test_info.jack.goal = WorldState::from([("won the game".to_string(), true)]);
+
+		assert_eq!(
+			test_info
+				.jack
+				.plan(&test_info.jack.goal, PLAN_DEPTH, &test_info.factions),
+			None
+		);
+		assert_eq!(
+			test_info.jack.next_action(&test_info.factions),
+			Action {
+				action_type: test_info.stab.clone(),
+				target: Some(test_info.carapacian_35.clone()),
+			}
+		);
+	}
+
+	#[test]
+	fn ties_go_to_the_first_target_by_name() {
+		let mut test_info = test_info();
+		let carapacian_12 = carapacian(&test_info, "Carapacian #12", 5.0);
+		test_info.jack.potential_targets =
+			vec![test_info.carapacian_35.clone(), carapacian_12.clone()];
+
+		assert_eq!(
+			test_info.jack.next_action(&test_info.factions),
+			Action {
+				action_type: test_info.stab.clone(),
+				target: Some(carapacian_12),
+			}
+		);
+	}
+
+	#[test]
+	fn jack_stabs_the_closest_carapacian() {
+		let mut test_info = test_info();
+		test_info.stab = Arc::new(ActionType::new("Stab", Some(false)).with_utility_curve(
+			UtilityCurve {
+				consideration: Consideration::Distance,
+				curve: Curve::Linear {
+					slope: -1.0,
+					intercept: 0.0,
+				},
+				weight: 1.0,
+			},
+		));
+		test_info.jack.personal_values[0].weights[0].action_type = test_info.stab.clone();
+		let carapacian_12 = carapacian(&test_info, "Carapacian #12", 20.0);
+		test_info.jack.potential_targets = vec![test_info.carapacian_35.clone(), carapacian_12];
+
+		assert_eq!(
+			test_info.jack.next_action(&test_info.factions),
+			Action {
+				action_type: test_info.stab.clone(),
+				target: Some(test_info.carapacian_35.clone()),
+			}
+		);
+	}
+
+	#[test]
+	fn curves() {
+		let linear = Curve::Linear {
+			slope: 2.0,
+			intercept: 1.0,
+		};
+		assert_eq!(linear.evaluate(3.0), 7.0);
+		assert_eq!(Curve::Power { exponent: 2.0 }.evaluate(0.5), 0.25);
+		let step = Curve::Step {
+			threshold: 0.5,
+			below: 1.0,
+			above: 0.0,
+		};
+		assert_eq!(step.evaluate(0.25), 1.0);
+		assert_eq!(step.evaluate(0.5), 0.0);
+	}
+
+	#[test]
+	fn factions_with_the_same_name_are_still_different_factions() {
+		let mut factions = FactionRegistry::default();
+		let first = factions.add("Carapacian");
+		let second = factions.add("Carapacian");
+
+		assert_ne!(first, second);
+		assert_eq!(factions.find("Carapacian"), Some(first));
+		assert_eq!(factions.relation(first, second), 0);
+	}
+
+	#[test]
+	fn jack_stops_stabbing_carapacians_he_respects() {
+		let mut test_info = test_info();
+		test_info
+			.factions
+			.change_reputation(test_info.jack_faction, test_info.carapacian, 1);
+
+		assert_eq!(
+			test_info.jack.next_action(&test_info.factions),
+			Action {
+				action_type: test_info.do_midnight_crew_things.clone(),
+				target: Some(test_info.diamonds_droog.clone()),
+			}
+		);
+	}
+
+	#[test]
+	fn jack_stabs_droog_when_derse_turns_on_the_midnight_crew() {
+		let mut test_info = test_info();
+		test_info.jack.potential_targets = vec![test_info.diamonds_droog.clone()];
+		test_info
+			.factions
+			.set_relation(test_info.derse, test_info.midnight_crew, -3);
+
+		assert_eq!(
+			test_info.jack.next_action(&test_info.factions),
+			Action {
+				action_type: test_info.stab.clone(),
+				target: Some(test_info.diamonds_droog.clone()),
+			}
+		);
+	}
+
+	#[test]
+	fn reputation_only_goes_so_far() {
+		let mut test_info = test_info();
+		test_info.factions.change_reputation(
+			test_info.derse,
+			test_info.carapacian,
+			MAX_REPUTATION * 3,
+		);
+
+		assert_eq!(
+			test_info
+				.factions
+				.relation(test_info.derse, test_info.carapacian),
+			MAX_REPUTATION
+		);
+	}
+}
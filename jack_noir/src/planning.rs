@@ -0,0 +1,100 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, BinaryHeap};
+
+use crate::{satisfies, Action, FactionRegistry, Jack, WorldState};
+
+/// How many actions ahead [`Jack::next_action`] plans.
+pub const PLAN_DEPTH: usize = 3;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Plan {
+	pub actions: Vec<Action>,
+	pub cost: f32,
+}
+
+/// A partial plan waiting to be expanded, cheapest first and then oldest first.
+struct Node {
+	plan: Plan,
+	state: WorldState,
+	order: usize,
+}
+
+impl PartialEq for Node {
+	fn eq(&self, other: &Self) -> bool {
+		self.cmp(other) == Ordering::Equal
+	}
+}
+
+impl Eq for Node {}
+
+impl PartialOrd for Node {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl Ord for Node {
+	fn cmp(&self, other: &Self) -> Ordering {
+		// Reversed, since BinaryHeap pops the biggest
+		other
+			.plan
+			.cost
+			.total_cmp(&self.plan.cost)
+			.then_with(|| other.order.cmp(&self.order))
+	}
+}
+
+impl Jack {
+	/// The cheapest chain of at most `max_depth` actions that makes `goal` true.
+	///
+	/// Plans that cost the same go to whichever one [`Jack::weighted_actions`] would rather start
+	/// with.
+	pub fn plan(
+		&self,
+		goal: &WorldState,
+		max_depth: usize,
+		factions: &FactionRegistry,
+	) -> Option<Plan> {
+		let mut open = BinaryHeap::new();
+		let mut visited = BTreeSet::new();
+		let mut order = 0;
+
+		open.push(Node {
+			plan: Plan {
+				actions: vec![],
+				cost: 0.0,
+			},
+			state: self.state.clone(),
+			order,
+		});
+
+		while let Some(Node { plan, state, .. }) = open.pop() {
+			if satisfies(&state, goal) {
+				return Some(plan);
+			}
+			if plan.actions.len() >= max_depth || !visited.insert(state.clone()) {
+				continue;
+			}
+
+			for (action, _) in self.weighted_actions(&state, factions) {
+				let next_state = action.action_type.apply(&state);
+				// Actions that don't change anything can't get any closer to the goal
+				if next_state == state || visited.contains(&next_state) {
+					continue;
+				}
+
+				order += 1;
+				let mut plan = plan.clone();
+				plan.cost += action.action_type.cost.max(0.0);
+				plan.actions.push(action);
+				open.push(Node {
+					plan,
+					state: next_state,
+					order,
+				});
+			}
+		}
+
+		None
+	}
+}
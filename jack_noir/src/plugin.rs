@@ -0,0 +1,242 @@
+use std::sync::Arc;
+
+use bevy::prelude::*;
+use hashbrown::HashMap;
+
+use crate::{Action, FactionId, FactionRegistry, Jack, PersonalValue, Target, WorldState};
+
+/// Has every [`Decider`] pick its next [`Action`] from the entities around it.
+pub struct JackNoirPlugin;
+
+impl Plugin for JackNoirPlugin {
+	fn build(&self, app: &mut App) {
+		app.init_resource::<FactionRegistry>()
+			.add_systems(Update, choose_actions.in_set(ChooseActionSet));
+	}
+}
+
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ChooseActionSet;
+
+/// How an entity feels about each faction, both when it's deciding and when it's a target.
+#[derive(Component, Debug, Clone, Default)]
+pub struct Allegiances(pub HashMap<FactionId, i32>);
+
+impl Allegiances {
+	/// The factions this entity counts as part of, as opposed to ones it just has feelings about.
+	pub fn members(&self) -> impl Iterator<Item = FactionId> + '_ {
+		self.0
+			.iter()
+			.filter(|(_, allegiance)| **allegiance > 0)
+			.map(|(faction, _)| *faction)
+	}
+}
+
+impl FromIterator<(FactionId, i32)> for Allegiances {
+	fn from_iter<T: IntoIterator<Item = (FactionId, i32)>>(iter: T) -> Self {
+		Self(HashMap::from_iter(iter))
+	}
+}
+
+/// How a target looks to a [`Decider`], for utility curves.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct TargetStats {
+	/// From 0 to 1.
+	pub health: f32,
+	pub threat: f32,
+}
+
+impl Default for TargetStats {
+	fn default() -> Self {
+		Self {
+			health: 1.0,
+			threat: 0.0,
+		}
+	}
+}
+
+/// An entity that picks its own actions, like [`Jack`] does.
+#[derive(Component, Debug, Clone)]
+#[require(Allegiances, ChosenAction)]
+pub struct Decider {
+	pub personal_values: Vec<PersonalValue>,
+	/// How far away an entity with [`Allegiances`] can be and still be considered as a target.
+	pub perception_radius: f32,
+	pub state: WorldState,
+	pub goal: WorldState,
+}
+
+/// What a [`Decider`] most recently decided on, with the entity behind its target.
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct ChosenAction {
+	pub action: Action,
+	pub target: Option<Entity>,
+}
+
+impl Default for ChosenAction {
+	fn default() -> Self {
+		Self {
+			action: Action::nothing(),
+			target: None,
+		}
+	}
+}
+
+fn choose_actions(
+	mut deciders: Query<(
+		Entity,
+		&Decider,
+		&Allegiances,
+		&GlobalTransform,
+		&mut ChosenAction,
+	)>,
+	targets: Query<(
+		Entity,
+		&Allegiances,
+		&GlobalTransform,
+		Option<&TargetStats>,
+		Option<&Name>,
+	)>,
+	factions: Res<FactionRegistry>,
+) {
+	for (entity, decider, allegiances, transform, mut chosen_action) in deciders.iter_mut() {
+		let mut potential_targets = targets
+			.iter()
+			.map(
+				|(target, target_allegiances, target_transform, stats, name)| {
+					let stats = stats.copied().unwrap_or_default();
+					(
+						target,
+						Arc::new(Target {
+							name: name
+								.map(|name| name.to_string())
+								.unwrap_or_else(|| target.to_string()),
+							allegiences: target_allegiances.0.clone(),
+							distance: target_transform
+								.translation()
+								.distance(transform.translation()),
+							health: stats.health,
+							threat: stats.threat,
+						}),
+					)
+				},
+			)
+			.filter(|(target, target_info)| {
+				*target != entity && target_info.distance <= decider.perception_radius
+			})
+			.collect::<Vec<_>>();
+		// So ties don't depend on archetype order
+		potential_targets.sort_by_key(|(target, _)| *target);
+
+		let jack = Jack {
+			potential_targets: potential_targets
+				.iter()
+				.map(|(_, target)| target.clone())
+				.collect(),
+			personal_values: decider.personal_values.clone(),
+			allegiences: allegiances.0.clone(),
+			state: decider.state.clone(),
+			goal: decider.goal.clone(),
+		};
+		let action = jack.next_action(&factions);
+		let target = action.target.as_ref().and_then(|action_target| {
+			potential_targets
+				.iter()
+				.find(|(_, target)| Arc::ptr_eq(target, action_target))
+				.map(|(target, _)| *target)
+		});
+
+		chosen_action.set_if_neq(ChosenAction { action, target });
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{ActionType, ActionWeight};
+
+	struct TestInfo {
+		app: App,
+		attack: Arc<ActionType>,
+		imp: Entity,
+		player: Entity,
+	}
+
+	fn test_info() -> TestInfo {
+		let attack = Arc::new(ActionType::new("Attack", Some(false)));
+		let mut app = App::new();
+		app.add_plugins(JackNoirPlugin);
+
+		let mut factions = app.world_mut().resource_mut::<FactionRegistry>();
+		let imps = factions.add("Imps");
+		let players = factions.add("Players");
+
+		let imp = app
+			.world_mut()
+			.spawn((
+				Decider {
+					personal_values: vec![PersonalValue {
+						name: "Mischief".to_string(),
+						weights: vec![ActionWeight {
+							action_type: attack.clone(),
+							weight: 1,
+						}],
+					}],
+					perception_radius: 10.0,
+					state: WorldState::new(),
+					goal: WorldState::new(),
+				},
+				Allegiances::from_iter([(imps, 5), (players, -1)]),
+				GlobalTransform::IDENTITY,
+			))
+			.id();
+		let player = app
+			.world_mut()
+			.spawn((
+				Allegiances::from_iter([(players, 5)]),
+				GlobalTransform::from_translation(Vec3::X * 5.0),
+			))
+			.id();
+
+		TestInfo {
+			app,
+			attack,
+			imp,
+			player,
+		}
+	}
+
+	#[test]
+	fn imp_attacks_nearby_player() {
+		let mut test_info = test_info();
+		test_info.app.update();
+
+		let chosen_action = test_info
+			.app
+			.world()
+			.get::<ChosenAction>(test_info.imp)
+			.unwrap();
+		assert_eq!(chosen_action.action.action_type, test_info.attack);
+		assert_eq!(chosen_action.target, Some(test_info.player));
+	}
+
+	#[test]
+	fn imp_does_nothing_when_player_is_out_of_range() {
+		let mut test_info = test_info();
+		test_info
+			.app
+			.world_mut()
+			.entity_mut(test_info.player)
+			.insert(GlobalTransform::from_translation(Vec3::X * 50.0));
+		test_info.app.update();
+
+		assert_eq!(
+			test_info
+				.app
+				.world()
+				.get::<ChosenAction>(test_info.imp)
+				.unwrap(),
+			&ChosenAction::default()
+		);
+	}
+}
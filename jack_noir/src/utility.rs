@@ -0,0 +1,67 @@
+use crate::Target;
+
+/// Something about a target that can make an action more or less appealing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Consideration {
+	Distance,
+	Health,
+	Threat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Curve {
+	Linear {
+		slope: f32,
+		intercept: f32,
+	},
+	/// `x` raised to `exponent`, so low values matter much less than high ones, or the other way
+	/// around.
+	Power {
+		exponent: f32,
+	},
+	/// `below` under the threshold and `above` at or over it.
+	Step {
+		threshold: f32,
+		below: f32,
+		above: f32,
+	},
+}
+
+impl Curve {
+	pub fn evaluate(&self, x: f32) -> f32 {
+		match *self {
+			Curve::Linear { slope, intercept } => slope * x + intercept,
+			Curve::Power { exponent } => x.max(0.0).powf(exponent),
+			Curve::Step {
+				threshold,
+				below,
+				above,
+			} => {
+				if x < threshold {
+					below
+				} else {
+					above
+				}
+			}
+		}
+	}
+}
+
+/// Adds to an action's weight based on its target.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UtilityCurve {
+	pub consideration: Consideration,
+	pub curve: Curve,
+	pub weight: f32,
+}
+
+impl UtilityCurve {
+	pub fn evaluate(&self, target: &Target) -> f32 {
+		let x = match self.consideration {
+			Consideration::Distance => target.distance,
+			Consideration::Health => target.health,
+			Consideration::Threat => target.threat,
+		};
+		self.curve.evaluate(x) * self.weight
+	}
+}
@@ -0,0 +1,60 @@
+//! Triangle counts and build times per chunk at each level of detail.
+//!
+//! Run with `cargo bench -p marching_cubes`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use bevy::math::IVec3;
+use marching_cubes::*;
+
+const RUNS: u32 = 5;
+
+fn main() {
+	let settings = ChunkSettings::default();
+	let planet = Planet::new(100.0, 8.0, 0);
+	let edits = TerrainEdits::default();
+
+	// A patch of chunks that the planet's surface runs through
+	let surface = settings.chunk_at(bevy::math::Vec3::X * planet.radius);
+	let chunks = (-2..=2)
+		.flat_map(|y| (-2..=2).map(move |z| surface + IVec3::new(0, y, z)))
+		.collect::<Vec<_>>();
+
+	println!(
+		"{} chunks of {} voxels, best of {RUNS} runs",
+		chunks.len(),
+		settings.voxels_per_chunk
+	);
+	println!("lod  neighbours  triangles/chunk  build time/chunk");
+
+	for lod in 0..=settings.max_lod() {
+		// Borders next to full detail chunks are the most work to match
+		for (name, neighbours) in [
+			("same lod", NeighbourLods::new(|_| Some(lod))),
+			("lod 0", NeighbourLods::new(|_| Some(0))),
+		] {
+			let mut best = Duration::MAX;
+			let mut triangles = 0;
+
+			for _ in 0..RUNS {
+				triangles = 0;
+
+				let start = Instant::now();
+				for chunk in chunks.iter() {
+					let chunk_triangles =
+						march_chunk(&planet, &edits, &settings, *chunk, lod, &neighbours);
+					triangles += chunk_triangles.len();
+					black_box(triangles_to_mesh(&chunk_triangles));
+				}
+				best = best.min(start.elapsed());
+			}
+
+			println!(
+				"{lod:>3}  {name:>10}  {:>15}  {:>16.2?}",
+				triangles / chunks.len(),
+				best / chunks.len() as u32
+			);
+		}
+	}
+}
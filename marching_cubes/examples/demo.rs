@@ -0,0 +1,65 @@
+use bevy::color::palettes::css;
+use bevy::color::palettes::tailwind;
+use bevy::pbr::wireframe::{WireframeConfig, WireframePlugin};
+use bevy::prelude::*;
+use marching_cubes::*;
+
+fn main() {
+	App::new()
+		.add_plugins((
+			DefaultPlugins,
+			WireframePlugin,
+			bevy_inspector_egui::quick::WorldInspectorPlugin::new(),
+			bevy_panorbit_camera::PanOrbitCameraPlugin,
+			MarchingCubesPlugin,
+		))
+		.insert_resource(WireframeConfig {
+			global: true,
+			default_color: css::WHITE.into(),
+		})
+		.add_systems(Startup, setup)
+		.run();
+}
+
+fn setup(mut commands: Commands, mut materials: ResMut<Assets<StandardMaterial>>) {
+	commands.spawn((
+		Name::new("Camera"),
+		Camera3d::default(),
+		Transform::from_xyz(20.0, 32.5, 40.0).looking_at(Vec3::ZERO, Vec3::Y),
+		bevy_panorbit_camera::PanOrbitCamera {
+			button_orbit: MouseButton::Left,
+			button_pan: MouseButton::Left,
+			modifier_pan: Some(KeyCode::ShiftLeft),
+			reversed_zoom: true,
+			..default()
+		},
+		TerrainViewer,
+	));
+
+	commands.spawn((
+		Name::new("Light"),
+		PointLight {
+			range: 100.0,
+			..default()
+		},
+		Transform::from_xyz(20.0, 40.0, 20.0),
+	));
+
+	let material = materials.add(Color::from(tailwind::EMERALD_500));
+	let settings = ChunkSettings {
+		chunk_size: 8.0,
+		voxels_per_chunk: 16,
+	};
+
+	commands.spawn((
+		Name::new("Box"),
+		Terrain::covering(RotatedBox::default(), settings, 16.0, material.clone()),
+	));
+
+	commands.spawn((
+		Name::new("Planet"),
+		Terrain::covering(Planet::new(12.0, 2.0, 0), settings, 16.0, material)
+			.with_lod_distances([40.0, 60.0]),
+		Transform::from_xyz(40.0, 0.0, 0.0),
+	));
+}
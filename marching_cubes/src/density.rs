@@ -0,0 +1,292 @@
+use std::f32::consts::PI;
+
+use bevy::math::{IVec3, Quat, Vec3};
+
+/// How far either side of a point [`DensityField::gradient`] samples by default.
+const GRADIENT_EPSILON: f32 = 0.01;
+
+/// Something that says how solid every point in space is.
+///
+/// Anywhere the density is above [`SURFACE_THRESHOLD`](crate::SURFACE_THRESHOLD) is inside the
+/// terrain. Fields are sampled from the task pool, so they need to be thread-safe.
+pub trait DensityField: Send + Sync + 'static {
+	/// `position` is local to the terrain.
+	fn sample(&self, position: Vec3) -> f32;
+
+	/// Which way density increases fastest, for normals.
+	///
+	/// Fields that know their own slope can override this, otherwise it's found by sampling either
+	/// side of `position`.
+	fn gradient(&self, position: Vec3) -> Vec3 {
+		let dx = self.sample(position + Vec3::X * GRADIENT_EPSILON)
+			- self.sample(position - Vec3::X * GRADIENT_EPSILON);
+		let dy = self.sample(position + Vec3::Y * GRADIENT_EPSILON)
+			- self.sample(position - Vec3::Y * GRADIENT_EPSILON);
+		let dz = self.sample(position + Vec3::Z * GRADIENT_EPSILON)
+			- self.sample(position - Vec3::Z * GRADIENT_EPSILON);
+		Vec3::new(dx, dy, dz) / (2.0 * GRADIENT_EPSILON)
+	}
+}
+
+impl<F: Fn(Vec3) -> f32 + Send + Sync + 'static> DensityField for F {
+	fn sample(&self, position: Vec3) -> f32 {
+		self(position)
+	}
+}
+
+/// A box tipped on its side, like the one in the original demo.
+#[derive(Debug, Clone, Copy)]
+pub struct RotatedBox {
+	pub center: Vec3,
+	pub dimensions: Vec3,
+	pub rotation: Quat,
+}
+
+impl Default for RotatedBox {
+	fn default() -> Self {
+		Self {
+			center: Vec3::ZERO,
+			dimensions: Vec3::new(9.5, 20.5, 24.0),
+			rotation: Quat::from_axis_angle(Vec3::ONE.normalize(), PI / 3.0),
+		}
+	}
+}
+
+impl DensityField for RotatedBox {
+	fn sample(&self, position: Vec3) -> f32 {
+		let position = self.rotation * (position - self.center);
+		let half_dimensions = self.dimensions / 2.0;
+
+		let normalized_distance = position.abs() / half_dimensions;
+		1.0 - normalized_distance.max_element()
+	}
+}
+
+/// Several octaves of Perlin noise that know how steep they are, so [`Planet`] can have exact
+/// normals without sampling around them.
+#[derive(Debug, Clone)]
+struct SlopedNoise {
+	permutation: [u8; 256],
+	octaves: u32,
+	frequency: f32,
+}
+
+impl SlopedNoise {
+	/// Where the gradients at the lattice points can point, Perlin's twelve cube edges.
+	const GRADIENTS: [Vec3; 12] = [
+		Vec3::new(1.0, 1.0, 0.0),
+		Vec3::new(-1.0, 1.0, 0.0),
+		Vec3::new(1.0, -1.0, 0.0),
+		Vec3::new(-1.0, -1.0, 0.0),
+		Vec3::new(1.0, 0.0, 1.0),
+		Vec3::new(-1.0, 0.0, 1.0),
+		Vec3::new(1.0, 0.0, -1.0),
+		Vec3::new(-1.0, 0.0, -1.0),
+		Vec3::new(0.0, 1.0, 1.0),
+		Vec3::new(0.0, -1.0, 1.0),
+		Vec3::new(0.0, 1.0, -1.0),
+		Vec3::new(0.0, -1.0, -1.0),
+	];
+
+	fn new(seed: u32, octaves: u32, frequency: f32) -> Self {
+		// Shuffled with splitmix64, which is plenty random for picking gradients
+		let mut permutation = std::array::from_fn(|i| i as u8);
+		let mut state = seed as u64;
+		for i in (1..permutation.len()).rev() {
+			state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+			let mut random = state;
+			random = (random ^ (random >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+			random = (random ^ (random >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+			random ^= random >> 31;
+			permutation.swap(i, (random % (i as u64 + 1)) as usize);
+		}
+
+		Self {
+			permutation,
+			octaves,
+			frequency,
+		}
+	}
+
+	fn lattice_gradient(&self, point: IVec3) -> Vec3 {
+		let hash = |value: i32| self.permutation[(value & 255) as usize] as i32;
+		Self::GRADIENTS[hash(hash(hash(point.x) + point.y) + point.z) as usize % 12]
+	}
+
+	/// One octave's value at a point.
+	fn octave_value(&self, point: Vec3) -> f32 {
+		let cell = point.floor();
+		let local = point - cell;
+		let cell = cell.as_ivec3();
+		// Perlin's quintic fade
+		let fade = local * local * local * (local * (local * 6.0 - 15.0) + 10.0);
+
+		let contribution = |x: i32, y: i32, z: i32| {
+			let offset = IVec3::new(x, y, z);
+			self.lattice_gradient(cell + offset)
+				.dot(local - offset.as_vec3())
+		};
+		let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+		let along_x = |y: i32, z: i32| lerp(contribution(0, y, z), contribution(1, y, z), fade.x);
+		let along_y = |z: i32| lerp(along_x(0, z), along_x(1, z), fade.y);
+		lerp(along_y(0), along_y(1), fade.z)
+	}
+
+	/// Which way one octave increases fastest at a point.
+	fn octave_slope(&self, point: Vec3) -> Vec3 {
+		let cell = point.floor();
+		let local = point - cell;
+		let cell = cell.as_ivec3();
+		// Perlin's quintic fade, and its slope
+		let fade = local * local * local * (local * (local * 6.0 - 15.0) + 10.0);
+		let fade_slope = 30.0 * local * local * (local * (local - 2.0) + 1.0);
+
+		let mut slope = Vec3::ZERO;
+		for corner in 0..8 {
+			let offset = IVec3::new(corner & 1, (corner >> 1) & 1, (corner >> 2) & 1);
+			let gradient = self.lattice_gradient(cell + offset);
+			let contribution = gradient.dot(local - offset.as_vec3());
+
+			let far = offset.cmpeq(IVec3::ONE);
+			let weights = Vec3::select(far, fade, Vec3::ONE - fade);
+			let weight_slopes = Vec3::select(far, fade_slope, -fade_slope);
+			let weight_slope = Vec3::new(
+				weight_slopes.x * weights.y * weights.z,
+				weights.x * weight_slopes.y * weights.z,
+				weights.x * weights.y * weight_slopes.z,
+			);
+
+			slope += weights.element_product() * gradient + contribution * weight_slope;
+		}
+		slope
+	}
+
+	/// Where each octave is read from, how much it's scaled up and how much it counts for.
+	fn octaves(&self) -> impl Iterator<Item = (Vec3, f32, f32)> + '_ {
+		(0..self.octaves as i32).map(|octave| {
+			// Shifted so the octaves don't all line up on the same lattice points
+			let shift = Vec3::splat(octave as f32 * 31.7);
+			(
+				shift,
+				self.frequency * 2.0_f32.powi(octave),
+				0.5_f32.powi(octave),
+			)
+		})
+	}
+
+	fn get(&self, point: Vec3) -> f32 {
+		let (value, total_amplitude) = self.octaves().fold(
+			(0.0, 0.0),
+			|(value, total_amplitude), (shift, frequency, amplitude)| {
+				let octave = self.octave_value(point * frequency + shift);
+				(value + octave * amplitude, total_amplitude + amplitude)
+			},
+		);
+		value / total_amplitude
+	}
+
+	/// Which way the noise increases fastest at a point.
+	fn slope(&self, point: Vec3) -> Vec3 {
+		let (slope, total_amplitude) = self.octaves().fold(
+			(Vec3::ZERO, 0.0),
+			|(slope, total_amplitude), (shift, frequency, amplitude)| {
+				let octave = self.octave_slope(point * frequency + shift);
+				(
+					slope + octave * amplitude * frequency,
+					total_amplitude + amplitude,
+				)
+			},
+		);
+		slope / total_amplitude
+	}
+}
+
+/// A ball of rock with hills and valleys, centered on the terrain's origin.
+#[derive(Debug, Clone)]
+pub struct Planet {
+	pub radius: f32,
+	/// How far hills rise above and valleys sink below [`Planet::radius`].
+	pub amplitude: f32,
+	noise: SlopedNoise,
+}
+
+impl Planet {
+	pub fn new(radius: f32, amplitude: f32, seed: u32) -> Self {
+		Self {
+			radius,
+			amplitude,
+			// Roughly one hill per radius around the equator
+			noise: SlopedNoise::new(seed, 4, 1.0 / radius),
+		}
+	}
+
+	/// How far the surface is from the center in this direction.
+	pub fn height_at(&self, direction: Vec3) -> f32 {
+		let point = direction.normalize_or_zero() * self.radius;
+		self.radius + self.noise.get(point) * self.amplitude
+	}
+}
+
+impl DensityField for Planet {
+	fn sample(&self, position: Vec3) -> f32 {
+		// One unit of density per unit of distance keeps the surface's slope easy to interpolate
+		crate::SURFACE_THRESHOLD + self.height_at(position) - position.length()
+	}
+
+	fn gradient(&self, position: Vec3) -> Vec3 {
+		let distance = position.length();
+		if distance == 0.0 {
+			return Vec3::ZERO;
+		}
+		let direction = position / distance;
+
+		// Moving sideways slides the point the noise is read from around the sphere, scaled down by
+		// how far out we are. Moving straight out doesn't move it at all.
+		let noise_slope = self.noise.slope(direction * self.radius);
+		let sideways_slope = noise_slope - direction * noise_slope.dot(direction);
+		sideways_slope * self.amplitude * self.radius / distance - direction
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn planet_gradient_matches_its_density() {
+		let planet = Planet::new(10.0, 2.0, 0);
+		let epsilon = 0.01;
+		for position in [
+			Vec3::new(10.0, 0.0, 0.0),
+			Vec3::new(3.0, -7.0, 5.5),
+			Vec3::new(-0.4, 11.2, 2.3),
+			Vec3::new(-6.0, -6.0, -6.0),
+		] {
+			let sampled = Vec3::new(
+				planet.sample(position + Vec3::X * epsilon)
+					- planet.sample(position - Vec3::X * epsilon),
+				planet.sample(position + Vec3::Y * epsilon)
+					- planet.sample(position - Vec3::Y * epsilon),
+				planet.sample(position + Vec3::Z * epsilon)
+					- planet.sample(position - Vec3::Z * epsilon),
+			) / (2.0 * epsilon);
+			assert!(planet.gradient(position).distance(sampled) < 0.01);
+		}
+	}
+
+	#[test]
+	fn planets_have_hills() {
+		let planet = Planet::new(10.0, 2.0, 0);
+		let heights = [
+			Vec3::X,
+			Vec3::Y,
+			Vec3::Z,
+			Vec3::NEG_X,
+			Vec3::NEG_Y,
+			Vec3::NEG_Z,
+		]
+		.map(|direction| planet.height_at(direction));
+		assert!(heights.iter().all(|height| (8.0..=12.0).contains(height)));
+		assert!(heights.iter().any(|height| *height != heights[0]));
+	}
+}
@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+
+use bevy::math::{IVec3, Vec3};
+use bevy::prelude::*;
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+
+use crate::ChunkSettings;
+
+/// A sphere of density to add to or carve out of any [`Terrain`](crate::Terrain) it touches.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct Brush {
+	pub center: Vec3,
+	pub radius: f32,
+	/// How much density is added at the center, fading out to nothing at the edge. Negative digs.
+	pub strength: f32,
+}
+
+impl Brush {
+	pub fn dig(center: Vec3, radius: f32, strength: f32) -> Self {
+		Self {
+			center,
+			radius,
+			strength: -strength,
+		}
+	}
+
+	pub fn build(center: Vec3, radius: f32, strength: f32) -> Self {
+		Self {
+			center,
+			radius,
+			strength,
+		}
+	}
+
+	fn density_at(&self, position: Vec3) -> f32 {
+		let distance = position.distance(self.center);
+		if distance >= self.radius {
+			0.0
+		} else {
+			self.strength * (1.0 - distance / self.radius)
+		}
+	}
+}
+
+/// Deforms every terrain the [`Brush`], in world space, touches.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct DeformTerrain(pub Brush);
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DeformTerrainSet;
+
+/// Everything that's been dug out of or built onto a [`Terrain`](crate::Terrain), on top of its
+/// density field.
+///
+/// Edits are kept per sample, so they only make sense with the [`ChunkSettings`] they were made
+/// with. Insert saved edits alongside the terrain to have them show up when it's first meshed.
+#[derive(Component, Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct TerrainEdits {
+	densities: HashMap<IVec3, f32>,
+}
+
+impl TerrainEdits {
+	pub fn is_empty(&self) -> bool {
+		self.densities.is_empty()
+	}
+
+	/// How much density has been added to a sample.
+	pub fn get(&self, sample: IVec3) -> f32 {
+		self.densities.get(&sample).copied().unwrap_or(0.0)
+	}
+
+	/// How much density has been added anywhere in terrain space, blending between samples.
+	pub fn density_at(&self, settings: &ChunkSettings, position: Vec3) -> f32 {
+		if self.is_empty() {
+			return 0.0;
+		}
+
+		let position = position / settings.voxel_size();
+		let base = position.floor();
+		let t = position - base;
+		let base = base.as_ivec3();
+
+		let mut density = 0.0;
+		for corner in 0..8 {
+			let offset = IVec3::new(corner & 1, (corner >> 1) & 1, (corner >> 2) & 1);
+			let weight = Vec3::select(offset.cmpeq(IVec3::ONE), t, Vec3::ONE - t);
+			density += self.get(base + offset) * weight.element_product();
+		}
+		density
+	}
+
+	/// Which way edits make density increase fastest, for normals.
+	pub fn gradient_at(&self, settings: &ChunkSettings, position: Vec3) -> Vec3 {
+		if self.is_empty() {
+			return Vec3::ZERO;
+		}
+
+		let epsilon = settings.voxel_size() * 0.5;
+		let dx = self.density_at(settings, position + Vec3::X * epsilon)
+			- self.density_at(settings, position - Vec3::X * epsilon);
+		let dy = self.density_at(settings, position + Vec3::Y * epsilon)
+			- self.density_at(settings, position - Vec3::Y * epsilon);
+		let dz = self.density_at(settings, position + Vec3::Z * epsilon)
+			- self.density_at(settings, position - Vec3::Z * epsilon);
+		Vec3::new(dx, dy, dz) / (2.0 * epsilon)
+	}
+
+	/// Paints a brush in terrain space, returning the chunks that need re-meshing.
+	pub fn apply(&mut self, settings: &ChunkSettings, brush: &Brush) -> Vec<IVec3> {
+		let voxel_size = settings.voxel_size();
+		let min_sample = ((brush.center - brush.radius) / voxel_size)
+			.floor()
+			.as_ivec3();
+		let max_sample = ((brush.center + brush.radius) / voxel_size)
+			.ceil()
+			.as_ivec3();
+
+		let mut changed = false;
+		for x in min_sample.x..=max_sample.x {
+			for y in min_sample.y..=max_sample.y {
+				for z in min_sample.z..=max_sample.z {
+					let sample = IVec3::new(x, y, z);
+					let density = brush.density_at(settings.sample_position(sample));
+					if density != 0.0 {
+						*self.densities.entry(sample).or_default() += density;
+						changed = true;
+					}
+				}
+			}
+		}
+
+		if !changed {
+			return Vec::new();
+		}
+
+		// Normals blend in samples one past a chunk's corners
+		let min_chunk = (min_sample - 2).div_euclid(IVec3::splat(settings.voxels_per_chunk));
+		let max_chunk = (max_sample + 1).div_euclid(IVec3::splat(settings.voxels_per_chunk));
+		(min_chunk.x..=max_chunk.x)
+			.flat_map(|x| {
+				(min_chunk.y..=max_chunk.y).flat_map(move |y| {
+					(min_chunk.z..=max_chunk.z).map(move |z| IVec3::new(x, y, z))
+				})
+			})
+			.collect()
+	}
+
+	/// Just the edits that a chunk samples or blends into its normals, to send off with it while
+	/// it's meshed.
+	pub fn for_chunk(&self, settings: &ChunkSettings, chunk: IVec3) -> Self {
+		let min_sample = chunk * settings.voxels_per_chunk - IVec3::ONE;
+		let max_sample = min_sample + IVec3::splat(settings.voxels_per_chunk + 2);
+		Self {
+			densities: self
+				.densities
+				.iter()
+				.filter(|(sample, _)| {
+					sample.cmpge(min_sample).all() && sample.cmple(max_sample).all()
+				})
+				.map(|(sample, density)| (*sample, *density))
+				.collect(),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn digging_only_touches_nearby_chunks() {
+		let settings = ChunkSettings {
+			chunk_size: 8.0,
+			voxels_per_chunk: 8,
+		};
+		let mut edits = TerrainEdits::default();
+		let chunks = edits.apply(&settings, &Brush::dig(Vec3::splat(4.0), 1.5, 1.0));
+
+		assert_eq!(chunks, vec![IVec3::ZERO]);
+		assert_eq!(edits.get(IVec3::splat(4)), -1.0);
+		assert_eq!(edits.get(IVec3::splat(8)), 0.0);
+	}
+
+	#[test]
+	fn digging_on_a_border_touches_both_chunks() {
+		let settings = ChunkSettings {
+			chunk_size: 8.0,
+			voxels_per_chunk: 8,
+		};
+		let mut edits = TerrainEdits::default();
+		let chunks = edits.apply(&settings, &Brush::dig(Vec3::new(8.0, 4.0, 4.0), 1.5, 1.0));
+
+		assert_eq!(chunks, vec![IVec3::ZERO, IVec3::X]);
+		assert!(
+			edits
+				.for_chunk(&settings, IVec3::ZERO)
+				.get(IVec3::new(8, 4, 4))
+				< 0.0
+		);
+		assert!(
+			edits
+				.for_chunk(&settings, IVec3::X)
+				.get(IVec3::new(8, 4, 4))
+				< 0.0
+		);
+		assert!(edits.for_chunk(&settings, IVec3::Y).is_empty());
+	}
+}
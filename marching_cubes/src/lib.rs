@@ -0,0 +1,12 @@
+// The edge interpolation in `Marcher::create_vertex` and the tipped box in `RotatedBox` started
+// out as https://github.com/SebLague/Terraforming, the rest is our own
+
+pub use density::*;
+pub use edits::*;
+pub use marching_cubes::*;
+pub use terrain::*;
+
+pub mod density;
+pub mod edits;
+mod marching_cubes;
+pub mod terrain;
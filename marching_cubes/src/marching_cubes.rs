@@ -1,20 +1,26 @@
-use std::f32::consts::PI;
-
-use bevy::math::{IVec2, IVec3, Quat, Vec3};
-
-use crate::march_tables::*;
-
-const SURFACE_THRESHOLD: f32 = 0.5;
-pub const NUM_SAMPLES: i32 = 32;
-pub const NUM_VOXELS: i32 = NUM_SAMPLES - 1;
-
-pub static CHUNK_COORD: IVec3 = IVec3::new(0, 0, 0);
+use bevy::math::{IVec3, Vec3};
+use bevy::render::mesh::{Indices, Mesh, PrimitiveTopology};
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::utils::HashMap;
+
+use crate::density::DensityField;
+use crate::edits::TerrainEdits;
+
+/// Density above this is solid, and below is empty.
+pub const SURFACE_THRESHOLD: f32 = 0.5;
+
+/// Which vertex this is, the same in every chunk that makes it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VertexId {
+	/// Where the surface crosses the edge between two samples, lowest sample first.
+	Edge(IVec3, IVec3),
+}
 
 #[derive(Debug, Clone)]
 pub struct Vertex {
 	pub position: Vec3,
 	pub normal: Vec3,
-	pub id: IVec2,
+	pub id: VertexId,
 }
 
 #[derive(Debug, Clone)]
@@ -24,145 +30,591 @@ pub struct Triangle {
 	pub vertex_c: Vertex,
 }
 
-fn coord_to_world(coord: IVec3) -> Vec3 {
-	// (coord / (textureSize - 1.0) - 0.5f) * planetSize
-	coord.as_vec3()
+/// How finely to chop up space into chunks and voxels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChunkSettings {
+	/// How long each chunk is along each side.
+	pub chunk_size: f32,
+	/// How many voxels fit along each side of a chunk at full detail.
+	///
+	/// Each level of detail halves this, so powers of two get the most levels.
+	pub voxels_per_chunk: i32,
 }
 
-fn index_from_coord(coord: IVec3) -> usize {
-	let coord = coord - CHUNK_COORD;
-	(coord.z * NUM_SAMPLES * NUM_SAMPLES + coord.y * NUM_SAMPLES + coord.x) as usize
+impl Default for ChunkSettings {
+	fn default() -> Self {
+		Self {
+			chunk_size: 16.0,
+			voxels_per_chunk: 32,
+		}
+	}
 }
 
-fn sample_density(position: IVec3) -> f32 {
-	let center = CHUNK_COORD.as_vec3() + Vec3::splat(NUM_SAMPLES as f32 / 2.0);
-
-	let position = position.as_vec3() - center;
-	let position = Quat::from_axis_angle(Vec3::new(1., 1., 1.), PI / 3.0) * position;
+impl ChunkSettings {
+	pub fn voxel_size(&self) -> f32 {
+		self.chunk_size / self.voxels_per_chunk as f32
+	}
 
-	let dimensions = Vec3::new(9.5, 20.5, 24.0);
-	let half_dimensions = dimensions / 2.0;
+	/// Where a sample lives in terrain space.
+	///
+	/// Samples are counted across the whole terrain rather than per chunk, so neighbouring chunks
+	/// land on exactly the same positions along their shared border.
+	pub fn sample_position(&self, sample: IVec3) -> Vec3 {
+		sample.as_vec3() * self.voxel_size()
+	}
 
-	let distance = position.abs();
-	let normalized_distance = distance / half_dimensions;
-	let max_distance = normalized_distance.max_element();
+	/// The chunk that a terrain-space position is in.
+	pub fn chunk_at(&self, position: Vec3) -> IVec3 {
+		(position / self.chunk_size).floor().as_ivec3()
+	}
 
-	1.0 - max_distance
+	/// The coarsest level of detail that still fits a whole number of voxels in a chunk.
+	pub fn max_lod(&self) -> u32 {
+		self.voxels_per_chunk.trailing_zeros()
+	}
 }
 
-fn calculate_normal(coord: IVec3) -> Vec3 {
-	let offset_x = IVec3::new(1, 0, 0);
-	let offset_y = IVec3::new(0, 1, 0);
-	let offset_z = IVec3::new(0, 0, 1);
+/// How detailed each of the 26 chunks around a chunk is.
+///
+/// Wherever a chunk touches more detailed neighbours, the faces, edges and corners they share get
+/// sampled at the neighbours' detail instead, so both sides put the surface in exactly the same
+/// place. Missing neighbours don't count.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NeighbourLods([Option<u32>; 27]);
+
+impl NeighbourLods {
+	/// Looks up the chunk at every offset from `-1` to `1` along each axis.
+	pub fn new(mut lod_at: impl FnMut(IVec3) -> Option<u32>) -> Self {
+		let mut lods = [None; 27];
+		for (index, lod) in lods.iter_mut().enumerate() {
+			let index = index as i32;
+			let offset = IVec3::new(index % 3, index / 3 % 3, index / 9) - 1;
+			if offset != IVec3::ZERO {
+				*lod = lod_at(offset);
+			}
+		}
+		Self(lods)
+	}
+
+	pub fn get(&self, offset: IVec3) -> Option<u32> {
+		self.0[(offset + 1).dot(IVec3::new(1, 3, 9)) as usize]
+	}
+
+	/// The most detailed neighbour's level of detail, if there are any.
+	pub fn finest(&self) -> Option<u32> {
+		self.0.iter().flatten().copied().min()
+	}
+}
 
-	let dx = sample_density(coord + offset_x) - sample_density(coord - offset_x);
-	let dy = sample_density(coord + offset_y) - sample_density(coord - offset_y);
-	let dz = sample_density(coord + offset_z) - sample_density(coord - offset_z);
+/// Which way is out of the surface at any point, edits included.
+///
+/// This works from the position alone, so chunks agree on normals along their borders no matter
+/// how detailed each of them is.
+pub fn surface_normal(
+	field: &dyn DensityField,
+	edits: &TerrainEdits,
+	settings: &ChunkSettings,
+	position: Vec3,
+) -> Vec3 {
+	-(field.gradient(position) + edits.gradient_at(settings, position)).normalize_or_zero()
+}
 
-	Vec3::new(dx, dy, dz).normalize()
+/// Every density a chunk needs, sampled once up front.
+struct DensityGrid {
+	/// The sample at the grid's `(0, 0, 0)`.
+	origin: IVec3,
+	/// How many samples apart each grid point is.
+	stride: i32,
+	size: i32,
+	densities: Vec<f32>,
 }
 
-// Calculate the position of the vertex
-// The position lies somewhere along the edge defined by the two corner points.
-// Where exactly along the edge is determined by the values of each corner point.
-fn create_vertex(coord_a: IVec3, coord_b: IVec3) -> Vertex {
-	let pos_a = coord_to_world(coord_a);
-	let pos_b = coord_to_world(coord_b);
-	let density_a = sample_density(coord_a);
-	let density_b = sample_density(coord_b);
-
-	// Interpolate between the two corner points based on the density
-	let t = (SURFACE_THRESHOLD - density_a) / (density_b - density_a);
-	let position = pos_a + t * (pos_b - pos_a);
-
-	// Normal:
-	let normal_a = calculate_normal(coord_a);
-	let normal_b = calculate_normal(coord_b);
-	let normal = (normal_a + t * (normal_b - normal_a)).normalize();
-
-	// ID
-	let index_a = index_from_coord(coord_a);
-	let index_b = index_from_coord(coord_b);
-
-	// Create vertex
-	Vertex {
-		position,
-		normal,
-		id: IVec2::new(index_a.min(index_b) as i32, index_a.max(index_b) as i32),
+impl DensityGrid {
+	fn sample(
+		field: &dyn DensityField,
+		edits: &TerrainEdits,
+		settings: &ChunkSettings,
+		chunk: IVec3,
+		lod: u32,
+	) -> Self {
+		let origin = chunk * settings.voxels_per_chunk;
+		let stride = 1 << lod;
+		// One sample per voxel corner
+		let size = settings.voxels_per_chunk / stride + 1;
+
+		let mut densities = Vec::with_capacity((size * size * size) as usize);
+		for z in 0..size {
+			for y in 0..size {
+				for x in 0..size {
+					let sample = origin + IVec3::new(x, y, z) * stride;
+					let position = settings.sample_position(sample);
+					densities.push(field.sample(position) + edits.get(sample));
+				}
+			}
+		}
+
+		Self {
+			origin,
+			stride,
+			size,
+			densities,
+		}
+	}
+
+	fn sample_from_coord(&self, coord: IVec3) -> IVec3 {
+		self.origin + coord * self.stride
+	}
+
+	fn density(&self, coord: IVec3) -> f32 {
+		self.densities[(coord.z * self.size * self.size + coord.y * self.size + coord.x) as usize]
+	}
+
+	/// The density at a sample, if it's one of the grid's.
+	fn get(&self, sample: IVec3) -> Option<f32> {
+		let offset = sample - self.origin;
+		let coord = offset / self.stride;
+		let in_grid = offset % self.stride == IVec3::ZERO
+			&& coord.cmpge(IVec3::ZERO).all()
+			&& coord.cmplt(IVec3::splat(self.size)).all();
+		in_grid.then(|| self.density(coord))
 	}
 }
 
-pub fn process_cube(id: IVec3) -> Vec<Triangle> {
-	let mut triangles = Vec::new();
+struct Marcher<'a> {
+	field: &'a dyn DensityField,
+	edits: &'a TerrainEdits,
+	settings: &'a ChunkSettings,
+	neighbours: &'a NeighbourLods,
+	grid: DensityGrid,
+	/// Samples in between the grid's, along borders shared with more detailed chunks.
+	border_densities: HashMap<IVec3, f32>,
+	/// Whether any neighbour is more detailed, so the border needs more than the grid.
+	detailed_border: bool,
+}
+
+impl Marcher<'_> {
+	fn density(&mut self, sample: IVec3) -> f32 {
+		if let Some(density) = self.grid.get(sample) {
+			return density;
+		}
+		let (field, edits, settings) = (self.field, self.edits, self.settings);
+		*self
+			.border_densities
+			.entry(sample)
+			.or_insert_with(|| field.sample(settings.sample_position(sample)) + edits.get(sample))
+	}
 
-	if id.x >= NUM_VOXELS || id.y >= NUM_VOXELS || id.z >= NUM_VOXELS {
-		return triangles;
-	}
-
-	let coord = id + CHUNK_COORD;
-
-	// Calculate coordinates of each corner of the current cube
-	let corner_coords = [
-		coord + IVec3::new(0, 0, 0),
-		coord + IVec3::new(1, 0, 0),
-		coord + IVec3::new(1, 0, 1),
-		coord + IVec3::new(0, 0, 1),
-		coord + IVec3::new(0, 1, 0),
-		coord + IVec3::new(1, 1, 0),
-		coord + IVec3::new(1, 1, 1),
-		coord + IVec3::new(0, 1, 1),
-	];
-	// Calculate unique index for each cube configuration.
-	// There are 256 possible values (cube has 8 corners, so 2^8 possibilites).
-	// A value of 0 means cube is entirely inside the surface; 255 entirely outside.
-	// The value is used to look up the edge table, which indicates which edges of the cube the surface passes through.
-	let mut cube_configuration = 0;
-	for (i, corner_coord) in corner_coords.iter().enumerate() {
-		// Think of the configuration as an 8-bit binary number (each bit represents the state of a corner point).
-		// The state of each corner point is either 0: above the surface, or 1: below the surface.
-		// The code below sets the corresponding bit to 1, if the point is below the surface.
-		if sample_density(*corner_coord) < SURFACE_THRESHOLD {
-			cube_configuration |= 1 << i;
-		}
-	}
-
-	// Get array of the edges of the cube that the surface passes through.
-	let edge_indices = TRIANGULATION[cube_configuration];
-
-	// Create triangles for the current cube configuration
-	for i in (0..16).step_by(3) {
-		// If edge index is -1, then no further vertices exist in this configuration
-		if edge_indices[i] == -1 {
-			break;
-		}
-
-		// Get indices of the two corner points defining the edge that the surface passes through.
-		// (Do this for each of the three edges we're currently looking at).
-		let edge_index_a = edge_indices[i] as usize;
-		let a0 = CORNER_INDEX_A_FROM_EDGE[edge_index_a];
-		let a1 = CORNER_INDEX_B_FROM_EDGE[edge_index_a];
-
-		let edge_index_b = edge_indices[i + 1] as usize;
-		let b0 = CORNER_INDEX_A_FROM_EDGE[edge_index_b];
-		let b1 = CORNER_INDEX_B_FROM_EDGE[edge_index_b];
-
-		let edge_index_c = edge_indices[i + 2] as usize;
-		let c0 = CORNER_INDEX_A_FROM_EDGE[edge_index_c];
-		let c1 = CORNER_INDEX_B_FROM_EDGE[edge_index_c];
-
-		// Calculate positions of each vertex.
-		let vertex_a = create_vertex(corner_coords[a0], corner_coords[a1]);
-		let vertex_b = create_vertex(corner_coords[b0], corner_coords[b1]);
-		let vertex_c = create_vertex(corner_coords[c0], corner_coords[c1]);
-
-		// Create triangle
-		let tri = Triangle {
-			vertex_a,
-			vertex_b,
-			vertex_c,
+	/// How many samples apart to go along the part of a voxel's outside running from `min` to
+	/// `max`.
+	///
+	/// Anything lying flat on the chunk's border is shared with the chunks on the other side, and
+	/// gets as much detail as the most detailed of them.
+	fn stride_between(&self, min: IVec3, max: IVec3) -> i32 {
+		let chunk_min = self.grid.origin;
+		let chunk_max = chunk_min + self.settings.voxels_per_chunk;
+		// Which way the chunks sharing this lie on each axis
+		let side = IVec3::select(
+			min.cmpne(max),
+			IVec3::ZERO,
+			IVec3::select(
+				min.cmpeq(chunk_min),
+				IVec3::NEG_ONE,
+				IVec3::select(min.cmpeq(chunk_max), IVec3::ONE, IVec3::ZERO),
+			),
+		);
+
+		let mut stride = self.grid.stride;
+		for x in 0..=side.x.abs() {
+			for y in 0..=side.y.abs() {
+				for z in 0..=side.z.abs() {
+					if let Some(lod) = self.neighbours.get(IVec3::new(x, y, z) * side) {
+						stride = stride.min(1 << lod.min(self.settings.max_lod()));
+					}
+				}
+			}
+		}
+		stride
+	}
+
+	/// The outside of the voxel whose lowest corner is `min`, as polygons of samples wound
+	/// anticlockwise when seen from outside.
+	///
+	/// Each face is a single square unless it's on a border with a more detailed chunk, where it's
+	/// split into the neighbour's squares. Sides get an extra sample wherever whatever's across them
+	/// has one, so every polygon lines up exactly with those of the voxels around it.
+	fn voxel_faces(&self, min: IVec3) -> Vec<Vec<IVec3>> {
+		let size = self.grid.stride;
+		let mut polygons = Vec::new();
+		// The corner each face starts from and the two directions around it, which cross to point
+		// out of the voxel
+		for (start, u, v) in [
+			(IVec3::ZERO, IVec3::Z, IVec3::Y),
+			(IVec3::X, IVec3::Y, IVec3::Z),
+			(IVec3::ZERO, IVec3::X, IVec3::Z),
+			(IVec3::Y, IVec3::Z, IVec3::X),
+			(IVec3::ZERO, IVec3::Y, IVec3::X),
+			(IVec3::Z, IVec3::X, IVec3::Y),
+		] {
+			let start = min + start * size;
+			let stride = self.stride_between(start, start + (u + v) * size);
+			for i in 0..size / stride {
+				for j in 0..size / stride {
+					let corner = start + (u * i + v * j) * stride;
+					let corners = [
+						corner,
+						corner + u * stride,
+						corner + (u + v) * stride,
+						corner + v * stride,
+					];
+
+					let mut polygon = Vec::with_capacity(4);
+					for (from, to) in corners.into_iter().zip(corners.into_iter().cycle().skip(1)) {
+						let step = self.stride_between(from.min(to), from.max(to));
+						let direction = (to - from) / stride;
+						polygon.extend((0..stride / step).map(|k| from + direction * step * k));
+					}
+					polygons.push(polygon);
+				}
+			}
+		}
+		polygons
+	}
+
+	// Calculate the position of the vertex
+	// The position lies somewhere along the edge defined by the two corner points.
+	// Where exactly along the edge is determined by the values of each corner point.
+	fn create_vertex(&mut self, sample_a: IVec3, sample_b: IVec3) -> Vertex {
+		// Neighbouring voxels walk some edges in opposite directions, so always interpolate the same
+		// way to get the exact same vertex out of both
+		let (sample_a, sample_b) = if sample_a.to_array() < sample_b.to_array() {
+			(sample_a, sample_b)
+		} else {
+			(sample_b, sample_a)
 		};
-		triangles.push(tri);
+
+		let pos_a = self.settings.sample_position(sample_a);
+		let pos_b = self.settings.sample_position(sample_b);
+		let density_a = self.density(sample_a);
+		let density_b = self.density(sample_b);
+
+		// Interpolate between the two corner points based on the density
+		let t = (SURFACE_THRESHOLD - density_a) / (density_b - density_a);
+		let position = pos_a + t * (pos_b - pos_a);
+
+		// Create vertex
+		Vertex {
+			position,
+			normal: surface_normal(self.field, self.edits, self.settings, position),
+			id: VertexId::Edge(sample_a, sample_b),
+		}
+	}
+
+	fn process_voxel(&mut self, coord: IVec3) -> Vec<Triangle> {
+		let on_border =
+			coord.cmpeq(IVec3::ZERO).any() || coord.cmpeq(IVec3::splat(self.grid.size - 2)).any();
+		if !(on_border && self.detailed_border) {
+			// Nothing to do if the whole voxel is on one side of the surface
+			let corner_solid = |corner: i32| {
+				let offset = IVec3::new(corner & 1, (corner >> 1) & 1, (corner >> 2) & 1);
+				self.grid.density(coord + offset) >= SURFACE_THRESHOLD
+			};
+			let solid = corner_solid(0);
+			if (1..8).all(|corner| corner_solid(corner) == solid) {
+				return Vec::new();
+			}
+		}
+
+		// Walking anticlockwise round each face, the surface crosses it from wherever it leaves solid
+		// ground to wherever it comes back. Every stretch of empty samples gets its own crossing, so
+		// the voxels on either side of a face always agree on how its corners connect.
+		let mut vertices: Vec<Vertex> = Vec::new();
+		let mut crossings: Vec<(usize, usize)> = Vec::new();
+		for polygon in self.voxel_faces(self.grid.sample_from_coord(coord)) {
+			let solid = polygon
+				.iter()
+				.map(|sample| self.density(*sample) >= SURFACE_THRESHOLD)
+				.collect::<Vec<_>>();
+			let sides = polygon.len();
+			let leaves_solid = |i: usize| solid[i] && !solid[(i + 1) % sides];
+			let reaches_solid = |i: usize| !solid[i] && solid[(i + 1) % sides];
+
+			for start in (0..sides).filter(|i| leaves_solid(*i)) {
+				let end = (start + 1..start + sides)
+					.map(|i| i % sides)
+					.find(|i| reaches_solid(*i))
+					.expect("Surfaces that leave solid ground come back to it");
+
+				let mut vertex_on = |side: usize| {
+					let (a, b) = (polygon[side], polygon[(side + 1) % sides]);
+					let id = VertexId::Edge(a.min(b), a.max(b));
+					if let Some(index) = vertices.iter().position(|vertex| vertex.id == id) {
+						return index;
+					}
+					vertices.push(self.create_vertex(a, b));
+					vertices.len() - 1
+				};
+				crossings.push((vertex_on(start), vertex_on(end)));
+			}
+		}
+
+		// The crossings chain into loops around the voxel, which get fanned out into triangles
+		let mut triangles = Vec::new();
+		while let Some((first, mut next)) = crossings.pop() {
+			let mut surface = vec![first];
+			while next != first {
+				let Some(index) = crossings.iter().position(|(from, _)| *from == next) else {
+					break;
+				};
+				surface.push(next);
+				next = crossings.swap_remove(index).1;
+			}
+
+			for pair in surface[1..].windows(2) {
+				triangles.push(Triangle {
+					vertex_a: vertices[first].clone(),
+					vertex_b: vertices[pair[0]].clone(),
+					vertex_c: vertices[pair[1]].clone(),
+				});
+			}
+		}
+		triangles
 	}
+}
+
+/// Marches through every voxel in a chunk, with vertices in terrain space.
+///
+/// Each level of detail doubles the size of the voxels, with `0` being full detail. It's clamped
+/// to [`ChunkSettings::max_lod`]. Borders shared with more detailed `neighbours` are meshed at
+/// their detail so the chunks meet without cracks.
+pub fn march_chunk(
+	field: &dyn DensityField,
+	edits: &TerrainEdits,
+	settings: &ChunkSettings,
+	chunk: IVec3,
+	lod: u32,
+	neighbours: &NeighbourLods,
+) -> Vec<Triangle> {
+	let lod = lod.min(settings.max_lod());
+	let mut marcher = Marcher {
+		field,
+		edits,
+		settings,
+		neighbours,
+		grid: DensityGrid::sample(field, edits, settings, chunk, lod),
+		border_densities: HashMap::new(),
+		detailed_border: neighbours
+			.finest()
+			.is_some_and(|finest| finest.min(settings.max_lod()) < lod),
+	};
+	let voxels = marcher.grid.size - 1;
 
+	let mut triangles = Vec::new();
+	for x in 0..voxels {
+		for y in 0..voxels {
+			for z in 0..voxels {
+				triangles.extend(marcher.process_voxel(IVec3::new(x, y, z)));
+			}
+		}
+	}
 	triangles
 }
+
+/// Turns triangles into a mesh, sharing vertices between triangles that meet.
+///
+/// Returns [`None`] if there's nothing to draw.
+pub fn triangles_to_mesh(triangles: &[Triangle]) -> Option<Mesh> {
+	if triangles.is_empty() {
+		return None;
+	}
+
+	let vertex_data = triangles
+		.iter()
+		.flat_map(|t| [&t.vertex_c, &t.vertex_b, &t.vertex_a]);
+
+	let mut vertex_positions: Vec<Vec3> = vec![];
+	let mut vertex_normals: Vec<Vec3> = vec![];
+	let mut indices: Vec<u32> = vec![];
+	let mut vertex_index_map: HashMap<VertexId, u32> = HashMap::new();
+
+	let mut vertex_index = 0;
+	for data in vertex_data {
+		if let Some(shared_vertex_index) = vertex_index_map.get(&data.id) {
+			indices.push(*shared_vertex_index);
+		} else {
+			vertex_index_map.insert(data.id, vertex_index);
+			vertex_positions.push(data.position);
+			vertex_normals.push(data.normal);
+			indices.push(vertex_index);
+			vertex_index += 1;
+		}
+	}
+
+	Some(
+		Mesh::new(
+			PrimitiveTopology::TriangleList,
+			RenderAssetUsages::RENDER_WORLD | RenderAssetUsages::MAIN_WORLD,
+		)
+		.with_inserted_indices(Indices::U32(indices))
+		.with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, vertex_positions)
+		.with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, vertex_normals),
+	)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const SETTINGS: ChunkSettings = ChunkSettings {
+		chunk_size: 8.0,
+		voxels_per_chunk: 8,
+	};
+
+	fn ball(position: Vec3) -> f32 {
+		SURFACE_THRESHOLD + 10.0 - position.length()
+	}
+
+	fn march_ball(chunk: IVec3, lod: u32) -> Vec<Triangle> {
+		march_chunk(
+			&ball,
+			&TerrainEdits::default(),
+			&SETTINGS,
+			chunk,
+			lod,
+			&NeighbourLods::default(),
+		)
+	}
+
+	/// Marches a chunk next to a neighbour one chunk along `offset` at another level of detail.
+	fn march_beside(
+		field: &dyn DensityField,
+		chunk: IVec3,
+		lod: u32,
+		offset: IVec3,
+		neighbour_lod: u32,
+	) -> Vec<Triangle> {
+		let neighbours = NeighbourLods::new(|at| (at == offset).then_some(neighbour_lod));
+		march_chunk(
+			field,
+			&TerrainEdits::default(),
+			&SETTINGS,
+			chunk,
+			lod,
+			&neighbours,
+		)
+	}
+
+	/// Vertices on edges that lie along `x = 0`.
+	fn border_vertices(triangles: &[Triangle]) -> Vec<(VertexId, Vec3, Vec3)> {
+		let mut vertices = triangles
+			.iter()
+			.flat_map(|t| [&t.vertex_a, &t.vertex_b, &t.vertex_c])
+			.filter(|vertex| matches!(vertex.id, VertexId::Edge(a, b) if a.x == 0 && b.x == 0))
+			.map(|vertex| (vertex.id, vertex.position, vertex.normal))
+			.collect::<Vec<_>>();
+		vertices.sort_by(|a, b| a.1.to_array().partial_cmp(&b.1.to_array()).unwrap());
+		vertices.dedup_by_key(|vertex| vertex.0);
+		vertices
+	}
+
+	#[test]
+	fn empty_space_has_no_mesh() {
+		let triangles = march_chunk(
+			&|_| 0.0,
+			&TerrainEdits::default(),
+			&ChunkSettings::default(),
+			IVec3::ZERO,
+			0,
+			&NeighbourLods::default(),
+		);
+		assert!(triangles.is_empty());
+		assert!(triangles_to_mesh(&triangles).is_none());
+	}
+
+	#[test]
+	fn neighbouring_chunks_share_border_vertices() {
+		let left = march_ball(IVec3::new(-1, 0, 0), 0);
+		let right = march_ball(IVec3::new(0, 0, 0), 0);
+
+		let left_border = border_vertices(&left);
+		assert!(!left_border.is_empty());
+		assert_eq!(left_border, border_vertices(&right));
+	}
+
+	#[test]
+	fn normals_point_out_of_the_ball() {
+		let triangles = march_chunk(
+			&ball,
+			&TerrainEdits::default(),
+			&ChunkSettings::default(),
+			IVec3::ZERO,
+			0,
+			&NeighbourLods::default(),
+		);
+		assert!(!triangles.is_empty());
+		for vertex in triangles.iter().map(|t| &t.vertex_a) {
+			assert!(vertex.normal.dot(vertex.position.normalize()) > 0.99);
+		}
+	}
+
+	#[test]
+	fn less_detail_means_fewer_triangles() {
+		let full = march_ball(IVec3::ZERO, 0).len();
+		let half = march_ball(IVec3::ZERO, 1).len();
+		assert!(half > 0);
+		assert!(half < full);
+		assert_eq!(
+			march_ball(IVec3::ZERO, SETTINGS.max_lod() + 1).len(),
+			march_ball(IVec3::ZERO, SETTINGS.max_lod()).len()
+		);
+	}
+
+	#[test]
+	fn triangles_face_out_of_the_ball() {
+		for triangle in march_ball(IVec3::ZERO, 0) {
+			let a = triangle.vertex_a.position;
+			let b = triangle.vertex_b.position;
+			let c = triangle.vertex_c.position;
+			// Meshes wind them the other way round
+			assert!((b - c).cross(a - c).dot(a) >= 0.0);
+		}
+	}
+
+	#[test]
+	fn borders_match_more_detailed_neighbours() {
+		for lod in 1..=SETTINGS.max_lod() {
+			let detailed = march_beside(&ball, IVec3::new(-1, 0, 0), 0, IVec3::X, lod);
+			let coarse = march_beside(&ball, IVec3::ZERO, lod, IVec3::NEG_X, 0);
+
+			let detailed_border = border_vertices(&detailed);
+			assert!(!detailed_border.is_empty());
+			assert_eq!(detailed_border, border_vertices(&coarse));
+		}
+	}
+
+	#[test]
+	fn chunks_at_different_detail_meet_without_cracks() {
+		// Small enough to fit in the two chunks either side of `x = 0`
+		let pebble =
+			|position: Vec3| SURFACE_THRESHOLD + 3.0 - position.distance(Vec3::new(0.0, 4.0, 4.0));
+
+		for lod in 1..=SETTINGS.max_lod() {
+			let mut triangles = march_beside(&pebble, IVec3::new(-1, 0, 0), 0, IVec3::X, lod);
+			triangles.extend(march_beside(&pebble, IVec3::ZERO, lod, IVec3::NEG_X, 0));
+
+			// Every edge of a closed surface is walked once each way
+			let mut edges = HashMap::new();
+			for triangle in triangles.iter() {
+				let [a, b, c] = [&triangle.vertex_a, &triangle.vertex_b, &triangle.vertex_c]
+					.map(|vertex| vertex.id);
+				for edge in [(a, b), (b, c), (c, a)] {
+					*edges.entry(edge).or_insert(0) += 1;
+				}
+			}
+			assert!(!edges.is_empty());
+			for ((from, to), count) in edges.iter() {
+				assert_eq!(*count, 1);
+				assert_eq!(edges.get(&(*to, *from)), Some(&1));
+			}
+		}
+	}
+}
@@ -0,0 +1,489 @@
+use std::sync::Arc;
+
+use bevy::prelude::*;
+use bevy::tasks::{block_on, poll_once, AsyncComputeTaskPool, Task};
+use bevy::utils::HashMap;
+#[cfg(feature = "rapier")]
+use bevy_rapier3d::prelude::*;
+
+#[cfg(test)]
+use crate::Planet;
+use crate::{
+	march_chunk, triangles_to_mesh, ChunkSettings, DeformTerrain, DeformTerrainSet, DensityField,
+	NeighbourLods, TerrainEdits,
+};
+
+/// Meshes every [`Terrain`] in the background, a chunk at a time.
+pub struct MarchingCubesPlugin;
+
+impl Plugin for MarchingCubesPlugin {
+	fn build(&self, app: &mut App) {
+		app.add_event::<ChunkGenerated>()
+			.add_event::<DeformTerrain>()
+			.add_systems(
+				Update,
+				(
+					spawn_chunks,
+					update_chunk_lods,
+					deform_terrain.in_set(DeformTerrainSet),
+					finish_chunks.in_set(ChunkGeneratedSet),
+				)
+					.chain(),
+			);
+	}
+}
+
+/// A density field turned into chunks of mesh.
+///
+/// Each chunk becomes a child entity with a [`TerrainChunk`], positioned at the terrain's origin.
+#[derive(Component, Clone)]
+#[require(Transform, Visibility, TerrainEdits)]
+pub struct Terrain {
+	pub field: Arc<dyn DensityField>,
+	pub settings: ChunkSettings,
+	/// The lowest chunk to generate on each axis.
+	pub min_chunk: IVec3,
+	/// The highest chunk to generate on each axis, inclusive.
+	pub max_chunk: IVec3,
+	pub material: Handle<StandardMaterial>,
+	/// Chunks further than each of these from the nearest [`TerrainViewer`] drop another level of
+	/// detail. Leave it empty to keep every chunk at full detail.
+	pub lod_distances: Vec<f32>,
+}
+
+impl Terrain {
+	/// Enough chunks to cover everything within `half_extent` of the terrain's origin.
+	pub fn covering(
+		field: impl DensityField,
+		settings: ChunkSettings,
+		half_extent: f32,
+		material: Handle<StandardMaterial>,
+	) -> Self {
+		Self {
+			field: Arc::new(field),
+			settings,
+			min_chunk: IVec3::splat((-half_extent / settings.chunk_size).floor() as i32),
+			max_chunk: IVec3::splat((half_extent / settings.chunk_size).ceil() as i32 - 1),
+			material,
+			lod_distances: Vec::new(),
+		}
+	}
+
+	pub fn with_lod_distances(mut self, lod_distances: impl IntoIterator<Item = f32>) -> Self {
+		self.lod_distances = lod_distances.into_iter().collect();
+		self
+	}
+
+	/// How detailed a chunk this far from the nearest viewer should be, with `0` being full detail.
+	pub fn lod_at(&self, distance: f32) -> u32 {
+		let lod = self
+			.lod_distances
+			.iter()
+			.filter(|lod_distance| distance > **lod_distance)
+			.count() as u32;
+		lod.min(self.settings.max_lod())
+	}
+
+	fn chunk_center(&self, coord: IVec3) -> Vec3 {
+		(coord.as_vec3() + 0.5) * self.settings.chunk_size
+	}
+
+	pub fn chunks(&self) -> impl Iterator<Item = IVec3> + '_ {
+		(self.min_chunk.x..=self.max_chunk.x).flat_map(move |x| {
+			(self.min_chunk.y..=self.max_chunk.y).flat_map(move |y| {
+				(self.min_chunk.z..=self.max_chunk.z).map(move |z| IVec3::new(x, y, z))
+			})
+		})
+	}
+}
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TerrainChunk {
+	pub coord: IVec3,
+	/// How much detail this chunk was last meshed with, with `0` being full detail.
+	pub lod: u32,
+}
+
+/// Something that terrain gets more detailed around, like the camera.
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct TerrainViewer;
+
+/// A chunk whose mesh is still being built on the [`AsyncComputeTaskPool`].
+#[derive(Component)]
+pub struct GeneratingChunk(Task<GeneratedChunk>);
+
+impl GeneratingChunk {
+	fn new(
+		terrain: &Terrain,
+		edits: &TerrainEdits,
+		coord: IVec3,
+		lod: u32,
+		neighbours: NeighbourLods,
+	) -> Self {
+		let field = terrain.field.clone();
+		let settings = terrain.settings;
+		let edits = edits.for_chunk(&settings, coord);
+		Self(AsyncComputeTaskPool::get().spawn(async move {
+			let triangles = march_chunk(field.as_ref(), &edits, &settings, coord, lod, &neighbours);
+			let mesh = triangles_to_mesh(&triangles);
+
+			#[cfg(feature = "rapier")]
+			let collider = mesh
+				.as_ref()
+				.and_then(|mesh| Collider::from_bevy_mesh(mesh, &ComputedColliderShape::default()));
+
+			GeneratedChunk {
+				mesh,
+				#[cfg(feature = "rapier")]
+				collider,
+			}
+		}))
+	}
+}
+
+fn neighbour_lods(lods: &HashMap<IVec3, u32>, coord: IVec3) -> NeighbourLods {
+	NeighbourLods::new(|offset| lods.get(&(coord + offset)).copied())
+}
+
+fn nearest_viewer_distance(
+	terrain: &Terrain,
+	transform: &GlobalTransform,
+	viewers: &Query<&GlobalTransform, With<TerrainViewer>>,
+	coord: IVec3,
+) -> f32 {
+	let center = transform.transform_point(terrain.chunk_center(coord));
+	viewers
+		.iter()
+		.map(|viewer| viewer.translation().distance(center))
+		.min_by(f32::total_cmp)
+		.unwrap_or(f32::INFINITY)
+}
+
+struct GeneratedChunk {
+	mesh: Option<Mesh>,
+	#[cfg(feature = "rapier")]
+	collider: Option<Collider>,
+}
+
+/// Sent when a chunk's mesh has been built and added.
+#[derive(Event)]
+pub struct ChunkGenerated {
+	pub terrain: Entity,
+	pub chunk: Entity,
+}
+
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ChunkGeneratedSet;
+
+fn spawn_chunks(
+	terrains: Query<(Entity, &Terrain, &TerrainEdits, &GlobalTransform), Added<Terrain>>,
+	viewers: Query<&GlobalTransform, With<TerrainViewer>>,
+	mut commands: Commands,
+) {
+	for (entity, terrain, edits, transform) in terrains.iter() {
+		let lods = terrain
+			.chunks()
+			.map(|coord| {
+				let distance = nearest_viewer_distance(terrain, transform, &viewers, coord);
+				(coord, terrain.lod_at(distance))
+			})
+			.collect::<HashMap<_, _>>();
+
+		for coord in terrain.chunks() {
+			let lod = lods[&coord];
+			commands.entity(entity).with_child((
+				Name::new(format!("Chunk {coord}")),
+				TerrainChunk { coord, lod },
+				GeneratingChunk::new(terrain, edits, coord, lod, neighbour_lods(&lods, coord)),
+				Transform::default(),
+				Visibility::default(),
+			));
+		}
+	}
+}
+
+fn update_chunk_lods(
+	terrains: Query<(&Terrain, &TerrainEdits, &GlobalTransform, &Children)>,
+	mut chunks: Query<&mut TerrainChunk>,
+	viewers: Query<&GlobalTransform, With<TerrainViewer>>,
+	mut commands: Commands,
+) {
+	for (terrain, edits, transform, children) in terrains.iter() {
+		if terrain.lod_distances.is_empty() {
+			continue;
+		}
+
+		let mut lods = HashMap::new();
+		let mut changed = Vec::new();
+		let mut terrain_chunks = chunks.iter_many_mut(children);
+		while let Some(mut chunk) = terrain_chunks.fetch_next() {
+			let lod = terrain.lod_at(nearest_viewer_distance(
+				terrain,
+				transform,
+				&viewers,
+				chunk.coord,
+			));
+			if lod != chunk.lod {
+				chunk.lod = lod;
+				changed.push(chunk.coord);
+			}
+			lods.insert(chunk.coord, lod);
+		}
+
+		// Chunks next to ones that changed have to match their new borders
+		for child in children.iter() {
+			let Ok(chunk) = chunks.get(*child) else {
+				continue;
+			};
+			let near_change = changed
+				.iter()
+				.any(|coord| (*coord - chunk.coord).abs().max_element() <= 1);
+			if near_change {
+				commands.entity(*child).insert(GeneratingChunk::new(
+					terrain,
+					edits,
+					chunk.coord,
+					chunk.lod,
+					neighbour_lods(&lods, chunk.coord),
+				));
+			}
+		}
+	}
+}
+
+fn deform_terrain(
+	mut ev_deform: EventReader<DeformTerrain>,
+	mut terrains: Query<(&Terrain, &mut TerrainEdits, &GlobalTransform, &Children)>,
+	chunks: Query<&TerrainChunk>,
+	mut commands: Commands,
+) {
+	for DeformTerrain(brush) in ev_deform.read() {
+		for (terrain, mut edits, transform, children) in terrains.iter_mut() {
+			let mut local_brush = *brush;
+			local_brush.center = transform.affine().inverse().transform_point3(brush.center);
+
+			let changed_chunks = edits.apply(&terrain.settings, &local_brush);
+			if changed_chunks.is_empty() {
+				continue;
+			}
+
+			let lods = chunks
+				.iter_many(children)
+				.map(|chunk| (chunk.coord, chunk.lod))
+				.collect::<HashMap<_, _>>();
+
+			for child in children.iter() {
+				let Ok(chunk) = chunks.get(*child) else {
+					continue;
+				};
+				if changed_chunks.contains(&chunk.coord) {
+					// Replacing a chunk that's still generating drops its old task
+					commands.entity(*child).insert(GeneratingChunk::new(
+						terrain,
+						&edits,
+						chunk.coord,
+						chunk.lod,
+						neighbour_lods(&lods, chunk.coord),
+					));
+				}
+			}
+		}
+	}
+}
+
+fn finish_chunks(
+	mut chunks: Query<(Entity, &mut GeneratingChunk, &Parent)>,
+	terrains: Query<&Terrain>,
+	mut meshes: ResMut<Assets<Mesh>>,
+	mut commands: Commands,
+	mut ev_generated: EventWriter<ChunkGenerated>,
+) {
+	for (entity, mut task, parent) in chunks.iter_mut() {
+		let Some(generated) = block_on(poll_once(&mut task.0)) else {
+			continue;
+		};
+
+		let mut chunk = commands.entity(entity);
+		chunk.remove::<GeneratingChunk>();
+
+		let Ok(terrain) = terrains.get(parent.get()) else {
+			continue;
+		};
+
+		if let Some(mesh) = generated.mesh {
+			chunk.insert((
+				Mesh3d(meshes.add(mesh)),
+				MeshMaterial3d(terrain.material.clone()),
+			));
+		} else {
+			// Everything here got dug out
+			chunk.remove::<(Mesh3d, MeshMaterial3d<StandardMaterial>)>();
+		}
+		#[cfg(feature = "rapier")]
+		if let Some(collider) = generated.collider {
+			chunk.insert(collider);
+		} else {
+			chunk.remove::<Collider>();
+		}
+
+		ev_generated.send(ChunkGenerated {
+			terrain: parent.get(),
+			chunk: entity,
+		});
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::Brush;
+
+	fn test_app() -> (App, Entity) {
+		let mut app = App::new();
+		app.add_plugins((MinimalPlugins, AssetPlugin::default(), MarchingCubesPlugin))
+			.init_asset::<Mesh>()
+			.init_asset::<StandardMaterial>();
+
+		let settings = ChunkSettings {
+			chunk_size: 8.0,
+			voxels_per_chunk: 8,
+		};
+		let terrain = app
+			.world_mut()
+			.spawn(Terrain::covering(
+				Planet::new(6.0, 1.0, 0),
+				settings,
+				8.0,
+				Handle::default(),
+			))
+			.id();
+
+		(app, terrain)
+	}
+
+	fn finish_generating(app: &mut App) -> Vec<Entity> {
+		let mut generated = Vec::new();
+		let mut cursor = app
+			.world()
+			.resource::<Events<ChunkGenerated>>()
+			.get_cursor_current();
+		for _ in 0..1000 {
+			app.update();
+			generated.extend(
+				cursor
+					.read(app.world().resource::<Events<ChunkGenerated>>())
+					.map(|ev| ev.chunk),
+			);
+			if app
+				.world_mut()
+				.query::<&GeneratingChunk>()
+				.iter(app.world())
+				.next()
+				.is_none()
+			{
+				break;
+			}
+		}
+		generated
+	}
+
+	#[test]
+	fn terrain_chunks_get_meshes() {
+		let (mut app, terrain) = test_app();
+		finish_generating(&mut app);
+
+		let children = app.world().get::<Children>(terrain).unwrap();
+		// Two chunks along each axis, -8..0 and 0..8
+		assert_eq!(children.len(), 8);
+		for chunk in children.iter() {
+			assert!(app.world().get::<Mesh3d>(*chunk).is_some());
+		}
+	}
+
+	#[test]
+	fn digging_only_remeshes_touched_chunks() {
+		let (mut app, _) = test_app();
+		finish_generating(&mut app);
+
+		app.world_mut().send_event(DeformTerrain(Brush::dig(
+			Vec3::new(6.0, 0.0, 0.0),
+			1.0,
+			1.0,
+		)));
+		let remeshed = finish_generating(&mut app);
+
+		assert_eq!(remeshed.len(), 4);
+		for chunk in remeshed {
+			let chunk = app.world().get::<TerrainChunk>(chunk).unwrap();
+			assert_eq!(chunk.coord.x, 0);
+		}
+	}
+
+	#[test]
+	fn chunks_lose_detail_far_from_viewers() {
+		let (mut app, terrain) = test_app();
+		app.world_mut()
+			.entity_mut(terrain)
+			.get_mut::<Terrain>()
+			.unwrap()
+			.lod_distances = vec![20.0];
+		let viewer = app
+			.world_mut()
+			.spawn((
+				TerrainViewer,
+				GlobalTransform::from_translation(Vec3::X * 100.0),
+			))
+			.id();
+		finish_generating(&mut app);
+
+		let lods = |app: &mut App| {
+			app.world_mut()
+				.query::<&TerrainChunk>()
+				.iter(app.world())
+				.map(|chunk| chunk.lod)
+				.collect::<Vec<_>>()
+		};
+		assert_eq!(lods(&mut app), vec![1; 8]);
+
+		app.world_mut()
+			.entity_mut(viewer)
+			.insert(GlobalTransform::IDENTITY);
+		let remeshed = finish_generating(&mut app);
+		assert_eq!(remeshed.len(), 8);
+		assert_eq!(lods(&mut app), vec![0; 8]);
+	}
+
+	#[test]
+	fn neighbours_remesh_when_detail_changes_next_to_them() {
+		let (mut app, terrain) = test_app();
+		app.world_mut()
+			.entity_mut(terrain)
+			.get_mut::<Terrain>()
+			.unwrap()
+			.lod_distances = vec![10.0];
+		let viewer = app
+			.world_mut()
+			.spawn((
+				TerrainViewer,
+				GlobalTransform::from_translation(Vec3::X * 100.0),
+			))
+			.id();
+		finish_generating(&mut app);
+
+		// Close enough to the +X chunks' centers to bring only them back to full detail
+		app.world_mut()
+			.entity_mut(viewer)
+			.insert(GlobalTransform::from_translation(Vec3::new(8.0, 0.0, 0.0)));
+		let remeshed = finish_generating(&mut app);
+
+		let mut lods = app
+			.world_mut()
+			.query::<&TerrainChunk>()
+			.iter(app.world())
+			.map(|chunk| chunk.lod)
+			.collect::<Vec<_>>();
+		lods.sort();
+		assert_eq!(lods, vec![0, 0, 0, 0, 1, 1, 1, 1]);
+		assert_eq!(remeshed.len(), 8);
+	}
+}
@@ -0,0 +1,89 @@
+//! Frame times for gravity with thousands of bodies standing on a planet full of buildings.
+//!
+//! Run with `cargo bench -p sbepis --bench gravity`.
+
+use std::time::Instant;
+
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use self::gravity::*;
+
+// sbepis is only a binary, so the gravity module is pulled in on its own
+#[allow(dead_code)]
+#[path = "../src/gravity/mod.rs"]
+mod gravity;
+
+/// The one bit of `src/util.rs` that gravity needs, without the rest of the game it brings along.
+mod util {
+	use std::array::IntoIter;
+
+	use bevy::math::Vec3;
+
+	pub trait IterElements<T, const N: usize> {
+		fn iter_elements(&self) -> IntoIter<T, N>;
+	}
+	impl IterElements<f32, 3> for Vec3 {
+		fn iter_elements(&self) -> IntoIter<f32, 3> {
+			[self.x, self.y, self.z].into_iter()
+		}
+	}
+}
+
+const NUM_BODIES: usize = 5000;
+const NUM_BUILDINGS: usize = 500;
+const FRAMES: u32 = 100;
+
+fn main() {
+	let mut app = App::new();
+	app.add_plugins((MinimalPlugins, GravityPlugin));
+	let mut rng = StdRng::seed_from_u64(0);
+
+	// One big planet with buildings scattered over its surface
+	app.world_mut().spawn((
+		GlobalTransform::default(),
+		GravityPriority(0),
+		GravityPoint {
+			standard_radius: 400.0,
+			acceleration_at_radius: 9.8,
+		},
+	));
+	for _ in 0..NUM_BUILDINGS {
+		let position = Sphere::new(400.0).sample_boundary(&mut rng);
+		app.world_mut().spawn((
+			GlobalTransform::from(
+				Transform::from_translation(position)
+					.with_rotation(Quat::from_rotation_arc(Vec3::Y, position.normalize())),
+			),
+			GravityPriority(1),
+			GravityBox {
+				half_extents: Vec3::new(5.0, 10.0, 5.0),
+				acceleration: 9.8,
+				range: 2.0,
+				falloff: 2.0,
+			},
+		));
+	}
+	for _ in 0..NUM_BODIES {
+		let position = Sphere::new(400.0).sample_boundary(&mut rng) * rng.gen_range(1.0..1.05);
+		app.world_mut().spawn((
+			Transform::from_translation(position),
+			AffectedByGravity::default(),
+		));
+	}
+
+	// The first frame builds the index
+	app.update();
+
+	let start = Instant::now();
+	for _ in 0..FRAMES {
+		app.update();
+	}
+	let frame_time = start.elapsed() / FRAMES;
+
+	println!(
+		"{NUM_BODIES} bodies, {} fields: {frame_time:?} per frame",
+		NUM_BUILDINGS + 1
+	);
+}
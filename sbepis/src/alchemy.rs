@@ -0,0 +1,368 @@
+use bevy::prelude::*;
+use bevy_butler::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::grist::{GristAmounts, GristCache};
+use crate::inventory::definitions::{CaptchaCode, ItemDefinition, ItemModel, WeaponStats};
+use crate::inventory::{Item, ItemSpawner};
+use crate::prelude::PlayerBody;
+use crate::{ok_or_continue, some_or_continue};
+
+#[butler_plugin]
+pub struct AlchemyPlugin;
+
+/// How far in front of the player alchemized items appear.
+const RESULT_DISTANCE: f32 = 1.5;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Operation {
+	/// `&&`, which tends to combine what the items do.
+	And,
+	/// `||`, which tends to combine what the items look like.
+	Or,
+}
+
+impl Operation {
+	pub const ALL: [Operation; 2] = [Operation::And, Operation::Or];
+
+	pub fn symbol(self) -> &'static str {
+		match self {
+			Operation::And => "&&",
+			Operation::Or => "||",
+		}
+	}
+
+	pub fn apply(self, first: CaptchaCode, second: CaptchaCode) -> CaptchaCode {
+		match self {
+			Operation::And => first & second,
+			Operation::Or => first | second,
+		}
+	}
+}
+
+/// Makes a new item out of two others, without using either of them up. The player pays for it out
+/// of their [`GristCache`].
+#[derive(Event)]
+#[event(plugin = AlchemyPlugin)]
+pub struct Alchemize {
+	pub first: Entity,
+	pub second: Entity,
+	pub operation: Operation,
+}
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AlchemizeSet;
+
+#[system(
+	plugin = AlchemyPlugin, schedule = Update,
+	after = AlchemizeSet,
+)]
+fn alchemize_items(
+	mut ev_alchemize: EventReader<Alchemize>,
+	items: Query<&Item>,
+	mut player: Query<(&Transform, &mut GristCache), With<PlayerBody>>,
+	mut item_spawner: ItemSpawner,
+) {
+	for ev in ev_alchemize.read() {
+		let first = ok_or_continue!(items.get(ev.first));
+		let second = ok_or_continue!(items.get(ev.second));
+		let id = recipe_id(&first.id, ev.operation, &second.id);
+		let result = some_or_continue!(item_spawner.resolve(&id));
+
+		let (player, mut grist_cache) = ok_or_continue!(player.get_single_mut());
+		if !grist_cache.spend(&result.grist_cost) {
+			info!("Not enough grist to make {}", result.name);
+			continue;
+		}
+
+		let position = player.translation + player.forward() * RESULT_DISTANCE;
+		item_spawner.spawn(&id, Transform::from_translation(position));
+	}
+}
+
+/// The id of whatever alchemizing two items makes, like `"orange_cube&&rock"`. Ids that are
+/// recipes themselves get wrapped in parentheses.
+pub fn recipe_id(first: &str, operation: Operation, second: &str) -> String {
+	let wrap = |id: &str| {
+		if parse_recipe(id).is_some() {
+			format!("({id})")
+		} else {
+			id.to_owned()
+		}
+	};
+	format!("{}{}{}", wrap(first), operation.symbol(), wrap(second))
+}
+
+/// Splits a recipe id back up into what it's made of, or `None` if it's a plain item id.
+pub fn parse_recipe(id: &str) -> Option<(&str, Operation, &str)> {
+	let mut depth = 0;
+	for (index, character) in id.char_indices() {
+		match character {
+			'(' => depth += 1,
+			')' => depth -= 1,
+			_ if depth == 0 => {
+				let operation = Operation::ALL
+					.into_iter()
+					.find(|operation| id[index..].starts_with(operation.symbol()));
+				if let Some(operation) = operation {
+					return Some((
+						unwrap_parentheses(&id[..index]),
+						operation,
+						unwrap_parentheses(&id[index + operation.symbol().len()..]),
+					));
+				}
+			}
+			_ => {}
+		}
+	}
+	None
+}
+
+fn unwrap_parentheses(id: &str) -> &str {
+	id.strip_prefix('(')
+		.and_then(|id| id.strip_suffix(')'))
+		.unwrap_or(id)
+}
+
+/// Makes up the item that comes of combining two others. The same two items and operation always
+/// make the same item, whichever way round they go.
+pub fn alchemize(
+	first: &ItemDefinition,
+	second: &ItemDefinition,
+	operation: Operation,
+) -> ItemDefinition {
+	let (first, second) = if (first.captcha, &first.id) <= (second.captcha, &second.id) {
+		(first, second)
+	} else {
+		(second, first)
+	};
+	let mut rng = StdRng::seed_from_u64(recipe_seed(first.captcha, second.captcha, operation));
+	// The result mostly takes after the base, with bits of the modifier mixed in
+	let (base, modifier) = if rng.gen() {
+		(first, second)
+	} else {
+		(second, first)
+	};
+
+	ItemDefinition {
+		id: recipe_id(&first.id, operation, &second.id),
+		name: blend_names(&modifier.name, &base.name, operation),
+		description: format!(
+			"What comes of {} {} {}.",
+			first.name,
+			operation.symbol(),
+			second.name
+		),
+		model: ItemModel::Fusion(vec![base.model.clone(), modifier.model.clone()]),
+		size: base.size,
+		icon: base.icon.clone(),
+		grist_cost: blend_grist_costs(&first.grist_cost, &second.grist_cost, operation),
+		captcha: operation.apply(first.captcha, second.captcha),
+		tint: Some(blend_tints(first.tint, second.tint, &mut rng)),
+		weapon: blend_weapons(first.weapon, second.weapon, operation, &mut rng),
+	}
+}
+
+/// FNV-1a over both codes and the operation, so it comes out the same on every machine.
+fn recipe_seed(first: CaptchaCode, second: CaptchaCode, operation: Operation) -> u64 {
+	first
+		.bits()
+		.to_le_bytes()
+		.into_iter()
+		.chain(second.bits().to_le_bytes())
+		.chain([operation as u8])
+		.fold(0xcbf29ce484222325, |hash, byte| {
+			(hash ^ byte as u64).wrapping_mul(0x100000001b3)
+		})
+}
+
+/// `&&` sticks the modifier's adjectives onto the base's noun, so an "Orange Cube" modifying a
+/// "Rock" makes an "Orange Rock". `||` mashes their nouns together instead, so a "Rock" modifying
+/// an "Orange Cube" makes an "Orange Robe".
+fn blend_names(modifier: &str, base: &str, operation: Operation) -> String {
+	let (base_adjectives, base_noun) = match base.rsplit_once(' ') {
+		Some((adjectives, noun)) => (Some(adjectives), noun),
+		None => (None, base),
+	};
+	let (modifier_adjectives, modifier_noun) =
+		modifier.rsplit_once(' ').unwrap_or((modifier, modifier));
+
+	match operation {
+		Operation::And => format!("{modifier_adjectives} {base_noun}"),
+		Operation::Or => {
+			let modifier_noun = modifier_noun.chars().collect::<Vec<_>>();
+			let base_noun = base_noun.chars().collect::<Vec<_>>();
+			let noun = modifier_noun[..modifier_noun.len().div_ceil(2)]
+				.iter()
+				.chain(&base_noun[base_noun.len() / 2..])
+				.collect::<String>();
+			match base_adjectives {
+				Some(adjectives) => format!("{adjectives} {noun}"),
+				None => noun,
+			}
+		}
+	}
+}
+
+/// `&&` costs both items' worth of grist. `||` costs the pricier of the two for each kind of grist,
+/// plus half of the cheaper.
+fn blend_grist_costs(
+	first: &GristAmounts,
+	second: &GristAmounts,
+	operation: Operation,
+) -> GristAmounts {
+	first
+		.keys()
+		.chain(second.keys())
+		.map(|grist| {
+			let first = first.get(grist).copied().unwrap_or_default();
+			let second = second.get(grist).copied().unwrap_or_default();
+			let cost = match operation {
+				Operation::And => first + second,
+				Operation::Or => first.max(second) + first.min(second) / 2,
+			};
+			(*grist, cost)
+		})
+		.collect()
+}
+
+/// Halfway between the parents' tints, pulled toward some new color.
+fn blend_tints(first: Option<Srgba>, second: Option<Srgba>, rng: &mut impl Rng) -> Srgba {
+	let parents = first
+		.unwrap_or(Srgba::WHITE)
+		.mix(&second.unwrap_or(Srgba::WHITE), 0.5);
+	let new_color = Srgba::from(Hsla::hsl(rng.gen_range(0.0..360.0), 0.7, 0.7));
+	parents.mix(&new_color, 0.5)
+}
+
+/// `&&` makes one weapon that hits as hard as both. `||` makes something in between.
+fn blend_weapons(
+	first: Option<WeaponStats>,
+	second: Option<WeaponStats>,
+	operation: Operation,
+	rng: &mut impl Rng,
+) -> Option<WeaponStats> {
+	let weapon = match (first, second) {
+		(None, None) => return None,
+		(Some(weapon), None) | (None, Some(weapon)) => weapon,
+		(Some(first), Some(second)) => match operation {
+			Operation::And => WeaponStats {
+				damage: first.damage + second.damage,
+				reach: first.reach.max(second.reach),
+			},
+			Operation::Or => WeaponStats {
+				damage: (first.damage + second.damage) / 2.0,
+				reach: (first.reach + second.reach) / 2.0,
+			},
+		},
+	};
+	// A little luck either way
+	Some(WeaponStats {
+		damage: weapon.damage * rng.gen_range(0.8..1.2),
+		reach: weapon.reach,
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::grist::GristType;
+
+	fn definition(id: &str, name: &str, captcha: &str, damage: Option<f32>) -> ItemDefinition {
+		ItemDefinition {
+			id: id.to_owned(),
+			name: name.to_owned(),
+			description: String::new(),
+			model: ItemModel::Cube("orange".to_owned()),
+			size: 0.2,
+			icon: "item.png".to_owned(),
+			grist_cost: GristAmounts::from([(GristType::Build, 2)]),
+			captcha: captcha.parse().unwrap(),
+			tint: None,
+			weapon: damage.map(|damage| WeaponStats { damage, reach: 1.0 }),
+		}
+	}
+
+	#[test]
+	fn recipe_ids_round_trip() {
+		let id = recipe_id("orange_cube", Operation::And, "rock");
+		assert_eq!(id, "orange_cube&&rock");
+		assert_eq!(
+			parse_recipe(&id),
+			Some(("orange_cube", Operation::And, "rock"))
+		);
+		assert_eq!(parse_recipe("rock"), None);
+	}
+
+	#[test]
+	fn nested_recipe_ids_round_trip() {
+		let inner = recipe_id("orange_cube", Operation::And, "rock");
+		let id = recipe_id(&inner, Operation::Or, "purple_cube");
+		assert_eq!(id, "(orange_cube&&rock)||purple_cube");
+		assert_eq!(
+			parse_recipe(&id),
+			Some((inner.as_str(), Operation::Or, "purple_cube"))
+		);
+	}
+
+	#[test]
+	fn codes_combine_bitwise() {
+		let first = "00000003".parse::<CaptchaCode>().unwrap();
+		let second = "00000005".parse::<CaptchaCode>().unwrap();
+		assert_eq!(Operation::And.apply(first, second).to_string(), "00000001");
+		assert_eq!(Operation::Or.apply(first, second).to_string(), "00000007");
+	}
+
+	#[test]
+	fn alchemy_is_the_same_either_way_round() {
+		let cube = definition("orange_cube", "Orange Cube", "Or4nG3cb", None);
+		let hammer = definition("hammer", "Claw Hammer", "hAmM3r!!", Some(3.0));
+		for operation in Operation::ALL {
+			let forward = alchemize(&cube, &hammer, operation);
+			let backward = alchemize(&hammer, &cube, operation);
+			assert_eq!(forward.id, backward.id);
+			assert_eq!(forward.name, backward.name);
+			assert_eq!(forward.captcha, backward.captcha);
+			assert_eq!(forward.tint, backward.tint);
+			assert_eq!(forward.weapon, backward.weapon);
+		}
+	}
+
+	#[test]
+	fn and_blends_adjectives_onto_nouns() {
+		assert_eq!(
+			blend_names("Orange Cube", "Rock", Operation::And),
+			"Orange Rock"
+		);
+		assert_eq!(
+			blend_names("Rock", "Orange Cube", Operation::And),
+			"Rock Cube"
+		);
+	}
+
+	#[test]
+	fn or_blends_nouns_together() {
+		assert_eq!(
+			blend_names("Rock", "Orange Cube", Operation::Or),
+			"Orange Robe"
+		);
+		assert_eq!(
+			blend_names("Pogo Ride", "Claw Hammer", Operation::Or),
+			"Claw Rimer"
+		);
+	}
+
+	#[test]
+	fn grist_costs_blend() {
+		let first = GristAmounts::from([(GristType::Build, 4), (GristType::Shale, 1)]);
+		let second = GristAmounts::from([(GristType::Build, 2)]);
+		assert_eq!(
+			blend_grist_costs(&first, &second, Operation::And),
+			GristAmounts::from([(GristType::Build, 6), (GristType::Shale, 1)])
+		);
+		assert_eq!(
+			blend_grist_costs(&first, &second, Operation::Or),
+			GristAmounts::from([(GristType::Build, 5), (GristType::Shale, 1)])
+		);
+	}
+}
@@ -4,12 +4,18 @@ use bevy::prelude::*;
 use bevy::utils::HashSet;
 use bevy_butler::*;
 use bevy_rapier3d::prelude::*;
+use marching_cubes::{ChunkSettings, Planet, Terrain};
 
 use crate::entity::spawner::Spawner;
 use crate::entity::GelViscosity;
-use crate::gravity::{AffectedByGravity, GravityPoint, GravityPriority};
+use crate::gravity::{
+	AffectedByGravity, GravityBox, GravityCylinder, GravityMesh, GravityPlane, GravityPoint,
+	GravityPriority, GravitySpline,
+};
+use crate::inventory::SpawnsItem;
 use crate::npcs::consort::ConsortSpawner;
 use crate::npcs::imp::ImpSpawner;
+use crate::player_controller::death::PlayerSpawnPoint;
 use crate::{ok_or_continue, some_or_continue};
 
 #[butler_plugin(build(add_plugins(::blenvy::BlenvyPlugin::default())))]
@@ -72,6 +78,250 @@ fn create_planet(scenes: Query<(Entity, &PlanetBlundle)>, mut commands: Commands
 	}
 }
 
+/// How far from the camera procedural planets drop to each coarser level of detail.
+const PROCEDURAL_PLANET_LOD_DISTANCES: [f32; 2] = [48.0, 96.0];
+
+/// A planet whose surface is generated instead of modeled.
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+#[register_type(plugin = BlenvyPlugin)]
+pub struct ProceduralPlanetBlundle {
+	pub radius: f32,
+	pub gravity: f32,
+	/// How far hills rise above and valleys sink below the radius.
+	pub hill_height: f32,
+	pub seed: u32,
+}
+
+#[system(
+	plugin = BlenvyPlugin, schedule = PreUpdate,
+)]
+fn create_procedural_planet(
+	scenes: Query<(Entity, &ProceduralPlanetBlundle)>,
+	mut commands: Commands,
+	mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+	for (scene, planet) in scenes.iter() {
+		commands
+			.entity(scene)
+			.remove::<ProceduralPlanetBlundle>()
+			.insert((
+				RigidBody::Fixed,
+				GravityPoint {
+					standard_radius: planet.radius,
+					acceleration_at_radius: planet.gravity,
+				},
+				GravityPriority(0),
+				Terrain::covering(
+					Planet::new(planet.radius, planet.hill_height, planet.seed),
+					ChunkSettings::default(),
+					planet.radius + planet.hill_height,
+					// Generated meshes don't have UVs for the gridbox textures
+					materials.add(Color::srgb(0.3, 0.5, 0.2)),
+				)
+				.with_lod_distances(PROCEDURAL_PLANET_LOD_DISTANCES),
+			));
+	}
+}
+
+/// Gravity pulling down along the object's Y axis.
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+#[register_type(plugin = BlenvyPlugin)]
+pub struct GravityPlaneBlundle {
+	pub gravity: f32,
+	/// How high above the plane gravity is at full strength.
+	pub range: f32,
+	/// How far above that it fades away.
+	pub falloff: f32,
+	pub priority: u32,
+}
+
+#[system(
+	plugin = BlenvyPlugin, schedule = PreUpdate,
+)]
+fn create_gravity_plane(scenes: Query<(Entity, &GravityPlaneBlundle)>, mut commands: Commands) {
+	for (scene, plane) in scenes.iter() {
+		commands
+			.entity(scene)
+			.remove::<GravityPlaneBlundle>()
+			.insert((
+				GravityPlane {
+					acceleration: plane.gravity,
+					range: plane.range,
+					falloff: plane.falloff,
+				},
+				GravityPriority(plane.priority),
+			));
+	}
+}
+
+/// Gravity pulling onto every side of a box.
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+#[register_type(plugin = BlenvyPlugin)]
+pub struct GravityBoxBlundle {
+	pub half_extents: Vec3,
+	pub gravity: f32,
+	pub range: f32,
+	pub falloff: f32,
+	pub priority: u32,
+}
+
+#[system(
+	plugin = BlenvyPlugin, schedule = PreUpdate,
+)]
+fn create_gravity_box(scenes: Query<(Entity, &GravityBoxBlundle)>, mut commands: Commands) {
+	for (scene, gravity_box) in scenes.iter() {
+		commands
+			.entity(scene)
+			.remove::<GravityBoxBlundle>()
+			.insert((
+				GravityBox {
+					half_extents: gravity_box.half_extents,
+					acceleration: gravity_box.gravity,
+					range: gravity_box.range,
+					falloff: gravity_box.falloff,
+				},
+				GravityPriority(gravity_box.priority),
+			));
+	}
+}
+
+/// Gravity pulling onto the curved side of a cylinder, from outside or, for rings, inside.
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+#[register_type(plugin = BlenvyPlugin)]
+pub struct GravityCylinderBlundle {
+	pub radius: f32,
+	pub half_height: f32,
+	pub gravity: f32,
+	pub range: f32,
+	pub falloff: f32,
+	pub priority: u32,
+}
+
+#[system(
+	plugin = BlenvyPlugin, schedule = PreUpdate,
+)]
+fn create_gravity_cylinder(
+	scenes: Query<(Entity, &GravityCylinderBlundle)>,
+	mut commands: Commands,
+) {
+	for (scene, cylinder) in scenes.iter() {
+		commands
+			.entity(scene)
+			.remove::<GravityCylinderBlundle>()
+			.insert((
+				GravityCylinder {
+					radius: cylinder.radius,
+					half_height: cylinder.half_height,
+					acceleration: cylinder.gravity,
+					range: cylinder.range,
+					falloff: cylinder.falloff,
+				},
+				GravityPriority(cylinder.priority),
+			));
+	}
+}
+
+/// A gravity tube following a smooth curve through `points`, which are local to the object.
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+#[register_type(plugin = BlenvyPlugin)]
+pub struct GravitySplineBlundle {
+	pub points: Vec<Vec3>,
+	pub radius: f32,
+	pub gravity: f32,
+	pub range: f32,
+	pub falloff: f32,
+	pub priority: u32,
+}
+
+#[system(
+	plugin = BlenvyPlugin, schedule = PreUpdate,
+)]
+fn create_gravity_spline(scenes: Query<(Entity, &GravitySplineBlundle)>, mut commands: Commands) {
+	for (scene, spline) in scenes.iter() {
+		commands
+			.entity(scene)
+			.remove::<GravitySplineBlundle>()
+			.insert((
+				GravitySpline::through(
+					&spline.points,
+					spline.radius,
+					spline.gravity,
+					spline.range,
+					spline.falloff,
+				),
+				GravityPriority(spline.priority),
+			));
+	}
+}
+
+/// Gravity pulling onto the surface of every mesh in the scene.
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+#[register_type(plugin = BlenvyPlugin)]
+pub struct GravityMeshBlundle {
+	pub gravity: f32,
+	pub range: f32,
+	pub falloff: f32,
+	pub priority: u32,
+}
+
+#[system(
+	plugin = BlenvyPlugin, schedule = PreUpdate,
+)]
+fn create_gravity_mesh(
+	scenes: Query<(Entity, &GravityMeshBlundle)>,
+	children: Query<&Children>,
+	meshes: Query<&Mesh3d>,
+	mesh_assets: Res<Assets<Mesh>>,
+	mut commands: Commands,
+) {
+	for (scene, gravity_mesh) in scenes.iter() {
+		let surfaces = children
+			.iter_descendants(scene)
+			.filter_map(|child| Some((child, meshes.get(child).ok()?)))
+			.map(|(child, mesh)| (child, mesh_assets.get(&mesh.0)))
+			.collect::<Vec<_>>();
+
+		// Wait for the whole scene, or whatever loads late never gets gravity
+		if surfaces.is_empty() || surfaces.iter().any(|(_, mesh)| mesh.is_none()) {
+			continue;
+		}
+
+		for (child, mesh) in surfaces {
+			let mesh = some_or_continue!(mesh);
+			// Lines and points in the level don't have a surface to pull onto
+			let Some(surface) = Collider::from_bevy_mesh(mesh, &ComputedColliderShape::default())
+			else {
+				warn!(
+					"Couldn't make a gravity mesh out of {child} with {:?}",
+					mesh.primitive_topology()
+				);
+				continue;
+			};
+			let bounding_radius = mesh.compute_aabb().map_or(0.0, |aabb| {
+				Vec3::from(aabb.center).length() + Vec3::from(aabb.half_extents).length()
+			});
+			commands.entity(child).insert((
+				GravityMesh {
+					surface,
+					bounding_radius,
+					acceleration: gravity_mesh.gravity,
+					range: gravity_mesh.range,
+					falloff: gravity_mesh.falloff,
+				},
+				GravityPriority(gravity_mesh.priority),
+			));
+		}
+
+		commands.entity(scene).remove::<GravityMeshBlundle>();
+	}
+}
+
 #[derive(Component, Reflect)]
 #[reflect(Component)]
 #[register_type(plugin = BlenvyPlugin)]
@@ -102,6 +352,8 @@ fn create_box(scenes: Query<Entity, With<BoxBlundle>>, mut commands: Commands) {
 pub enum SpawnerBlundle {
 	Imp,
 	Consort,
+	/// Items with this id.
+	Item(String),
 }
 
 #[system(
@@ -127,6 +379,112 @@ fn create_spawner(scenes: Query<(Entity, &SpawnerBlundle)>, mut commands: Comman
 			SpawnerBlundle::Consort => {
 				spawner_commands.insert(ConsortSpawner);
 			}
+			SpawnerBlundle::Item(id) => {
+				spawner_commands.insert(SpawnsItem(id.clone()));
+			}
 		}
 	}
 }
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+#[register_type(plugin = BlenvyPlugin)]
+pub struct PlayerSpawnBlundle;
+
+#[system(
+	plugin = BlenvyPlugin, schedule = PreUpdate,
+)]
+fn create_player_spawn(scenes: Query<Entity, With<PlayerSpawnBlundle>>, mut commands: Commands) {
+	for scene in scenes.iter() {
+		commands
+			.entity(scene)
+			.remove::<PlayerSpawnBlundle>()
+			.insert(PlayerSpawnPoint);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use bevy::render::mesh::PrimitiveTopology;
+	use bevy::render::render_asset::RenderAssetUsages;
+
+	use super::*;
+
+	#[test]
+	fn gravity_meshes_wait_for_every_mesh_to_load() {
+		let mut app = App::new();
+		app.init_resource::<Assets<Mesh>>()
+			.add_systems(Update, create_gravity_mesh);
+
+		let mut mesh_assets = app.world_mut().resource_mut::<Assets<Mesh>>();
+		let loaded = mesh_assets.add(Cuboid::default());
+		let loading = mesh_assets.reserve_handle();
+
+		let scene = app
+			.world_mut()
+			.spawn(GravityMeshBlundle {
+				gravity: 9.8,
+				range: 1.0,
+				falloff: 1.0,
+				priority: 1,
+			})
+			.with_children(|scene| {
+				scene.spawn(Mesh3d(loaded));
+				scene
+					.spawn(Mesh3d(loading.clone()))
+					.with_child(Mesh3d(loading.clone()));
+			})
+			.id();
+		let surfaces = |app: &mut App| {
+			app.world_mut()
+				.query::<&GravityMesh>()
+				.iter(app.world())
+				.count()
+		};
+
+		app.update();
+		assert!(app.world().get::<GravityMeshBlundle>(scene).is_some());
+		assert_eq!(surfaces(&mut app), 0);
+
+		app.world_mut()
+			.resource_mut::<Assets<Mesh>>()
+			.insert(&loading, Cuboid::default().into());
+		app.update();
+		assert!(app.world().get::<GravityMeshBlundle>(scene).is_none());
+		assert_eq!(surfaces(&mut app), 3);
+	}
+
+	#[test]
+	fn gravity_meshes_skip_meshes_without_triangles() {
+		let mut app = App::new();
+		app.init_resource::<Assets<Mesh>>()
+			.add_systems(Update, create_gravity_mesh);
+
+		let mut mesh_assets = app.world_mut().resource_mut::<Assets<Mesh>>();
+		let cube = mesh_assets.add(Cuboid::default());
+		let line = mesh_assets.add(
+			Mesh::new(PrimitiveTopology::LineList, RenderAssetUsages::default())
+				.with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, vec![Vec3::ZERO, Vec3::X]),
+		);
+
+		let mut children = Vec::new();
+		let scene = app
+			.world_mut()
+			.spawn(GravityMeshBlundle {
+				gravity: 9.8,
+				range: 1.0,
+				falloff: 1.0,
+				priority: 1,
+			})
+			.with_children(|scene| {
+				children.push(scene.spawn(Mesh3d(cube)).id());
+				children.push(scene.spawn(Mesh3d(line)).id());
+			})
+			.id();
+
+		app.update();
+		assert!(app.world().get::<GravityMeshBlundle>(scene).is_none());
+		assert!(app.world().get::<GravityMesh>(children[0]).is_some());
+		assert!(app.world().get::<GravityMesh>(children[1]).is_none());
+	}
+}
@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use bevy::prelude::*;
 use bevy_butler::*;
 use bevy_rapier3d::prelude::Velocity;
@@ -14,6 +16,16 @@ pub struct GelViscosity {
 	pub max: f32,
 }
 
+/// Keeps an entity from taking damage until the timer runs out.
+#[derive(Component)]
+pub struct Invulnerable(pub Timer);
+
+impl Invulnerable {
+	pub fn new(duration: Duration) -> Self {
+		Self(Timer::new(duration, TimerMode::Once))
+	}
+}
+
 #[derive(Component)]
 pub struct SpawnHealthBar;
 
@@ -183,3 +195,19 @@ fn heal(mut healings: Query<(&Healing, &mut GelViscosity)>, time: Res<Time>) {
 		health.value = health.value.min(health.max);
 	}
 }
+
+#[system(
+	plugin = EntityPlugin, schedule = Update,
+	before = EntityDamagedSet,
+)]
+fn tick_invulnerability(
+	mut invulnerables: Query<(Entity, &mut Invulnerable)>,
+	mut commands: Commands,
+	time: Res<Time>,
+) {
+	for (entity, mut invulnerable) in invulnerables.iter_mut() {
+		if invulnerable.0.tick(time.delta()).finished() {
+			commands.entity(entity).remove::<Invulnerable>();
+		}
+	}
+}
@@ -1,8 +1,10 @@
 use bevy::prelude::*;
 use bevy_butler::*;
 
-pub use self::health::{GelViscosity, Healing, SpawnHealthBar};
-pub use self::movement::{Movement, RandomInput, RotateTowardMovement, TargetPlayer};
+use crate::prelude::PlayerBody;
+
+pub use self::health::{GelViscosity, Healing, Invulnerable, SpawnHealthBar};
+pub use self::movement::{Movement, RandomInput, RotateTowardMovement};
 pub use self::orientation::GravityOrientation;
 
 pub mod health;
@@ -23,8 +25,17 @@ pub struct EntityKilledSet;
 	plugin = EntityPlugin, schedule = Update,
 	after = EntityKilledSet,
 )]
-fn kill_entities(mut ev_killed: EventReader<EntityKilled>, mut commands: Commands) {
+fn kill_entities(
+	mut ev_killed: EventReader<EntityKilled>,
+	mut commands: Commands,
+	players: Query<(), With<PlayerBody>>,
+) {
 	for ev in ev_killed.read() {
+		// The player gets to respawn instead
+		if players.contains(ev.0) {
+			continue;
+		}
+
 		commands.entity(ev.0).despawn_recursive();
 	}
 }
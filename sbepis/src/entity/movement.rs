@@ -3,9 +3,10 @@ use std::time::Duration;
 use bevy::prelude::*;
 use bevy_butler::*;
 use bevy_rapier3d::prelude::*;
+use rand::Rng;
 
 use crate::entity::EntityPlugin;
-use crate::prelude::PlayerBody;
+use crate::replay::GameRng;
 
 #[derive(Component, Deref, DerefMut, Default)]
 /// The desired velocity in world-space. Will be projected onto the entity's floor plane.
@@ -51,40 +52,32 @@ pub struct RandomInput {
 	pub time_to_change: Duration,
 }
 
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RandomInputSet;
+
 #[system(
 	plugin = EntityPlugin, schedule = Update,
 	before = ExecuteMovementSet,
+	in_set = RandomInputSet,
 )]
-fn random_vec2(mut input: Query<(&mut RandomInput, &mut Movement)>, time: Res<Time>) {
+fn random_vec2(
+	mut input: Query<(&mut RandomInput, &mut Movement)>,
+	time: Res<Time>,
+	mut rng: GameRng,
+) {
+	let rng = rng.stream("random_vec2");
+
 	for (mut random_input, mut movement_input) in input.iter_mut() {
 		random_input.time_since_last_change += time.delta();
 
 		if random_input.time_since_last_change >= random_input.time_to_change {
-			let dir = rand::random::<Vec3>().normalize() * 2.0 - Vec3::ONE;
-			let mag = rand::random::<f32>() + 0.2;
+			let dir = rng.gen::<Vec3>().normalize() * 2.0 - Vec3::ONE;
+			let mag = rng.gen::<f32>() + 0.2;
 			random_input.input = dir * mag;
 			random_input.time_since_last_change = Duration::default();
-			random_input.time_to_change =
-				Duration::from_secs_f32(rand::random::<f32>() * 2.0 + 1.0);
+			random_input.time_to_change = Duration::from_secs_f32(rng.gen::<f32>() * 2.0 + 1.0);
 		}
 
 		movement_input.0 = random_input.input;
 	}
 }
-
-#[derive(Component)]
-pub struct TargetPlayer;
-
-#[system(
-	plugin = EntityPlugin, schedule = Update,
-	before = ExecuteMovementSet,
-)]
-fn target_player(
-	mut target_players: Query<(&Transform, &mut Movement), With<TargetPlayer>>,
-	player: Query<&Transform, With<PlayerBody>>,
-) {
-	let player_transform = player.single();
-	for (transform, mut input) in target_players.iter_mut() {
-		input.0 = (player_transform.translation - transform.translation).normalize();
-	}
-}
@@ -5,9 +5,10 @@ use bevy::audio::Volume;
 use bevy::prelude::*;
 use bevy_butler::*;
 use soundyrust::*;
-use tracks::{FrayTracks, Track, TrackSwitcher};
+use tracks::TrackSwitcher;
 
 use crate::camera::PlayerCameraNode;
+use crate::menus::InputManagerMenuPlugin;
 use crate::npcs::imp::Imp;
 use crate::player_controller::weapons::{EntityHit, EntityHitSet};
 use crate::prelude::PlayerBody;
@@ -15,7 +16,12 @@ use crate::util::MapRange;
 
 mod tracks;
 
-#[butler_plugin(build(add_plugins(SoundyPlugin)))]
+pub use tracks::{FrayTracks, Track, TrackSwitcherAction};
+
+#[butler_plugin(build(
+	add_plugins(SoundyPlugin),
+	add_plugins(InputManagerMenuPlugin::<TrackSwitcherAction>::default()),
+))]
 pub struct FrayPlugin;
 
 #[system(
@@ -39,6 +45,8 @@ fn play_background_music(mut commands: Commands, mut assets: ResMut<Assets<MidiA
 			.with_channel_patch(0, 0, 1)
 			.stopped(),
 	);
+	// Played live from the staff instead of from a file
+	let staff = midi.add_track(MidiAudioTrack::live(4.0 / 4.0));
 
 	midi.queue(
 		four_four,
@@ -79,6 +87,7 @@ fn play_background_music(mut commands: Commands, mut assets: ResMut<Assets<MidiA
 		imp: Track::SixEight,
 		four_four,
 		six_eight,
+		staff,
 	});
 
 	commands.spawn((
@@ -95,6 +104,38 @@ fn play_background_music(mut commands: Commands, mut assets: ResMut<Assets<MidiA
 	));
 }
 
+/// A scale that notes get pulled into so they fit with the music.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MusicalKey {
+	/// Pitch class of the tonic, with C as 0.
+	pub tonic: i32,
+	/// Semitones above the tonic that are in the scale.
+	pub scale: &'static [i32],
+}
+
+impl MusicalKey {
+	pub const MAJOR: &'static [i32] = &[0, 2, 4, 5, 7, 9, 11];
+	pub const NATURAL_MINOR: &'static [i32] = &[0, 2, 3, 5, 7, 8, 10];
+
+	pub fn contains(&self, key: i32) -> bool {
+		self.scale.contains(&(key - self.tonic).rem_euclid(12))
+	}
+
+	/// Pulls a MIDI key down onto the closest note of the scale at or below it.
+	pub fn clamp(&self, key: i32) -> i32 {
+		(0..12)
+			.map(|offset| key - offset)
+			.find(|key| self.contains(*key))
+			.unwrap_or(key)
+	}
+}
+
+/// The backing track and both leads are in F minor.
+const FRAY_KEY: MusicalKey = MusicalKey {
+	tonic: 5,
+	scale: MusicalKey::NATURAL_MINOR,
+};
+
 #[derive(Component)]
 pub struct FrayMusic {
 	beat: f64,
@@ -102,6 +143,7 @@ pub struct FrayMusic {
 	beats_per_second: f64,
 	delay: Option<Duration>,
 	backing_track: MidiAudioTrackHandle,
+	key: MusicalKey,
 }
 
 impl FrayMusic {
@@ -112,6 +154,7 @@ impl FrayMusic {
 			beats_per_second: 0.0,
 			delay: Some(Duration::from_secs_f32(1.0)),
 			backing_track,
+			key: FRAY_KEY,
 		}
 	}
 
@@ -122,6 +165,20 @@ impl FrayMusic {
 		self.beat %= self.beats_per_bar;
 	}
 
+	pub fn beat(&self) -> f64 {
+		self.beat
+	}
+
+	/// The key the music is playing in right now.
+	pub fn key(&self) -> MusicalKey {
+		self.key
+	}
+
+	/// Whether the music has started and knows its tempo, so beats and durations mean something.
+	pub fn is_keeping_time(&self) -> bool {
+		self.delay.is_none() && self.beats_per_second > 0.0
+	}
+
 	pub fn subbeats(&self, divisions: u32) -> u32 {
 		(self.beat * divisions as f64).floor() as u32
 	}
@@ -150,6 +207,13 @@ impl FrayMusic {
 		time.as_secs_f64() * self.beats_per_second
 	}
 
+	pub fn bpm_beat_to_time(&self, beats: f64) -> Duration {
+		if self.beats_per_second <= 0.0 {
+			return Duration::ZERO;
+		}
+		Duration::from_secs_f64(beats / self.beats_per_second)
+	}
+
 	pub fn speed(&self) -> f32 {
 		self.beats_per_second as f32
 	}
@@ -167,7 +231,7 @@ fn tick_fray_music(
 	#[cfg(feature = "metronome")] mut commands: Commands,
 	#[cfg(feature = "metronome")] asset_server: Res<AssetServer>,
 	time: Res<Time>,
-	mut fray_musics: Query<(&mut FrayMusic, &AudioSink, &AudioPlayer<MidiAudio>)>,
+	mut fray_musics: Query<(&mut FrayMusic, Option<&AudioSink>, &AudioPlayer<MidiAudio>)>,
 	mut beat_counters: Query<(&mut BeatCounter, &mut Text)>,
 	mut assets: ResMut<Assets<MidiAudio>>,
 ) {
@@ -193,7 +257,10 @@ fn tick_fray_music(
 		let midi_audio = assets
 			.get_mut(&midi_audio.0)
 			.expect("Couldn't find midi audio");
-		audio_sink.play(); // this should really be phased out or smth
+		// Without an audio device there's no sink, and the music just keeps time off the clock
+		if let Some(audio_sink) = audio_sink {
+			audio_sink.play(); // this should really be phased out or smth
+		}
 		fray_music.tick(time.delta(), midi_audio);
 		let beat = fray_music.subbeats(1);
 		let beat_progress = fray_music.beat_progress();
@@ -250,3 +317,27 @@ fn queue_tracks_on_hit(
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn notes_in_key_stay_put() {
+		// F, Ab, C, Eb
+		for key in [65, 68, 72, 75] {
+			assert_eq!(FRAY_KEY.clamp(key), key);
+		}
+	}
+
+	#[test]
+	fn notes_out_of_key_drop_into_it() {
+		// F# to F, A to Ab, B to Bb, E to Eb
+		assert_eq!(FRAY_KEY.clamp(66), 65);
+		assert_eq!(FRAY_KEY.clamp(69), 68);
+		assert_eq!(FRAY_KEY.clamp(71), 70);
+		assert_eq!(FRAY_KEY.clamp(64), 63);
+		// Below middle C too
+		assert_eq!(FRAY_KEY.clamp(54), 53);
+	}
+}
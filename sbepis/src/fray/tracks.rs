@@ -1,11 +1,13 @@
 use bevy::prelude::*;
 use bevy_butler::*;
 use leafwing_input_manager::prelude::*;
+use serde::{Deserialize, Serialize};
 use soundyrust::MidiAudioTrackHandle;
 
 use crate::dialogue::spawn_dialogue;
 use crate::fray::FrayPlugin;
 use crate::input::{ActionButtonEvent, InputManagerReference};
+use crate::keybinds::Keybinds;
 use crate::menus::{MenuManipulationSet, MenuStack};
 use crate::prelude::InteractedWithSet;
 
@@ -23,6 +25,8 @@ pub struct FrayTracks {
 	pub imp: Track,
 	pub four_four: MidiAudioTrackHandle,
 	pub six_eight: MidiAudioTrackHandle,
+	/// Notes played on the staff.
+	pub staff: MidiAudioTrackHandle,
 }
 impl FrayTracks {
 	pub fn player_track(&self) -> MidiAudioTrackHandle {
@@ -67,6 +71,7 @@ fn open_track_switch_dialogue(
 	mut ev_interact: EventReader<InteractedWith<TrackSwitcher>>,
 	mut commands: Commands,
 	mut menu_stack: ResMut<MenuStack>,
+	keybinds: Res<Keybinds>,
 ) {
 	for _ev in ev_interact.read() {
 		let mut dialogue = spawn_dialogue(
@@ -74,18 +79,28 @@ fn open_track_switch_dialogue(
 			&mut menu_stack,
 			"Select a track for the player to use.\nThe imps will use the other one.".to_owned(),
 			(),
-			InputMap::<TrackSwitcherAction>::default(),
+			keybinds.track_switcher.input_map(),
 		);
 		dialogue.add_option(
 			&mut commands,
-			"4/4".to_owned(),
+			format!(
+				"4/4 [{}]",
+				keybinds
+					.track_switcher
+					.describe(TrackSwitcherAction::FourFour)
+			),
 			TrackSwitcherFourFour {
 				dialogue: dialogue.root,
 			},
 		);
 		dialogue.add_option(
 			&mut commands,
-			"6/8".to_owned(),
+			format!(
+				"6/8 [{}]",
+				keybinds
+					.track_switcher
+					.describe(TrackSwitcherAction::SixEight)
+			),
 			TrackSwitcherSixEight {
 				dialogue: dialogue.root,
 			},
@@ -170,7 +185,7 @@ impl ActionButtonEvent for TrackSwitcherSixEight {
 	}
 }
 
-#[derive(Clone, Copy, Eq, PartialEq, Hash, Reflect, Debug)]
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Reflect, Debug, Serialize, Deserialize)]
 pub enum TrackSwitcherAction {
 	FourFour,
 	SixEight,
@@ -184,7 +199,7 @@ impl Actionlike for TrackSwitcherAction {
 	}
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub enum Track {
 	FourFour,
 	SixEight,
@@ -0,0 +1,202 @@
+use std::collections::BTreeMap;
+
+use bevy::math::Affine3A;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use bevy_butler::*;
+
+use crate::gravity::GravityPlugin;
+
+/// How wide each cell of the grid fields are sorted into is.
+const CELL_SIZE: f32 = 32.0;
+/// Fields wider than this many cells are checked everywhere instead of filling up the grid.
+const MAX_CELLS_ACROSS: i32 = 8;
+
+fn cell_at(position: Vec3) -> IVec3 {
+	(position / CELL_SIZE).floor().as_ivec3()
+}
+
+/// A gravity field's placement, ready to be checked against bodies.
+pub struct IndexedField {
+	pub entity: Entity,
+	pub world_from_local: Affine3A,
+	pub local_from_world: Affine3A,
+	/// The world-space sphere outside of which the field does nothing, if it ever stops.
+	bounds: Option<(Vec3, f32)>,
+}
+
+impl IndexedField {
+	pub fn new(entity: Entity, transform: &GlobalTransform, influence_radius: Option<f32>) -> Self {
+		let world_from_local = transform.affine();
+		let scale = Vec3::new(
+			world_from_local.matrix3.x_axis.length(),
+			world_from_local.matrix3.y_axis.length(),
+			world_from_local.matrix3.z_axis.length(),
+		)
+		.max_element();
+
+		Self {
+			entity,
+			world_from_local,
+			local_from_world: world_from_local.inverse(),
+			bounds: influence_radius
+				.map(|radius| (world_from_local.translation.into(), radius * scale)),
+		}
+	}
+
+	pub fn reaches(&self, position: Vec3) -> bool {
+		self.bounds
+			.is_none_or(|(center, radius)| center.distance_squared(position) <= radius * radius)
+	}
+}
+
+/// Every field of one priority, sorted into a grid by where they reach.
+#[derive(Default)]
+struct PriorityGroup {
+	fields: Vec<IndexedField>,
+	/// Fields that reach too far to be worth putting in the grid.
+	everywhere: Vec<usize>,
+	cells: HashMap<IVec3, Vec<usize>>,
+}
+
+impl PriorityGroup {
+	fn insert(&mut self, field: IndexedField) {
+		let index = self.fields.len();
+
+		match field.bounds {
+			Some((center, radius)) => {
+				let min_cell = cell_at(center - Vec3::splat(radius));
+				let max_cell = cell_at(center + Vec3::splat(radius));
+				if (max_cell - min_cell).max_element() < MAX_CELLS_ACROSS {
+					for x in min_cell.x..=max_cell.x {
+						for y in min_cell.y..=max_cell.y {
+							for z in min_cell.z..=max_cell.z {
+								self.cells
+									.entry(IVec3::new(x, y, z))
+									.or_default()
+									.push(index);
+							}
+						}
+					}
+				} else {
+					self.everywhere.push(index);
+				}
+			}
+			None => self.everywhere.push(index),
+		}
+
+		self.fields.push(field);
+	}
+
+	fn fields_near(&self, position: Vec3) -> impl Iterator<Item = &IndexedField> {
+		let cell = self
+			.cells
+			.get(&cell_at(position))
+			.map(Vec::as_slice)
+			.unwrap_or_default();
+		self.everywhere
+			.iter()
+			.chain(cell)
+			.map(|index| &self.fields[*index])
+			.filter(move |field| field.reaches(position))
+	}
+}
+
+/// Every gravity field, grouped by priority and sorted into a grid so bodies only check the
+/// fields that can reach them.
+///
+/// Rebuilt whenever a field is added, moved, changed or removed.
+#[derive(Resource, Default)]
+#[resource(plugin = GravityPlugin)]
+pub struct GravityIndex {
+	/// Lowest priority first.
+	groups: Vec<PriorityGroup>,
+}
+
+impl GravityIndex {
+	pub fn new(fields: impl IntoIterator<Item = (u32, IndexedField)>) -> Self {
+		let mut groups: BTreeMap<u32, PriorityGroup> = BTreeMap::new();
+		for (priority, field) in fields {
+			groups.entry(priority).or_default().insert(field);
+		}
+
+		Self {
+			groups: groups.into_values().collect(),
+		}
+	}
+
+	/// The fields that reach `position`, one iterator per priority from lowest to highest.
+	pub fn groups_near(
+		&self,
+		position: Vec3,
+	) -> impl Iterator<Item = impl Iterator<Item = &IndexedField>> {
+		self.groups
+			.iter()
+			.map(move |group| group.fields_near(position))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn field(entity: u32, position: Vec3, influence_radius: Option<f32>) -> IndexedField {
+		IndexedField::new(
+			Entity::from_raw(entity),
+			&GlobalTransform::from_translation(position),
+			influence_radius,
+		)
+	}
+
+	fn entities_near(index: &GravityIndex, position: Vec3) -> Vec<Vec<u32>> {
+		index
+			.groups_near(position)
+			.map(|group| group.map(|field| field.entity.index()).collect())
+			.collect()
+	}
+
+	#[test]
+	fn groups_are_sorted_by_priority() {
+		let index = GravityIndex::new([
+			(2, field(0, Vec3::ZERO, None)),
+			(0, field(1, Vec3::ZERO, None)),
+			(2, field(2, Vec3::ZERO, None)),
+		]);
+		assert_eq!(entities_near(&index, Vec3::ZERO), vec![vec![1], vec![0, 2]]);
+	}
+
+	#[test]
+	fn bounded_fields_are_culled() {
+		let index = GravityIndex::new([
+			(0, field(0, Vec3::ZERO, Some(5.0))),
+			(0, field(1, Vec3::X * 100.0, Some(5.0))),
+			(0, field(2, Vec3::X * 100.0, None)),
+		]);
+		assert_eq!(entities_near(&index, Vec3::X * 3.0), vec![vec![2, 0]]);
+		assert_eq!(entities_near(&index, Vec3::X * 97.0), vec![vec![2, 1]]);
+		assert_eq!(entities_near(&index, Vec3::X * 50.0), vec![vec![2]]);
+	}
+
+	#[test]
+	fn huge_fields_still_reach_everywhere_inside() {
+		let index = GravityIndex::new([(0, field(0, Vec3::ZERO, Some(1000.0)))]);
+		assert_eq!(entities_near(&index, Vec3::Y * 999.0), vec![vec![0]]);
+		assert_eq!(
+			entities_near(&index, Vec3::Y * 1001.0),
+			vec![Vec::<u32>::new()]
+		);
+	}
+
+	#[test]
+	fn scale_grows_the_reach() {
+		let index = GravityIndex::new([(
+			0,
+			IndexedField::new(
+				Entity::from_raw(0),
+				&GlobalTransform::from_scale(Vec3::splat(2.0)),
+				Some(5.0),
+			),
+		)]);
+		assert_eq!(entities_near(&index, Vec3::X * 9.0), vec![vec![0]]);
+	}
+}
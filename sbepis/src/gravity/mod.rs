@@ -0,0 +1,713 @@
+use bevy::prelude::*;
+use bevy_butler::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::gravity::index::{GravityIndex, IndexedField};
+use crate::util::IterElements;
+
+mod index;
+
+#[butler_plugin]
+pub struct GravityPlugin;
+
+#[derive(Component, Reflect)]
+#[register_type(plugin = GravityPlugin)]
+pub struct GravityPriority(pub u32);
+
+pub trait GravitationalField {
+	/// How much this acceleration affects an object, but also how much this priority should override lower priorities.
+	fn get_priority_factor_at(&self, local_position: Vec3) -> Vec3;
+	fn get_acceleration_at(&self, local_position: Vec3) -> Vec3;
+	/// How far from the origin, locally, the priority factor can be more than zero. `None` reaches everywhere.
+	fn get_influence_radius(&self) -> Option<f32> {
+		None
+	}
+}
+
+#[derive(Component, Reflect)]
+#[register_type(plugin = GravityPlugin)]
+pub struct GravityPoint {
+	pub standard_radius: f32,
+	pub acceleration_at_radius: f32,
+}
+
+impl GravitationalField for GravityPoint {
+	/// Points affect *all* objects, so they will always override lower priorities.
+	fn get_priority_factor_at(&self, _local_position: Vec3) -> Vec3 {
+		Vec3::ONE
+	}
+
+	fn get_acceleration_at(&self, local_position: Vec3) -> Vec3 {
+		let mass = self.acceleration_at_radius * self.standard_radius * self.standard_radius;
+		mass / -local_position.length_squared() * local_position.normalize()
+	}
+}
+
+#[derive(Component, Default)]
+#[require(RigidBody, Velocity)]
+pub struct AffectedByGravity {
+	pub acceleration: Vec3,
+	pub up: Vec3,
+}
+
+/// How much a field still applies `distance` away from its surface.
+///
+/// Full strength out to `range`, then fading away to nothing over `falloff`.
+fn falloff_factor(distance: f32, range: f32, falloff: f32) -> f32 {
+	if distance <= range {
+		1.0
+	} else if falloff <= 0.0 {
+		0.0
+	} else {
+		(1.0 - (distance - range) / falloff).max(0.0)
+	}
+}
+
+/// Pulls down along the local Y axis, like the floor of a space station.
+#[derive(Component, Reflect)]
+#[register_type(plugin = GravityPlugin)]
+pub struct GravityPlane {
+	pub acceleration: f32,
+	/// How high above the plane gravity is at full strength.
+	pub range: f32,
+	/// How far above that it fades away.
+	pub falloff: f32,
+}
+
+impl GravitationalField for GravityPlane {
+	fn get_priority_factor_at(&self, local_position: Vec3) -> Vec3 {
+		Vec3::new(
+			1.0,
+			falloff_factor(local_position.y.max(0.0), self.range, self.falloff),
+			1.0,
+		)
+	}
+
+	fn get_acceleration_at(&self, _local_position: Vec3) -> Vec3 {
+		Vec3::NEG_Y * self.acceleration
+	}
+}
+
+/// Pulls onto whichever side of a box is closest, so buildings can be walked up.
+#[derive(Component, Reflect)]
+#[register_type(plugin = GravityPlugin)]
+pub struct GravityBox {
+	pub half_extents: Vec3,
+	pub acceleration: f32,
+	/// How far from the box gravity is at full strength.
+	pub range: f32,
+	/// How far past that it fades away.
+	pub falloff: f32,
+}
+
+impl GravitationalField for GravityBox {
+	/// Each axis fades on its own, so corners blend between the faces that meet there.
+	fn get_priority_factor_at(&self, local_position: Vec3) -> Vec3 {
+		let distance = (local_position.abs() - self.half_extents).max(Vec3::ZERO);
+		Vec3::new(
+			falloff_factor(distance.x, self.range, self.falloff),
+			falloff_factor(distance.y, self.range, self.falloff),
+			falloff_factor(distance.z, self.range, self.falloff),
+		)
+	}
+
+	fn get_influence_radius(&self) -> Option<f32> {
+		Some((self.half_extents + self.range + self.falloff).length())
+	}
+
+	fn get_acceleration_at(&self, local_position: Vec3) -> Vec3 {
+		let closest_point = local_position.clamp(-self.half_extents, self.half_extents);
+		let down = if closest_point == local_position {
+			// Inside, so push out through the nearest face
+			let depth = self.half_extents - local_position.abs();
+			let axis = if depth.x < depth.y && depth.x < depth.z {
+				Vec3::X
+			} else if depth.y < depth.z {
+				Vec3::Y
+			} else {
+				Vec3::Z
+			};
+			-axis * local_position.signum()
+		} else {
+			(closest_point - local_position).normalize()
+		};
+		down * self.acceleration
+	}
+}
+
+/// Pulls onto the curved side of a cylinder along the local Y axis.
+///
+/// Anything inside is pulled outwards, so a short wide cylinder makes a ring that can be walked
+/// around on the inside.
+#[derive(Component, Reflect)]
+#[register_type(plugin = GravityPlugin)]
+pub struct GravityCylinder {
+	pub radius: f32,
+	pub half_height: f32,
+	pub acceleration: f32,
+	/// How far from the curved side or past the ends gravity is at full strength.
+	pub range: f32,
+	/// How far past that it fades away.
+	pub falloff: f32,
+}
+
+impl GravitationalField for GravityCylinder {
+	fn get_priority_factor_at(&self, local_position: Vec3) -> Vec3 {
+		let radial_distance = (local_position.xz().length() - self.radius).abs();
+		let height_distance = (local_position.y.abs() - self.half_height).max(0.0);
+		Vec3::new(
+			falloff_factor(radial_distance, self.range, self.falloff),
+			falloff_factor(height_distance, self.range, self.falloff),
+			1.0,
+		)
+	}
+
+	fn get_influence_radius(&self) -> Option<f32> {
+		let reach = self.range + self.falloff;
+		Some(Vec2::new(self.radius + reach, self.half_height + reach).length())
+	}
+
+	fn get_acceleration_at(&self, local_position: Vec3) -> Vec3 {
+		let outwards = Vec3::new(local_position.x, 0.0, local_position.z).normalize_or_zero();
+		let down = if local_position.xz().length() > self.radius {
+			-outwards
+		} else {
+			outwards
+		};
+		down * self.acceleration
+	}
+}
+
+/// Pulls onto the outside of a tube that follows a path, or outwards from inside it.
+#[derive(Component, Reflect)]
+#[register_type(plugin = GravityPlugin)]
+pub struct GravitySpline {
+	/// Points along the middle of the tube, close enough together to look smooth.
+	pub path: Vec<Vec3>,
+	pub radius: f32,
+	pub acceleration: f32,
+	/// How far from the tube's surface gravity is at full strength.
+	pub range: f32,
+	/// How far past that it fades away.
+	pub falloff: f32,
+}
+
+impl GravitySpline {
+	/// How many points each span between control points is split into.
+	const SUBDIVISIONS: usize = 16;
+
+	/// Runs a smooth curve through every control point.
+	pub fn through(
+		control_points: &[Vec3],
+		radius: f32,
+		acceleration: f32,
+		range: f32,
+		falloff: f32,
+	) -> Self {
+		// Bevy's Catmull-Rom curves already reach their end points, and doubling them up makes the
+		// curve loop back on itself there. Too few points for a curve stay as they are.
+		let path = CubicCardinalSpline::new_catmull_rom(control_points.to_vec())
+			.to_curve()
+			.map(|curve| curve.iter_positions(Self::SUBDIVISIONS).collect())
+			.unwrap_or_else(|_| control_points.to_vec());
+
+		Self {
+			path,
+			radius,
+			acceleration,
+			range,
+			falloff,
+		}
+	}
+
+	fn closest_point(&self, local_position: Vec3) -> Option<Vec3> {
+		let segments = self.path.windows(2).map(|segment| {
+			let (start, end) = (segment[0], segment[1]);
+			let t = (local_position - start).dot(end - start)
+				/ (end - start).length_squared().max(f32::EPSILON);
+			start.lerp(end, t.clamp(0.0, 1.0))
+		});
+		let points = if self.path.len() == 1 {
+			vec![self.path[0]]
+		} else {
+			segments.collect()
+		};
+		points.into_iter().min_by(|a, b| {
+			a.distance_squared(local_position)
+				.total_cmp(&b.distance_squared(local_position))
+		})
+	}
+}
+
+impl GravitationalField for GravitySpline {
+	fn get_priority_factor_at(&self, local_position: Vec3) -> Vec3 {
+		let Some(closest_point) = self.closest_point(local_position) else {
+			return Vec3::ZERO;
+		};
+		let distance = (closest_point.distance(local_position) - self.radius).abs();
+		Vec3::splat(falloff_factor(distance, self.range, self.falloff))
+	}
+
+	fn get_influence_radius(&self) -> Option<f32> {
+		let furthest_point = self
+			.path
+			.iter()
+			.map(|point| point.length())
+			.fold(0.0, f32::max);
+		Some(furthest_point + self.radius + self.range + self.falloff)
+	}
+
+	fn get_acceleration_at(&self, local_position: Vec3) -> Vec3 {
+		let Some(closest_point) = self.closest_point(local_position) else {
+			return Vec3::ZERO;
+		};
+		let outwards = (local_position - closest_point).normalize_or_zero();
+		let down = if closest_point.distance(local_position) > self.radius {
+			-outwards
+		} else {
+			outwards
+		};
+		down * self.acceleration
+	}
+}
+
+/// Pulls towards the closest point on a mesh, for walking on odd shapes.
+#[derive(Component)]
+pub struct GravityMesh {
+	/// The mesh's surface, as a collider since those already know how to find their closest point.
+	pub surface: Collider,
+	/// How far the furthest part of the mesh is from its origin.
+	pub bounding_radius: f32,
+	pub acceleration: f32,
+	/// How far from the surface gravity is at full strength.
+	pub range: f32,
+	/// How far past that it fades away.
+	pub falloff: f32,
+}
+
+impl GravitationalField for GravityMesh {
+	fn get_priority_factor_at(&self, local_position: Vec3) -> Vec3 {
+		let projection =
+			self.surface
+				.project_point(Vec3::ZERO, Quat::IDENTITY, local_position, false);
+		let distance = projection.point.distance(local_position);
+		Vec3::splat(falloff_factor(distance, self.range, self.falloff))
+	}
+
+	fn get_influence_radius(&self) -> Option<f32> {
+		Some(self.bounding_radius + self.range + self.falloff)
+	}
+
+	fn get_acceleration_at(&self, local_position: Vec3) -> Vec3 {
+		let projection =
+			self.surface
+				.project_point(Vec3::ZERO, Quat::IDENTITY, local_position, false);
+		(projection.point - local_position).normalize_or_zero() * self.acceleration
+	}
+}
+
+type AnyField = AnyOf<(
+	&'static GravityPoint,
+	&'static GravityPlane,
+	&'static GravityBox,
+	&'static GravityCylinder,
+	&'static GravitySpline,
+	&'static GravityMesh,
+)>;
+
+fn as_field<'a>(
+	(point, plane, gravity_box, cylinder, spline, mesh): (
+		Option<&'a GravityPoint>,
+		Option<&'a GravityPlane>,
+		Option<&'a GravityBox>,
+		Option<&'a GravityCylinder>,
+		Option<&'a GravitySpline>,
+		Option<&'a GravityMesh>,
+	),
+) -> &'a dyn GravitationalField {
+	[
+		point.map(|field| field as &dyn GravitationalField),
+		plane.map(|field| field as _),
+		gravity_box.map(|field| field as _),
+		cylinder.map(|field| field as _),
+		spline.map(|field| field as _),
+		mesh.map(|field| field as _),
+	]
+	.into_iter()
+	.flatten()
+	.next()
+	.expect("AnyOf always has a field")
+}
+
+#[system(
+	plugin = GravityPlugin, schedule = Update,
+	before = calculate_gravity,
+)]
+fn index_gravity_fields(
+	mut index: ResMut<GravityIndex>,
+	fields: Query<(Entity, &GlobalTransform, &GravityPriority, AnyField)>,
+	changed_fields: Query<
+		(),
+		(
+			With<GravityPriority>,
+			Or<(
+				Changed<GlobalTransform>,
+				Changed<GravityPriority>,
+				Changed<GravityPoint>,
+				Changed<GravityPlane>,
+				Changed<GravityBox>,
+				Changed<GravityCylinder>,
+				Changed<GravitySpline>,
+				Changed<GravityMesh>,
+			)>,
+		),
+	>,
+	mut removed_fields: RemovedComponents<GravityPriority>,
+) {
+	let any_removed = removed_fields.read().count() > 0;
+	if changed_fields.is_empty() && !any_removed {
+		return;
+	}
+
+	*index = GravityIndex::new(fields.iter().map(|(entity, transform, priority, field)| {
+		(
+			priority.0,
+			IndexedField::new(entity, transform, as_field(field).get_influence_radius()),
+		)
+	}));
+}
+
+#[system(
+	plugin = GravityPlugin, schedule = Update,
+)]
+fn calculate_gravity(
+	mut rigidbodies: Query<(&Transform, &mut AffectedByGravity)>,
+	fields: Query<AnyField>,
+	index: Res<GravityIndex>,
+) {
+	rigidbodies
+		.par_iter_mut()
+		.for_each(|(transform, mut gravity)| {
+			let position = transform.translation;
+			let acceleration = index.groups_near(position).fold(
+				Vec3::ZERO,
+				|lower_priority_acceleration, group| {
+					let (acceleration, priority_factor) = group
+						.filter_map(|indexed| {
+							Some((indexed, as_field(fields.get(indexed.entity).ok()?)))
+						})
+						.fold(
+							(Vec3::ZERO, 0.0),
+							|(acceleration, priority_factor), (indexed, field)| {
+								let local_position =
+									indexed.local_from_world.transform_point3(position);
+								let field_priority_factor: f32 = field
+									.get_priority_factor_at(local_position)
+									.iter_elements()
+									.product();
+								let field_acceleration = indexed
+									.world_from_local
+									.transform_vector3(field.get_acceleration_at(local_position));
+								(
+									acceleration + field_acceleration * field_priority_factor,
+									priority_factor + field_priority_factor,
+								)
+							},
+						);
+					Vec3::lerp(lower_priority_acceleration, acceleration, priority_factor)
+				},
+			);
+
+			gravity.acceleration = acceleration;
+			gravity.up = -acceleration.normalize_or(Vec3::Y);
+		});
+}
+
+#[system(
+	plugin = GravityPlugin, schedule = Update,
+	after = calculate_gravity,
+)]
+fn apply_gravity(mut rigidbodies: Query<(&mut Velocity, &AffectedByGravity)>, time: Res<Time>) {
+	for (mut velocity, gravity) in rigidbodies.iter_mut() {
+		velocity.linvel += gravity.acceleration * time.delta_secs();
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn assert_close(actual: Vec3, expected: Vec3) {
+		assert!(
+			actual.distance(expected) < 1e-4,
+			"expected {expected}, got {actual}"
+		);
+	}
+
+	#[test]
+	fn planes_pull_down_and_fade_above_their_range() {
+		let plane = GravityPlane {
+			acceleration: 10.0,
+			range: 2.0,
+			falloff: 4.0,
+		};
+
+		for position in [Vec3::new(3.0, 1.0, -2.0), Vec3::Y * 50.0, Vec3::NEG_Y * 3.0] {
+			assert_eq!(plane.get_acceleration_at(position), Vec3::NEG_Y * 10.0);
+		}
+		assert_eq!(
+			plane.get_priority_factor_at(Vec3::new(5.0, 1.0, 5.0)),
+			Vec3::ONE
+		);
+		assert_eq!(plane.get_priority_factor_at(Vec3::NEG_Y * 3.0), Vec3::ONE);
+		assert_eq!(
+			plane.get_priority_factor_at(Vec3::Y * 4.0),
+			Vec3::new(1.0, 0.5, 1.0)
+		);
+		assert_eq!(
+			plane.get_priority_factor_at(Vec3::Y * 7.0),
+			Vec3::new(1.0, 0.0, 1.0)
+		);
+		assert_eq!(plane.get_influence_radius(), None);
+	}
+
+	#[test]
+	fn boxes_pull_onto_their_nearest_side() {
+		let gravity_box = GravityBox {
+			half_extents: Vec3::new(1.0, 2.0, 3.0),
+			acceleration: 10.0,
+			range: 1.0,
+			falloff: 2.0,
+		};
+
+		assert_close(
+			gravity_box.get_acceleration_at(Vec3::new(0.0, 4.0, 0.0)),
+			Vec3::NEG_Y * 10.0,
+		);
+		assert_close(
+			gravity_box.get_acceleration_at(Vec3::new(2.0, 0.0, 1.0)),
+			Vec3::NEG_X * 10.0,
+		);
+		assert_eq!(
+			gravity_box.get_priority_factor_at(Vec3::new(0.0, 4.0, 0.0)),
+			Vec3::new(1.0, 0.5, 1.0)
+		);
+		assert_eq!(
+			gravity_box.get_priority_factor_at(Vec3::new(0.0, 0.0, 7.0)),
+			Vec3::new(1.0, 1.0, 0.0)
+		);
+	}
+
+	#[test]
+	fn boxes_pull_through_the_nearest_face_from_inside() {
+		let gravity_box = GravityBox {
+			half_extents: Vec3::new(1.0, 2.0, 3.0),
+			acceleration: 10.0,
+			range: 1.0,
+			falloff: 2.0,
+		};
+
+		assert_close(
+			gravity_box.get_acceleration_at(Vec3::new(0.9, 0.5, -1.0)),
+			Vec3::NEG_X * 10.0,
+		);
+		assert_close(
+			gravity_box.get_acceleration_at(Vec3::new(-0.2, -1.9, 0.0)),
+			Vec3::Y * 10.0,
+		);
+		assert_eq!(
+			gravity_box.get_priority_factor_at(Vec3::new(0.9, 0.5, -1.0)),
+			Vec3::ONE
+		);
+	}
+
+	#[test]
+	fn box_corners_blend_their_faces() {
+		let gravity_box = GravityBox {
+			half_extents: Vec3::new(1.0, 2.0, 3.0),
+			acceleration: 10.0,
+			range: 1.0,
+			falloff: 2.0,
+		};
+
+		assert_close(
+			gravity_box.get_acceleration_at(Vec3::new(2.0, 3.0, 4.0)),
+			Vec3::NEG_ONE.normalize() * 10.0,
+		);
+		assert_eq!(
+			gravity_box.get_priority_factor_at(Vec3::new(2.0, 3.0, 4.0)),
+			Vec3::ONE
+		);
+		assert_eq!(
+			gravity_box.get_priority_factor_at(Vec3::new(-3.0, 4.0, -5.0)),
+			Vec3::splat(0.5)
+		);
+		assert_eq!(
+			gravity_box.get_priority_factor_at(Vec3::new(4.0, 5.0, 6.0)),
+			Vec3::ZERO
+		);
+	}
+
+	#[test]
+	fn cylinders_pull_onto_their_curved_side() {
+		let cylinder = GravityCylinder {
+			radius: 5.0,
+			half_height: 2.0,
+			acceleration: 10.0,
+			range: 1.0,
+			falloff: 2.0,
+		};
+
+		// Outside pulls in, inside pulls out
+		assert_close(
+			cylinder.get_acceleration_at(Vec3::new(7.0, 1.0, 0.0)),
+			Vec3::NEG_X * 10.0,
+		);
+		assert_close(
+			cylinder.get_acceleration_at(Vec3::new(0.0, -1.0, 4.5)),
+			Vec3::Z * 10.0,
+		);
+		assert_eq!(
+			cylinder.get_priority_factor_at(Vec3::new(7.0, 1.0, 0.0)),
+			Vec3::new(0.5, 1.0, 1.0)
+		);
+		assert_eq!(
+			cylinder.get_priority_factor_at(Vec3::new(0.0, -1.0, 4.5)),
+			Vec3::ONE
+		);
+		// Right on the axis is as far from the side as it gets
+		assert_eq!(cylinder.get_acceleration_at(Vec3::ZERO), Vec3::ZERO);
+		assert_eq!(
+			cylinder.get_priority_factor_at(Vec3::ZERO),
+			Vec3::new(0.0, 1.0, 1.0)
+		);
+	}
+
+	#[test]
+	fn cylinders_fade_past_their_ends() {
+		let cylinder = GravityCylinder {
+			radius: 5.0,
+			half_height: 2.0,
+			acceleration: 10.0,
+			range: 1.0,
+			falloff: 2.0,
+		};
+
+		assert_close(
+			cylinder.get_acceleration_at(Vec3::new(0.0, 4.0, -6.0)),
+			Vec3::Z * 10.0,
+		);
+		assert_eq!(
+			cylinder.get_priority_factor_at(Vec3::new(0.0, 4.0, -6.0)),
+			Vec3::new(1.0, 0.5, 1.0)
+		);
+		assert_eq!(
+			cylinder.get_priority_factor_at(Vec3::new(0.0, -6.0, -6.0)),
+			Vec3::new(1.0, 0.0, 1.0)
+		);
+	}
+
+	fn straight_spline() -> GravitySpline {
+		GravitySpline::through(&[Vec3::ZERO, Vec3::X * 10.0], 1.0, 10.0, 1.0, 2.0)
+	}
+
+	#[test]
+	fn splines_pull_onto_their_tube() {
+		let spline = straight_spline();
+
+		assert_close(
+			spline.get_acceleration_at(Vec3::new(5.0, 3.0, 0.0)),
+			Vec3::NEG_Y * 10.0,
+		);
+		assert_close(
+			spline.get_acceleration_at(Vec3::new(5.0, 0.0, -0.5)),
+			Vec3::NEG_Z * 10.0,
+		);
+		assert_eq!(
+			spline.get_priority_factor_at(Vec3::new(5.0, 3.0, 0.0)),
+			Vec3::splat(0.5)
+		);
+		assert_eq!(
+			spline.get_priority_factor_at(Vec3::new(5.0, 0.0, -0.5)),
+			Vec3::ONE
+		);
+		assert_eq!(
+			spline.get_priority_factor_at(Vec3::new(5.0, 5.0, 0.0)),
+			Vec3::ZERO
+		);
+	}
+
+	#[test]
+	fn splines_end_at_their_end_points() {
+		let spline = straight_spline();
+
+		assert_close(spline.path[0], Vec3::ZERO);
+		assert_close(*spline.path.last().unwrap(), Vec3::X * 10.0);
+		// Past the ends pulls back towards them
+		assert_close(
+			spline.get_acceleration_at(Vec3::new(13.0, 0.0, 0.0)),
+			Vec3::NEG_X * 10.0,
+		);
+		assert_close(
+			spline.get_acceleration_at(Vec3::new(-3.0, 0.0, 0.0)),
+			Vec3::X * 10.0,
+		);
+		assert_close(
+			spline.get_priority_factor_at(Vec3::new(13.0, 0.0, 0.0)),
+			Vec3::splat(0.5),
+		);
+	}
+
+	#[test]
+	fn empty_splines_do_nothing() {
+		let spline = GravitySpline::through(&[], 1.0, 10.0, 1.0, 2.0);
+		assert_eq!(spline.get_acceleration_at(Vec3::ONE), Vec3::ZERO);
+		assert_eq!(spline.get_priority_factor_at(Vec3::ONE), Vec3::ZERO);
+	}
+
+	fn cube_mesh() -> GravityMesh {
+		GravityMesh {
+			surface: Collider::cuboid(1.0, 1.0, 1.0),
+			bounding_radius: 3.0_f32.sqrt(),
+			acceleration: 10.0,
+			range: 1.0,
+			falloff: 2.0,
+		}
+	}
+
+	#[test]
+	fn meshes_pull_onto_their_surface() {
+		let mesh = cube_mesh();
+
+		assert_close(
+			mesh.get_acceleration_at(Vec3::new(0.0, 3.0, 0.0)),
+			Vec3::NEG_Y * 10.0,
+		);
+		assert_close(
+			mesh.get_acceleration_at(Vec3::splat(2.0)),
+			Vec3::NEG_ONE.normalize() * 10.0,
+		);
+		assert_eq!(
+			mesh.get_priority_factor_at(Vec3::new(0.0, 3.0, 0.0)),
+			Vec3::splat(0.5)
+		);
+		assert_eq!(
+			mesh.get_priority_factor_at(Vec3::new(0.0, 5.0, 0.0)),
+			Vec3::ZERO
+		);
+	}
+
+	#[test]
+	fn meshes_pull_outwards_from_inside() {
+		let mesh = cube_mesh();
+
+		assert_close(
+			mesh.get_acceleration_at(Vec3::new(0.0, 0.5, 0.0)),
+			Vec3::Y * 10.0,
+		);
+		assert_eq!(
+			mesh.get_priority_factor_at(Vec3::new(0.0, 0.5, 0.0)),
+			Vec3::ONE
+		);
+	}
+}
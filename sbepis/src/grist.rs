@@ -0,0 +1,357 @@
+use std::collections::BTreeMap;
+use std::f32::consts::TAU;
+
+use bevy::color::palettes::css;
+use bevy::prelude::*;
+use bevy_butler::*;
+use bevy_rapier3d::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::camera::PlayerCameraNode;
+use crate::entity::{EntityKilled, EntityKilledSet};
+use crate::gravity::AffectedByGravity;
+use crate::player_controller::death::PlayerDead;
+use crate::prelude::PlayerBody;
+use crate::replay::GameRng;
+use crate::{ok_or_continue, some_or_return};
+
+#[butler_plugin]
+pub struct GristPlugin;
+
+/// The kinds of grist that items are made out of.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum GristType {
+	/// What everything's made of, so every underling drops some.
+	Build,
+	Shale,
+	Ruby,
+	Amber,
+	Garnet,
+	Cobalt,
+	Iron,
+	Chalk,
+}
+
+impl GristType {
+	pub const ALL: [GristType; 8] = [
+		GristType::Build,
+		GristType::Shale,
+		GristType::Ruby,
+		GristType::Amber,
+		GristType::Garnet,
+		GristType::Cobalt,
+		GristType::Iron,
+		GristType::Chalk,
+	];
+
+	pub fn name(self) -> &'static str {
+		match self {
+			GristType::Build => "Build",
+			GristType::Shale => "Shale",
+			GristType::Ruby => "Ruby",
+			GristType::Amber => "Amber",
+			GristType::Garnet => "Garnet",
+			GristType::Cobalt => "Cobalt",
+			GristType::Iron => "Iron",
+			GristType::Chalk => "Chalk",
+		}
+	}
+
+	pub fn color(self) -> Srgba {
+		match self {
+			GristType::Build => Srgba::rgb(0.2, 0.6, 1.0),
+			GristType::Shale => Srgba::rgb(0.1, 0.08, 0.12),
+			GristType::Ruby => Srgba::rgb(0.85, 0.1, 0.2),
+			GristType::Amber => Srgba::rgb(1.0, 0.65, 0.1),
+			GristType::Garnet => Srgba::rgb(0.5, 0.05, 0.1),
+			GristType::Cobalt => Srgba::rgb(0.0, 0.28, 0.67),
+			GristType::Iron => Srgba::rgb(0.5, 0.5, 0.55),
+			GristType::Chalk => Srgba::rgb(0.95, 0.95, 0.9),
+		}
+	}
+}
+
+/// How much of each kind of grist something costs or is worth.
+pub type GristAmounts = BTreeMap<GristType, u32>;
+
+/// How much of each kind of grist the cache can hold at tier 1. Every tier doubles it.
+const BASE_GRIST_CAPACITY: u32 = 50;
+
+/// All the grist a player has collected, which gets spent on alchemy and building.
+#[derive(Component, Clone, Debug, Serialize, Deserialize)]
+pub struct GristCache {
+	pub amounts: GristAmounts,
+	/// Raises how much of each kind of grist fits in the cache.
+	pub tier: u32,
+}
+
+impl Default for GristCache {
+	fn default() -> Self {
+		Self {
+			amounts: GristAmounts::new(),
+			tier: 1,
+		}
+	}
+}
+
+impl GristCache {
+	/// How much of each kind of grist fits in the cache.
+	pub fn capacity(&self) -> u32 {
+		BASE_GRIST_CAPACITY << self.tier.saturating_sub(1).min(16)
+	}
+
+	pub fn get(&self, grist_type: GristType) -> u32 {
+		self.amounts.get(&grist_type).copied().unwrap_or_default()
+	}
+
+	/// Puts grist in the cache, and returns how much of it fit. Whatever doesn't fit is lost.
+	pub fn add(&mut self, grist_type: GristType, amount: u32) -> u32 {
+		let held = self.get(grist_type);
+		let added = amount.min(self.capacity().saturating_sub(held));
+		if added > 0 {
+			self.amounts.insert(grist_type, held + added);
+		}
+		added
+	}
+
+	pub fn can_afford(&self, cost: &GristAmounts) -> bool {
+		cost.iter()
+			.all(|(&grist_type, &amount)| self.get(grist_type) >= amount)
+	}
+
+	/// Takes the cost out of the cache, or leaves it alone and returns `false` if there isn't
+	/// enough of everything.
+	pub fn spend(&mut self, cost: &GristAmounts) -> bool {
+		if !self.can_afford(cost) {
+			return false;
+		}
+		for (&grist_type, &amount) in cost {
+			let held = self.get(grist_type) - amount;
+			if held == 0 {
+				self.amounts.remove(&grist_type);
+			} else {
+				self.amounts.insert(grist_type, held);
+			}
+		}
+		true
+	}
+}
+
+/// Scatters this much grist around when the entity gets killed.
+#[derive(Component, Clone, Debug)]
+pub struct DropsGrist(pub GristAmounts);
+
+/// A chunk of grist lying around, waiting to be collected.
+#[derive(Component)]
+pub struct GristPickup {
+	pub grist_type: GristType,
+	pub amount: u32,
+}
+
+#[derive(Resource)]
+pub struct GristAssets {
+	pub mesh: Handle<Mesh>,
+	pub materials: BTreeMap<GristType, Handle<StandardMaterial>>,
+}
+
+/// The most grist that goes into one pickup.
+const GRIST_PER_PICKUP: u32 = 5;
+const GRIST_PICKUP_RADIUS: f32 = 0.08;
+/// How fast dropped grist gets flung out.
+const GRIST_SCATTER_SPEED: f32 = 3.0;
+/// How close grist has to be to the player to start flying toward them.
+const GRIST_MAGNET_RADIUS: f32 = 4.0;
+const GRIST_MAGNET_SPEED: f32 = 8.0;
+/// How quickly grist turns toward the player once it's caught.
+const GRIST_MAGNET_PULL: f32 = 6.0;
+/// How close grist has to get to the player to be collected.
+const GRIST_COLLECT_RADIUS: f32 = 0.75;
+
+#[system(
+	plugin = GristPlugin, schedule = Startup,
+)]
+fn setup_grist_assets(
+	mut commands: Commands,
+	mut meshes: ResMut<Assets<Mesh>>,
+	mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+	commands.insert_resource(GristAssets {
+		mesh: meshes.add(Sphere::new(GRIST_PICKUP_RADIUS).mesh().ico(1).unwrap()),
+		materials: GristType::ALL
+			.into_iter()
+			.map(|grist_type| {
+				let color = grist_type.color();
+				let material = materials.add(StandardMaterial {
+					base_color: color.into(),
+					emissive: LinearRgba::from(color) * 0.5,
+					..default()
+				});
+				(grist_type, material)
+			})
+			.collect(),
+	});
+}
+
+#[system(
+	plugin = GristPlugin, schedule = Update,
+	after = EntityKilledSet,
+)]
+fn drop_grist(
+	mut ev_killed: EventReader<EntityKilled>,
+	mut commands: Commands,
+	droppers: Query<(&GlobalTransform, &DropsGrist)>,
+	grist_assets: Res<GristAssets>,
+	mut rng: GameRng,
+) {
+	let rng = rng.stream("drop_grist");
+
+	for ev in ev_killed.read() {
+		let (transform, drops) = ok_or_continue!(droppers.get(ev.0));
+		let up = transform.up();
+		let position = transform.translation() + up * 0.5;
+
+		for (&grist_type, &amount) in drops.0.iter() {
+			let mut remaining = amount;
+			while remaining > 0 {
+				let amount = remaining.min(GRIST_PER_PICKUP);
+				remaining -= amount;
+
+				// Flung up and out in a random direction
+				let outward = Quat::from_axis_angle(*up, rng.gen_range(0.0..TAU))
+					* up.any_orthonormal_vector();
+				let direction = (*up + outward * rng.gen_range(0.3..1.0)).normalize();
+				commands.spawn((
+					Name::new(format!("{} Grist", grist_type.name())),
+					Transform::from_translation(position),
+					Mesh3d(grist_assets.mesh.clone()),
+					MeshMaterial3d(grist_assets.materials[&grist_type].clone()),
+					Collider::ball(GRIST_PICKUP_RADIUS),
+					AffectedByGravity::default(),
+					Velocity::linear(direction * GRIST_SCATTER_SPEED),
+					GristPickup { grist_type, amount },
+				));
+			}
+		}
+	}
+}
+
+/// Pulls nearby grist toward the player, and collects it once it's close enough.
+#[system(
+	plugin = GristPlugin, schedule = Update,
+)]
+fn collect_grist(
+	mut commands: Commands,
+	mut pickups: Query<(Entity, &GlobalTransform, &mut Velocity, &GristPickup)>,
+	mut player: Query<(&GlobalTransform, &mut GristCache), (With<PlayerBody>, Without<PlayerDead>)>,
+	time: Res<Time>,
+) {
+	let (player_transform, mut cache) = some_or_return!(player.get_single_mut().ok());
+	let pull = (GRIST_MAGNET_PULL * time.delta_secs()).min(1.0);
+
+	for (pickup_entity, transform, mut velocity, pickup) in pickups.iter_mut() {
+		let to_player = player_transform.translation() - transform.translation();
+		let distance = to_player.length();
+
+		if distance <= GRIST_COLLECT_RADIUS {
+			cache.add(pickup.grist_type, pickup.amount);
+			commands.entity(pickup_entity).despawn_recursive();
+		} else if distance <= GRIST_MAGNET_RADIUS {
+			velocity.linvel = velocity
+				.linvel
+				.lerp(to_player / distance * GRIST_MAGNET_SPEED, pull);
+		}
+	}
+}
+
+#[derive(Component)]
+pub struct GristCounter;
+
+#[system(
+	plugin = GristPlugin, schedule = Startup,
+)]
+fn spawn_grist_counter(mut commands: Commands) {
+	commands.spawn((
+		Name::new("Grist Counter"),
+		Node {
+			position_type: PositionType::Absolute,
+			top: Val::Px(35.0),
+			left: Val::Px(5.0),
+			flex_direction: FlexDirection::Column,
+			padding: UiRect::all(Val::Px(4.0)),
+			..default()
+		},
+		BackgroundColor(css::GRAY.with_alpha(0.5).into()),
+		PlayerCameraNode,
+		GristCounter,
+	));
+}
+
+/// Lists how much of each kind of grist the player has, in that grist's color.
+#[system(
+	plugin = GristPlugin, schedule = Update,
+	after = collect_grist,
+)]
+fn update_grist_counter(
+	mut commands: Commands,
+	player: Query<&GristCache, (With<PlayerBody>, Changed<GristCache>)>,
+	counter: Query<Entity, With<GristCounter>>,
+) {
+	let cache = some_or_return!(player.get_single().ok());
+	let counter = some_or_return!(counter.get_single().ok());
+
+	commands
+		.entity(counter)
+		.despawn_descendants()
+		.with_children(|parent| {
+			parent.spawn((
+				Text::new(format!(
+					"Grist (tier {}, {} max)",
+					cache.tier,
+					cache.capacity()
+				)),
+				TextColor(Color::WHITE),
+				TextFont::from_font_size(16.0),
+			));
+			for (&grist_type, &amount) in cache.amounts.iter() {
+				parent.spawn((
+					Text::new(format!("{amount} {}", grist_type.name())),
+					TextColor(grist_type.color().into()),
+					TextFont::from_font_size(16.0),
+				));
+			}
+		});
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn adding_stops_at_capacity() {
+		let mut cache = GristCache::default();
+		assert_eq!(cache.add(GristType::Build, 30), 30);
+		assert_eq!(cache.add(GristType::Build, 30), 20);
+		assert_eq!(cache.get(GristType::Build), cache.capacity());
+
+		cache.tier = 2;
+		assert_eq!(cache.add(GristType::Build, 30), 30);
+	}
+
+	#[test]
+	fn spending_takes_all_or_nothing() {
+		let mut cache = GristCache::default();
+		cache.add(GristType::Build, 10);
+		cache.add(GristType::Shale, 1);
+
+		let too_much = GristAmounts::from([(GristType::Build, 4), (GristType::Shale, 2)]);
+		assert!(!cache.spend(&too_much));
+		assert_eq!(cache.get(GristType::Build), 10);
+
+		let cost = GristAmounts::from([(GristType::Build, 4), (GristType::Shale, 1)]);
+		assert!(cache.spend(&cost));
+		assert_eq!(cache.get(GristType::Build), 6);
+		assert_eq!(cache.get(GristType::Shale), 0);
+		assert!(!cache.amounts.contains_key(&GristType::Shale));
+	}
+}
@@ -0,0 +1,287 @@
+use std::time::Duration;
+
+use bevy::animation::AnimationPlugin;
+use bevy::app::{PluginGroupBuilder, ScheduleRunnerPlugin};
+use bevy::audio::AudioPlugin;
+use bevy::core::FrameCount;
+use bevy::gltf::GltfPlugin;
+use bevy::hierarchy::HierarchyPlugin;
+use bevy::input::InputPlugin;
+use bevy::log::LogPlugin;
+use bevy::prelude::*;
+use bevy::render::mesh::MeshPlugin;
+use bevy::scene::ScenePlugin;
+use bevy::time::TimeUpdateStrategy;
+use bevy::transform::TransformPlugin;
+use bevy::window::ExitCondition;
+use bevy_butler::*;
+
+use crate::util::arg_value;
+
+/// Every frame advances the clock by exactly this much, no matter how fast the machine is.
+pub const HEADLESS_TIMESTEP: Duration = Duration::from_nanos(16_666_667);
+
+/// Nothing keeps the clock in sync with an audio device, so the fray keeps time off
+/// [`HEADLESS_TIMESTEP`] just like everything else.
+#[butler_plugin(build(
+	insert_resource(TimeUpdateStrategy::ManualDuration(HEADLESS_TIMESTEP)),
+	init_asset::<StandardMaterial>(),
+	init_asset::<bevy_hanabi::EffectAsset>(),
+))]
+pub struct HeadlessPlugin;
+
+/// Stops the simulation after this many frames, set with `--frames <count>`.
+#[derive(Resource)]
+#[resource(plugin = HeadlessPlugin, init = ExitAfterFrames::from_args())]
+pub struct ExitAfterFrames(pub Option<u32>);
+
+impl ExitAfterFrames {
+	fn from_args() -> Self {
+		Self(arg_value("--frames").and_then(|frames| frames.parse().ok()))
+	}
+}
+
+/// [`MinimalPlugins`] plus just enough to load the level and run the gameplay plugins.
+///
+/// There's no window, GPU or gamepads, and audio is muted. The asset types that rendering would
+/// normally register come from [`HeadlessPlugin`], so meshes and materials can still be spawned,
+/// they just never get drawn.
+pub fn headless_plugins() -> PluginGroupBuilder {
+	MinimalPlugins
+		.set(ScheduleRunnerPlugin::run_loop(Duration::ZERO))
+		.add(LogPlugin::default())
+		.add(TransformPlugin)
+		.add(HierarchyPlugin)
+		.add(InputPlugin)
+		.add(WindowPlugin {
+			primary_window: None,
+			exit_condition: ExitCondition::DontExit,
+			close_when_requested: false,
+		})
+		.add(AssetPlugin::default())
+		.add(ScenePlugin)
+		.add(ImagePlugin::default())
+		.add(MeshPlugin)
+		.add(AnimationPlugin)
+		.add(GltfPlugin::default())
+		.add(AudioPlugin {
+			global_volume: GlobalVolume::new(0.0),
+			..default()
+		})
+}
+
+#[system(
+	plugin = HeadlessPlugin, schedule = Update,
+)]
+fn exit_after_frames(
+	exit_after: Res<ExitAfterFrames>,
+	frame_count: Res<FrameCount>,
+	mut ev_exit: EventWriter<AppExit>,
+) {
+	if exit_after.0.is_some_and(|frames| frame_count.0 >= frames) {
+		info!("Simulated {} frames", frame_count.0);
+		ev_exit.send(AppExit::Success);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use leafwing_input_manager::prelude::*;
+
+	use super::*;
+	use crate::entity::EntityKilled;
+	use crate::fray::FrayMusic;
+	use crate::menus::MenuNavigationAction;
+	use crate::questing::{Goal, Objective, Quest, QuestId, Quests};
+	use crate::replay::{EventLog, Replay, ReplayPlayback, ReplayRecording, RngSeed};
+
+	fn test_app() -> App {
+		let mut app = App::new();
+		app.add_plugins(headless_plugins().disable::<LogPlugin>());
+		crate::add_game_plugins(&mut app);
+		app.add_plugins(HeadlessPlugin);
+		app
+	}
+
+	fn give_quest(app: &mut App, goal: Goal) -> QuestId {
+		let id: QuestId = rand::random();
+		app.world_mut().resource_mut::<Quests>().0.insert(
+			id,
+			Quest {
+				id,
+				definition: "test".to_owned(),
+				name: "Test".to_owned(),
+				description: String::new(),
+				objectives: vec![Objective {
+					description: String::new(),
+					goal,
+					progress: 0,
+				}],
+				rewards: Vec::new(),
+			},
+		);
+		id
+	}
+
+	#[test]
+	fn killing_imps_completes_kill_quest() {
+		let mut app = test_app();
+		app.update();
+		let quest = give_quest(
+			&mut app,
+			Goal::Kill {
+				target: "Imp".to_owned(),
+				amount: 3,
+			},
+		);
+		let imps = (0..3)
+			.map(|_| app.world_mut().spawn(Name::new("Imp")).id())
+			.collect::<Vec<_>>();
+		let bystander = app.world_mut().spawn(Name::new("Consort")).id();
+
+		app.world_mut().send_event(EntityKilled(imps[0]));
+		app.world_mut().send_event(EntityKilled(imps[1]));
+		app.world_mut().send_event(EntityKilled(bystander));
+		app.update();
+		assert!(!app.world().resource::<Quests>().0[&quest].is_completed());
+
+		app.world_mut().send_event(EntityKilled(imps[2]));
+		app.update();
+		let quests = app.world().resource::<Quests>();
+		assert_eq!(quests.0[&quest].objectives[0].progress, 3);
+		assert!(quests.0[&quest].is_completed());
+		for imp in imps {
+			assert!(app.world().get_entity(imp).is_err());
+		}
+	}
+
+	#[test]
+	fn fray_keeps_time_without_audio() {
+		let mut app = test_app();
+		// The music waits a second before it starts
+		for _ in 0..90 {
+			app.update();
+		}
+
+		let fray_music = app.world_mut().query::<&FrayMusic>().single(app.world());
+		assert!(fray_music.is_keeping_time());
+		assert!(fray_music.beat() > 0.0);
+	}
+
+	#[test]
+	fn simulation_stops_after_frames() {
+		let mut app = test_app();
+		app.insert_resource(ExitAfterFrames(Some(10)));
+
+		for _ in 0..10 {
+			app.update();
+			assert!(app.should_exit().is_none());
+		}
+		app.update();
+		assert_eq!(app.should_exit(), Some(AppExit::Success));
+	}
+
+	/// Kills two imps, with the arrow keys held for a bit in between if `keys`.
+	///
+	/// Returns how far the menu navigation got pushed while they were held.
+	fn play_imp_scenario(app: &mut App, keys: bool) -> Vec2 {
+		app.update();
+		let imps = (0..2)
+			.map(|_| app.world_mut().spawn(Name::new("Imp")).id())
+			.collect::<Vec<_>>();
+
+		let mut navigation = Vec2::ZERO;
+		for frame in 1..30 {
+			match frame {
+				5 if keys => app
+					.world_mut()
+					.resource_mut::<ButtonInput<KeyCode>>()
+					.press(KeyCode::ArrowUp),
+				10 if keys => app
+					.world_mut()
+					.resource_mut::<ButtonInput<KeyCode>>()
+					.release(KeyCode::ArrowUp),
+				12 => {
+					app.world_mut().send_event(EntityKilled(imps[0]));
+				}
+				20 => {
+					app.world_mut().send_event(EntityKilled(imps[1]));
+				}
+				_ => {}
+			}
+			app.update();
+
+			if frame == 7 {
+				navigation = app
+					.world_mut()
+					.query::<&ActionState<MenuNavigationAction>>()
+					.single(app.world())
+					.axis_pair(&MenuNavigationAction::Navigate);
+			}
+		}
+
+		app.world_mut().send_event(AppExit::Success);
+		app.update();
+		navigation
+	}
+
+	/// Deliberately not [`HEADLESS_TIMESTEP`], which the replay has to ignore in favour of this.
+	const RECORDING_TIMESTEP: Duration = Duration::from_millis(10);
+
+	fn record_imp_scenario(path: &std::path::Path) -> Replay {
+		let mut app = test_app();
+		app.insert_resource(TimeUpdateStrategy::ManualDuration(RECORDING_TIMESTEP))
+			.insert_resource(RngSeed(1))
+			.insert_resource(ReplayRecording {
+				path: path.to_owned(),
+				replay: Replay {
+					seed: 1,
+					..default()
+				},
+			})
+			.init_resource::<EventLog>();
+
+		assert_eq!(play_imp_scenario(&mut app, true), Vec2::Y);
+		assert_eq!(app.should_exit(), Some(AppExit::Success));
+		Replay::read(path).unwrap()
+	}
+
+	fn replay_app(replay: Replay) -> App {
+		let mut app = test_app();
+		app.insert_resource(RngSeed(replay.seed))
+			.insert_resource(ReplayPlayback::new(replay))
+			.init_resource::<EventLog>();
+		app
+	}
+
+	#[test]
+	fn replays_match_their_recording() {
+		let path = std::env::temp_dir().join("sbepis_replays_match_their_recording.ron");
+		let replay = record_imp_scenario(&path);
+		assert_eq!(replay.events.len(), 2);
+		assert!(replay
+			.inputs
+			.iter()
+			.any(|input| input.input_manager.as_deref() == Some("Menu Navigation")));
+
+		// No keys this time, the replay presses them instead
+		let mut app = replay_app(replay);
+		assert_eq!(play_imp_scenario(&mut app, false), Vec2::Y);
+		assert_eq!(app.should_exit(), Some(AppExit::Success));
+		assert_eq!(
+			app.world().resource::<Time<Real>>().elapsed(),
+			RECORDING_TIMESTEP * 30
+		);
+	}
+
+	#[test]
+	fn replays_that_diverge_fail() {
+		let path = std::env::temp_dir().join("sbepis_replays_that_diverge_fail.ron");
+		let mut replay = record_imp_scenario(&path);
+		replay.events.pop();
+
+		let mut app = replay_app(replay);
+		play_imp_scenario(&mut app, false);
+		assert_eq!(app.should_exit(), Some(AppExit::error()));
+	}
+}
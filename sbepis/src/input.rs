@@ -68,17 +68,6 @@ pub fn map_event<EventA: Event + MapsToEvent<EventB>, EventB: Event>(
 		ev_b.send(ev.make_event());
 	}
 }
-pub fn map_action_to_event<Action: Actionlike + MapsToEvent<EventB>, EventB: Event>(
-	input: Query<(Entity, &ActionState<Action>)>,
-	mut ev_b: EventWriter<EventB>,
-) {
-	input
-		.iter()
-		.flat_map(|(_, input)| input.get_just_pressed())
-		.for_each(|action| {
-			ev_b.send(action.make_event());
-		});
-}
 
 pub fn button_just_pressed<T: Actionlike + Copy>(
 	action: T,
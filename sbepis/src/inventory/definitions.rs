@@ -0,0 +1,442 @@
+use std::fmt::{self, Display, Formatter};
+use std::ops::{BitAnd, BitOr};
+use std::str::FromStr;
+
+use ::blenvy::blueprints::spawn_from_blueprints::{BlueprintInfo, SpawnBlueprint};
+use bevy::ecs::system::{EntityCommands, SystemParam};
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+use bevy_butler::*;
+use bevy_rapier3d::prelude::Collider;
+use serde::Deserialize;
+
+use crate::alchemy::{alchemize, parse_recipe};
+use crate::grist::GristAmounts;
+use crate::inventory::{InventoryPlugin, Item};
+use crate::{gridbox_material_extra, Box};
+
+#[derive(Asset, Deserialize, TypePath)]
+pub struct ItemDefinitions {
+	items: Vec<ItemDefinition>,
+}
+
+/// Everything there is to know about a kind of item.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ItemDefinition {
+	pub id: String,
+	pub name: String,
+	pub description: String,
+	pub model: ItemModel,
+	/// How wide it is, which sizes its collider and, for cubes, its mesh.
+	pub size: f32,
+	pub icon: String,
+	/// How much of each kind of grist it takes to make one.
+	#[serde(default)]
+	pub grist_cost: GristAmounts,
+	pub captcha: CaptchaCode,
+	/// Multiplied into the color of its cubes.
+	#[serde(default)]
+	pub tint: Option<Srgba>,
+	#[serde(default)]
+	pub weapon: Option<WeaponStats>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub enum ItemModel {
+	/// A Blenvy blueprint, like `"blueprints/rock.glb"`.
+	Blueprint(String),
+	/// A cube with the gridbox texture of this color.
+	Cube(String),
+	/// The first model at full size, with the rest shrunk down and stuck on around it.
+	Fusion(Vec<ItemModel>),
+}
+
+/// How well an item does as a weapon.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct WeaponStats {
+	pub damage: f32,
+	/// How far away it can hit things.
+	pub reach: f32,
+}
+
+/// The characters a captcha code is made of. Each one stands for six bits.
+const CAPTCHA_ALPHABET: &[u8; 64] =
+	b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz!?";
+pub const CAPTCHA_LENGTH: usize = 8;
+
+/// The code on the back of a captchalogue card, which is what alchemy works from. The eight
+/// characters pack into 48 bits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize)]
+#[serde(try_from = "String")]
+pub struct CaptchaCode(u64);
+
+impl CaptchaCode {
+	pub fn bits(self) -> u64 {
+		self.0
+	}
+}
+
+impl BitAnd for CaptchaCode {
+	type Output = Self;
+
+	fn bitand(self, other: Self) -> Self {
+		Self(self.0 & other.0)
+	}
+}
+
+impl BitOr for CaptchaCode {
+	type Output = Self;
+
+	fn bitor(self, other: Self) -> Self {
+		Self(self.0 | other.0)
+	}
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum CaptchaCodeError {
+	Length(usize),
+	Character(char),
+}
+
+impl Display for CaptchaCodeError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		match self {
+			CaptchaCodeError::Length(length) => write!(
+				f,
+				"captcha codes are {CAPTCHA_LENGTH} characters long, not {length}"
+			),
+			CaptchaCodeError::Character(character) => {
+				write!(f, "{character:?} can't be in a captcha code")
+			}
+		}
+	}
+}
+
+impl FromStr for CaptchaCode {
+	type Err = CaptchaCodeError;
+
+	fn from_str(code: &str) -> Result<Self, Self::Err> {
+		let length = code.chars().count();
+		if length != CAPTCHA_LENGTH {
+			return Err(CaptchaCodeError::Length(length));
+		}
+
+		code.chars()
+			.try_fold(0, |bits, character| {
+				let digit = CAPTCHA_ALPHABET
+					.iter()
+					.position(|&c| c as char == character)
+					.ok_or(CaptchaCodeError::Character(character))?;
+				Ok((bits << 6) | digit as u64)
+			})
+			.map(Self)
+	}
+}
+
+impl TryFrom<String> for CaptchaCode {
+	type Error = CaptchaCodeError;
+
+	fn try_from(code: String) -> Result<Self, Self::Error> {
+		code.parse()
+	}
+}
+
+impl Display for CaptchaCode {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		for index in (0..CAPTCHA_LENGTH).rev() {
+			let digit = (self.0 >> (index * 6)) & 0b111111;
+			write!(f, "{}", CAPTCHA_ALPHABET[digit as usize] as char)?;
+		}
+		Ok(())
+	}
+}
+
+#[derive(Resource, Default)]
+#[resource(plugin = InventoryPlugin)]
+pub struct ItemRegistry {
+	definitions: Vec<ItemDefinition>,
+	/// Items that have been made up by alchemy so far. They don't need saving, since alchemizing
+	/// the same things always makes the same item.
+	alchemized: Vec<ItemDefinition>,
+}
+
+impl ItemRegistry {
+	pub fn new(definitions: Vec<ItemDefinition>) -> Self {
+		Self {
+			definitions,
+			alchemized: Vec::new(),
+		}
+	}
+
+	pub fn get(&self, id: &str) -> Option<&ItemDefinition> {
+		self.definitions
+			.iter()
+			.chain(self.alchemized.iter())
+			.find(|definition| definition.id == id)
+	}
+
+	/// Like [`Self::get`], but recipes like `"orange_cube&&rock"` get alchemized if they haven't
+	/// been yet.
+	pub fn resolve(&mut self, id: &str) -> Option<&ItemDefinition> {
+		if self.get(id).is_some() {
+			return self.get(id);
+		}
+
+		let (first, operation, second) = parse_recipe(id)?;
+		let first = self.resolve(first)?.clone();
+		let second = self.resolve(second)?.clone();
+
+		// Anything with a handmade definition for the code comes out as that
+		let code = operation.apply(first.captcha, second.captcha);
+		if let Some(index) = self
+			.definitions
+			.iter()
+			.position(|definition| definition.captcha == code)
+		{
+			return self.definitions.get(index);
+		}
+
+		let result = alchemize(&first, &second, operation);
+		let result_id = result.id.clone();
+		if self.get(&result_id).is_none() {
+			self.alchemized.push(result);
+		}
+		self.get(&result_id)
+	}
+
+	/// Only the items from the definitions, not ones that have been alchemized.
+	pub fn iter(&self) -> impl Iterator<Item = &ItemDefinition> {
+		self.definitions.iter()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.definitions.is_empty()
+	}
+}
+
+#[derive(Resource)]
+pub struct ItemDefinitionAssets {
+	pub definitions: Handle<ItemDefinitions>,
+}
+
+#[system(
+	plugin = InventoryPlugin, schedule = Startup,
+)]
+fn load_item_definitions(mut commands: Commands, asset_server: Res<AssetServer>) {
+	commands.insert_resource(ItemDefinitionAssets {
+		definitions: asset_server.load("sburb.items.ron"),
+	});
+}
+
+#[system(
+	plugin = InventoryPlugin, schedule = Update,
+	run_if = on_event::<AssetEvent<ItemDefinitions>>,
+)]
+fn update_item_registry(
+	mut ev_asset: EventReader<AssetEvent<ItemDefinitions>>,
+	assets: Res<Assets<ItemDefinitions>>,
+	item_definition_assets: Res<ItemDefinitionAssets>,
+	mut registry: ResMut<ItemRegistry>,
+) {
+	for ev in ev_asset.read() {
+		if !ev.is_loaded_with_dependencies(&item_definition_assets.definitions)
+			&& !ev.is_modified(&item_definition_assets.definitions)
+		{
+			continue;
+		}
+
+		let definitions = assets
+			.get(&item_definition_assets.definitions)
+			.expect("Item definitions should be loaded by now");
+		*registry = ItemRegistry::new(definitions.items.clone());
+
+		let mut ids = HashSet::new();
+		let mut captchas = HashSet::new();
+		for definition in registry.iter() {
+			if !ids.insert(&definition.id) {
+				warn!("There's more than one item called \"{}\"", definition.id);
+			}
+			if !captchas.insert(definition.captcha) {
+				warn!(
+					"Item \"{}\" has the same captcha code as another item, {}",
+					definition.id, definition.captcha
+				);
+			}
+		}
+	}
+}
+
+/// Spawns items by id. Quest drops, rewards, spawners, commands and loaded saves all go through
+/// this, so an item comes out the same wherever it came from.
+#[derive(SystemParam)]
+pub struct ItemSpawner<'w, 's> {
+	commands: Commands<'w, 's>,
+	registry: ResMut<'w, ItemRegistry>,
+	asset_server: Res<'w, AssetServer>,
+	meshes: ResMut<'w, Assets<Mesh>>,
+	materials: ResMut<'w, Assets<StandardMaterial>>,
+}
+
+impl ItemSpawner<'_, '_> {
+	/// Spawns an item, alchemizing it first if it's a recipe.
+	pub fn spawn(&mut self, id: &str, transform: Transform) -> Option<EntityCommands<'_>> {
+		Self::definition(&mut self.registry, id)?;
+		let entity = self.commands.spawn_empty().id();
+		self.insert(entity, id, transform)
+	}
+
+	/// Turns an entity that's already been spawned, like one a [`Spawner`] set aside, into an item.
+	///
+	/// [`Spawner`]: crate::entity::spawner::Spawner
+	pub fn insert(
+		&mut self,
+		entity: Entity,
+		id: &str,
+		transform: Transform,
+	) -> Option<EntityCommands<'_>> {
+		let definition = Self::definition(&mut self.registry, id)?;
+
+		let half_size = definition.size / 2.0;
+		self.commands.entity(entity).insert((
+			transform,
+			Name::new(definition.name.clone()),
+			Box,
+			Collider::cuboid(half_size, half_size, half_size),
+			Item {
+				id: definition.id.clone(),
+				icon: self.asset_server.load(&definition.icon),
+			},
+		));
+		let model = ItemModelSpawner {
+			commands: &mut self.commands,
+			asset_server: &self.asset_server,
+			meshes: &mut self.meshes,
+			materials: &mut self.materials,
+			size: definition.size,
+			tint: definition.tint.unwrap_or(Srgba::WHITE),
+		};
+		model.insert(entity, &definition.model);
+		Some(self.commands.entity(entity))
+	}
+
+	/// Looks up an item without spawning it, alchemizing it first if it's a recipe.
+	pub fn resolve(&mut self, id: &str) -> Option<&ItemDefinition> {
+		Self::definition(&mut self.registry, id)
+	}
+
+	pub fn registry(&self) -> &ItemRegistry {
+		&self.registry
+	}
+
+	fn definition<'a>(registry: &'a mut ItemRegistry, id: &str) -> Option<&'a ItemDefinition> {
+		let definition = registry.resolve(id);
+		if definition.is_none() {
+			warn!("There's no item called {id:?}");
+		}
+		definition
+	}
+}
+
+/// How much smaller the extra parts of a [`ItemModel::Fusion`] are than the first.
+const FUSION_PART_SCALE: f32 = 0.6;
+
+struct ItemModelSpawner<'a, 'w, 's> {
+	commands: &'a mut Commands<'w, 's>,
+	asset_server: &'a AssetServer,
+	meshes: &'a mut Assets<Mesh>,
+	materials: &'a mut Assets<StandardMaterial>,
+	size: f32,
+	tint: Srgba,
+}
+
+impl ItemModelSpawner<'_, '_, '_> {
+	fn insert(mut self, entity: Entity, model: &ItemModel) {
+		self.insert_part(entity, model);
+	}
+
+	fn insert_part(&mut self, entity: Entity, model: &ItemModel) {
+		match model {
+			ItemModel::Blueprint(path) => {
+				self.commands
+					.entity(entity)
+					.insert((BlueprintInfo::from_path(path), SpawnBlueprint));
+			}
+			ItemModel::Cube(color) => {
+				let mesh = self.meshes.add(Cuboid::from_length(self.size));
+				let material = gridbox_material_extra(
+					color,
+					self.materials,
+					self.asset_server,
+					StandardMaterial {
+						base_color: self.tint.into(),
+						..default()
+					},
+				);
+				self.commands
+					.entity(entity)
+					.insert((Mesh3d(mesh), MeshMaterial3d(material)));
+			}
+			ItemModel::Fusion(parts) => {
+				let Some((first, rest)) = parts.split_first() else {
+					return;
+				};
+				self.insert_part(entity, first);
+
+				// Spread around the first part so they don't all end up inside each other
+				for (index, part) in rest.iter().enumerate() {
+					let angle = index as f32 / rest.len() as f32 * std::f32::consts::TAU;
+					let offset = Vec3::new(angle.cos(), 0.5, angle.sin()) * self.size * 0.5;
+					let child = self
+						.commands
+						.spawn((
+							Transform::from_translation(offset)
+								.with_scale(Vec3::splat(FUSION_PART_SCALE)),
+							Visibility::default(),
+						))
+						.set_parent(entity)
+						.id();
+					self.insert_part(child, part);
+				}
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn captcha_codes_round_trip() {
+		for code in ["00000000", "????????", "Or4nG3!x", "aZ9?b!0q"] {
+			assert_eq!(code.parse::<CaptchaCode>().unwrap().to_string(), code);
+		}
+	}
+
+	#[test]
+	fn captcha_codes_pack_six_bits_per_character() {
+		assert_eq!("00000000".parse(), Ok(CaptchaCode(0)));
+		assert_eq!("00000010".parse(), Ok(CaptchaCode(64)));
+		assert_eq!("????????".parse(), Ok(CaptchaCode((1 << 48) - 1)));
+	}
+
+	#[test]
+	fn captcha_codes_must_be_eight_characters() {
+		assert_eq!(
+			"short".parse::<CaptchaCode>(),
+			Err(CaptchaCodeError::Length(5))
+		);
+		assert_eq!(
+			"far too long".parse::<CaptchaCode>(),
+			Err(CaptchaCodeError::Length(12))
+		);
+	}
+
+	#[test]
+	fn captcha_codes_only_use_the_alphabet() {
+		assert_eq!(
+			"Or4nG3_x".parse::<CaptchaCode>(),
+			Err(CaptchaCodeError::Character('_'))
+		);
+	}
+}
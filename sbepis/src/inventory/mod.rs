@@ -1,33 +1,169 @@
 use bevy::prelude::*;
 use bevy_butler::*;
+use bevy_common_assets::ron::RonAssetPlugin;
 use bevy_rapier3d::prelude::*;
+use definitions::*;
+use modus::*;
 use screen::*;
 
+use crate::camera::PlayerCamera;
+use crate::entity::spawner::{Spawner, SpawnerActivated, SpawnerActivatedSet};
+use crate::gravity::AffectedByGravity;
 use crate::menus::{MenuManipulationSet, OpenMenuBinding};
+use crate::ok_or_continue;
 use crate::player_controller::camera_controls::InteractedWithSet;
 use crate::player_controller::PlayerAction;
+use crate::util::arg_value;
 
+pub mod definitions;
+pub mod modus;
 mod screen;
 
-#[butler_plugin]
+pub use definitions::{ItemRegistry, ItemSpawner};
+
+#[butler_plugin(build(
+	add_plugins(RonAssetPlugin::<ItemDefinitions>::new(&["items.ron"])),
+))]
 pub struct InventoryPlugin;
 
-#[derive(Component, Default)]
+/// How many cards a new captchalogue deck starts with.
+const DEFAULT_CARDS: usize = 8;
+/// How fast items ejected from a full deck get flung out in front of whoever was holding them.
+const EJECT_SPEED: f32 = 3.0;
+/// How far in front of the player dropped items land.
+const DROP_DISTANCE: f32 = 0.75;
+const THROW_SPEED: f32 = 10.0;
+/// Where equipped items are held, relative to the camera. Off to the side so they don't get in the
+/// way of interacting with things.
+const HAND_OFFSET: Vec3 = Vec3::new(-0.3, -0.25, -0.5);
+
+/// A captchalogue deck, which holds as many items as it has cards, in whatever arrangement its
+/// [`FetchModus`] wants.
+#[derive(Component)]
 pub struct Inventory {
-	pub items: Vec<Entity>,
+	pub cards: usize,
+	modus: Box<dyn FetchModus>,
+}
+
+impl Inventory {
+	pub fn new(cards: usize, modus: Box<dyn FetchModus>) -> Self {
+		Self { cards, modus }
+	}
+
+	/// A deck set up with `--cards <count>` and `--modus <name>`, or eight cards in a stack.
+	pub fn from_args() -> Self {
+		let cards = arg_value("--cards")
+			.and_then(|cards| cards.parse().ok())
+			.unwrap_or(DEFAULT_CARDS);
+		let modus = arg_value("--modus")
+			.and_then(|name| modus_by_name(&name))
+			.unwrap_or_else(|| Box::new(StackModus::default()));
+		Self::new(cards, modus)
+	}
+
+	pub fn modus_name(&self) -> &'static str {
+		self.modus.name()
+	}
+
+	/// Puts an item on a card, returning anything that got ejected to make room.
+	pub fn captchalogue(&mut self, item: Entity, name: impl Into<String>) -> Vec<Entity> {
+		self.modus.insert(
+			Card {
+				item,
+				name: name.into(),
+			},
+			self.cards,
+		)
+	}
+
+	pub fn can_retrieve(&self, item: Entity) -> bool {
+		self.modus.can_retrieve(item)
+	}
+
+	/// Takes an item out whether the modus allows it or not.
+	pub fn remove(&mut self, item: Entity) -> bool {
+		self.modus.remove(item)
+	}
+
+	pub fn contains(&self, item: Entity) -> bool {
+		self.items().contains(&item)
+	}
+
+	/// Everything in the deck, in the order to captchalogue it back in.
+	pub fn items(&self) -> Vec<Entity> {
+		self.modus.items()
+	}
+
+	/// The cards on the inventory screen, row by row. Empty cards are `None`.
+	pub fn layout(&self) -> Vec<Vec<Option<Entity>>> {
+		self.modus.layout(self.cards)
+	}
+
+	pub fn clear(&mut self) {
+		self.modus.clear();
+	}
 }
 
 #[derive(Component)]
 pub struct Item {
+	/// Which [`ItemDefinition`] it was spawned from.
+	pub id: String,
 	pub icon: Handle<Image>,
 }
 
+/// Makes a [`Spawner`] put out items with this id.
+#[derive(Component)]
+pub struct SpawnsItem(pub String);
+
 #[derive(Event)]
 #[event(plugin = InventoryPlugin)]
 pub struct ItemPickedUp(pub Entity);
 #[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
 pub struct ItemPickedUpSet;
 
+/// An item has left an inventory, whether it was taken out, ejected or used up.
+#[derive(Event)]
+#[event(plugin = InventoryPlugin)]
+pub struct ItemRemoved(pub Entity);
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ItemRemovedSet;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ItemAction {
+	/// Set it down in front of the player.
+	Drop,
+	/// Fling it where the player's looking.
+	Throw,
+	/// Hold it in the player's hand.
+	Equip,
+}
+
+impl ItemAction {
+	pub const ALL: [ItemAction; 3] = [ItemAction::Drop, ItemAction::Throw, ItemAction::Equip];
+
+	pub fn label(&self) -> &'static str {
+		match self {
+			ItemAction::Drop => "Drop",
+			ItemAction::Throw => "Throw",
+			ItemAction::Equip => "Equip",
+		}
+	}
+}
+
+/// Takes an item out of the player's inventory, if its modus allows it.
+#[derive(Event)]
+#[event(plugin = InventoryPlugin)]
+pub struct RetrieveItem {
+	pub item: Entity,
+	pub action: ItemAction,
+}
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RetrieveItemSet;
+
+/// An item held in the player's hand instead of sitting in their inventory.
+#[derive(Component)]
+pub struct EquippedItem;
+
 type InteractedWithItemSet = InteractedWithSet<Item>;
 
 #[system(
@@ -41,28 +177,185 @@ use crate::prelude::interact_with;
 	plugin = InventoryPlugin, schedule = Update,
 	after = InteractedWithItemSet::default(),
 	in_set = ItemPickedUpSet,
+	in_set = ItemRemovedSet,
 	in_set = InventoryChangedSet,
 )]
 fn pick_up_items(
 	mut ev_interact: EventReader<InteractedWith<Item>>,
 	mut commands: Commands,
-	mut player: Query<(Entity, &mut Inventory)>,
+	mut player: Query<(Entity, &mut Inventory, &Transform)>,
+	names: Query<&Name>,
 	mut ev_picked_up: EventWriter<ItemPickedUp>,
+	mut ev_removed: EventWriter<ItemRemoved>,
 	mut ev_inventory_changed: EventWriter<InventoryChanged>,
 ) {
 	for ev in ev_interact.read() {
-		let (inventory_entity, mut inventory) = player.single_mut();
-		inventory.items.push(ev.0);
+		let (inventory_entity, mut inventory, transform) = player.single_mut();
+		let name = names.get(ev.0).map_or("Item", Name::as_str);
+		let ejected = inventory.captchalogue(ev.0, name);
 		commands
 			.entity(ev.0)
 			.remove::<RigidBody>()
 			.insert(Visibility::Hidden)
 			.insert(ColliderDisabled);
 		ev_picked_up.send(ItemPickedUp(ev.0));
+
+		for item in ejected {
+			release_item(
+				&mut commands,
+				item,
+				Transform::from_translation(transform.translation + transform.forward() * 0.5),
+				(transform.forward() + transform.up()) * EJECT_SPEED,
+			);
+			ev_removed.send(ItemRemoved(item));
+		}
+
+		ev_inventory_changed.send(InventoryChanged(inventory_entity));
+	}
+}
+
+/// Puts an item that's been taken out of a deck or hand back into the world as a physics object.
+pub fn release_item(commands: &mut Commands, item: Entity, transform: Transform, velocity: Vec3) {
+	commands
+		.entity(item)
+		.remove_parent()
+		.remove::<(ColliderDisabled, Sensor, EquippedItem)>()
+		.insert((
+			RigidBody::Dynamic,
+			AffectedByGravity::default(),
+			Visibility::Inherited,
+			transform,
+			Velocity::linear(velocity),
+		));
+}
+
+/// Puts an item in the player's hand. It keeps its collider, but as a sensor so it doesn't shove
+/// the player around.
+fn equip_item(commands: &mut Commands, item: Entity, camera: Entity) {
+	commands
+		.entity(item)
+		.remove::<ColliderDisabled>()
+		.insert((
+			RigidBody::KinematicPositionBased,
+			AffectedByGravity::default(),
+			Sensor,
+			Visibility::Inherited,
+			Transform::from_translation(HAND_OFFSET),
+			EquippedItem,
+		))
+		.set_parent(camera);
+}
+
+#[system(
+	plugin = InventoryPlugin, schedule = Update,
+	after = RetrieveItemSet,
+	in_set = ItemRemovedSet,
+	in_set = InventoryChangedSet,
+)]
+fn retrieve_items(
+	mut ev_retrieve: EventReader<RetrieveItem>,
+	mut commands: Commands,
+	mut player: Query<(Entity, &mut Inventory, &Transform)>,
+	camera: Query<(Entity, &GlobalTransform), With<PlayerCamera>>,
+	equipped_items: Query<Entity, With<EquippedItem>>,
+	mut ev_removed: EventWriter<ItemRemoved>,
+	mut ev_inventory_changed: EventWriter<InventoryChanged>,
+) {
+	for RetrieveItem { item, action } in ev_retrieve.read() {
+		let (inventory_entity, mut inventory, transform) = player.single_mut();
+		if !inventory.can_retrieve(*item) {
+			continue;
+		}
+		inventory.remove(*item);
+
+		let (camera_entity, camera_transform) = camera.single();
+		let in_front = Transform::from_translation(
+			transform.translation + transform.forward() * DROP_DISTANCE,
+		);
+		match action {
+			ItemAction::Drop => release_item(&mut commands, *item, in_front, Vec3::ZERO),
+			ItemAction::Throw => release_item(
+				&mut commands,
+				*item,
+				Transform::from_translation(
+					camera_transform.translation() + camera_transform.forward() * DROP_DISTANCE,
+				),
+				camera_transform.forward() * THROW_SPEED,
+			),
+			ItemAction::Equip => {
+				// Only one hand to hold things in
+				for equipped_item in equipped_items.iter() {
+					release_item(&mut commands, equipped_item, in_front, Vec3::ZERO);
+				}
+				equip_item(&mut commands, *item, camera_entity);
+			}
+		}
+
+		ev_removed.send(ItemRemoved(*item));
 		ev_inventory_changed.send(InventoryChanged(inventory_entity));
 	}
 }
 
+#[system(
+	plugin = InventoryPlugin, schedule = Update,
+	in_set = ItemRemovedSet,
+	in_set = InventoryChangedSet,
+)]
+fn forget_despawned_items(
+	mut inventories: Query<(Entity, &mut Inventory)>,
+	items: Query<(), With<Item>>,
+	mut ev_removed: EventWriter<ItemRemoved>,
+	mut ev_inventory_changed: EventWriter<InventoryChanged>,
+) {
+	for (inventory_entity, mut inventory) in inventories.iter_mut() {
+		let despawned = inventory
+			.items()
+			.into_iter()
+			.filter(|item| items.get(*item).is_err())
+			.collect::<Vec<_>>();
+		if despawned.is_empty() {
+			continue;
+		}
+
+		for item in despawned {
+			inventory.remove(item);
+			ev_removed.send(ItemRemoved(item));
+		}
+		ev_inventory_changed.send(InventoryChanged(inventory_entity));
+	}
+}
+
+#[system(
+	plugin = InventoryPlugin, schedule = Update,
+	after = SpawnerActivatedSet,
+)]
+fn spawn_items_from_spawners(
+	mut ev_spawner: EventReader<SpawnerActivated>,
+	spawners: Query<&SpawnsItem>,
+	mut item_spawner: ItemSpawner,
+) {
+	for ev in ev_spawner.read() {
+		let SpawnsItem(id) = ok_or_continue!(spawners.get(ev.spawner));
+		item_spawner.insert(ev.entity, id, Transform::from_translation(ev.position));
+	}
+}
+
+/// Picked up items stop counting against their spawner, so it can put out more.
+#[system(
+	plugin = InventoryPlugin, schedule = Update,
+	after = ItemPickedUpSet,
+)]
+fn free_spawner_slots(
+	mut ev_picked_up: EventReader<ItemPickedUp>,
+	mut spawners: Query<&mut Spawner>,
+) {
+	for ItemPickedUp(item) in ev_picked_up.read() {
+		for mut spawner in spawners.iter_mut() {
+			spawner.entities.remove(item);
+		}
+	}
+}
+
 pub struct OpenInventoryBinding;
 impl OpenMenuBinding for OpenInventoryBinding {
 	type Action = PlayerAction;
@@ -0,0 +1,469 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+
+/// How many cards the array and hash map modi lay out side by side before starting a new row.
+const ROW_LENGTH: usize = 5;
+
+/// An item that's been captchalogued, with the name some modi sort it by.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Card {
+	pub item: Entity,
+	pub name: String,
+}
+
+/// How a captchalogue deck decides where items go, which ones can come back out, and which ones
+/// get pushed out when it's full.
+pub trait FetchModus: Send + Sync + 'static {
+	fn name(&self) -> &'static str;
+	/// Puts an item on a card, returning anything that got ejected to make room.
+	fn insert(&mut self, card: Card, cards: usize) -> Vec<Entity>;
+	/// Whether the modus lets this item be taken out right now.
+	fn can_retrieve(&self, item: Entity) -> bool;
+	/// Takes an item out whether the modus allows it or not, like when it's handed over to a quest
+	/// giver.
+	fn remove(&mut self, item: Entity) -> bool;
+	/// Everything in the deck, in the order to captchalogue it back in.
+	fn items(&self) -> Vec<Entity>;
+	/// How the cards are shown on the inventory screen, row by row. Empty cards are `None`.
+	fn layout(&self, cards: usize) -> Vec<Vec<Option<Entity>>>;
+	fn clear(&mut self);
+}
+
+/// Looks a modus up by name, like `"queue"` or `"hash map"`.
+pub fn modus_by_name(name: &str) -> Option<Box<dyn FetchModus>> {
+	match name.to_lowercase().replace(['_', '-', ' '], "").as_str() {
+		"stack" => Some(Box::new(StackModus::default())),
+		"queue" => Some(Box::new(QueueModus::default())),
+		"array" => Some(Box::new(ArrayModus::default())),
+		"hashmap" | "hash" => Some(Box::new(HashMapModus::default())),
+		"tree" => Some(Box::new(TreeModus::default())),
+		_ => None,
+	}
+}
+
+/// Only the top card can be taken out, and a full deck loses its bottom card.
+#[derive(Default)]
+pub struct StackModus {
+	/// Bottom first.
+	cards: Vec<Card>,
+}
+
+impl FetchModus for StackModus {
+	fn name(&self) -> &'static str {
+		"Stack"
+	}
+
+	fn insert(&mut self, card: Card, cards: usize) -> Vec<Entity> {
+		self.cards.push(card);
+		let overflow = self.cards.len().saturating_sub(cards);
+		self.cards.drain(..overflow).map(|card| card.item).collect()
+	}
+
+	fn can_retrieve(&self, item: Entity) -> bool {
+		self.cards.last().is_some_and(|card| card.item == item)
+	}
+
+	fn remove(&mut self, item: Entity) -> bool {
+		remove_card(&mut self.cards, item)
+	}
+
+	fn items(&self) -> Vec<Entity> {
+		self.cards.iter().map(|card| card.item).collect()
+	}
+
+	fn layout(&self, cards: usize) -> Vec<Vec<Option<Entity>>> {
+		let mut top_first = self.cards.iter().rev().map(|card| card.item);
+		(0..cards.max(self.cards.len()))
+			.map(|_| vec![top_first.next()])
+			.collect()
+	}
+
+	fn clear(&mut self) {
+		self.cards.clear();
+	}
+}
+
+/// Only the oldest card can be taken out, and a full deck loses it to make room.
+#[derive(Default)]
+pub struct QueueModus {
+	/// Oldest first.
+	cards: VecDeque<Card>,
+}
+
+impl FetchModus for QueueModus {
+	fn name(&self) -> &'static str {
+		"Queue"
+	}
+
+	fn insert(&mut self, card: Card, cards: usize) -> Vec<Entity> {
+		self.cards.push_back(card);
+		let overflow = self.cards.len().saturating_sub(cards);
+		self.cards.drain(..overflow).map(|card| card.item).collect()
+	}
+
+	fn can_retrieve(&self, item: Entity) -> bool {
+		self.cards.front().is_some_and(|card| card.item == item)
+	}
+
+	fn remove(&mut self, item: Entity) -> bool {
+		let Some(index) = self.cards.iter().position(|card| card.item == item) else {
+			return false;
+		};
+		self.cards.remove(index);
+		true
+	}
+
+	fn items(&self) -> Vec<Entity> {
+		self.cards.iter().map(|card| card.item).collect()
+	}
+
+	fn layout(&self, cards: usize) -> Vec<Vec<Option<Entity>>> {
+		let mut oldest_first = self.cards.iter().map(|card| card.item);
+		vec![(0..cards.max(self.cards.len()))
+			.map(|_| oldest_first.next())
+			.collect()]
+	}
+
+	fn clear(&mut self) {
+		self.cards.clear();
+	}
+}
+
+/// Any card can be taken out. Items go on the first empty card, and a full deck loses its first
+/// card to make room.
+#[derive(Default)]
+pub struct ArrayModus {
+	slots: Vec<Option<Card>>,
+}
+
+impl FetchModus for ArrayModus {
+	fn name(&self) -> &'static str {
+		"Array"
+	}
+
+	fn insert(&mut self, card: Card, cards: usize) -> Vec<Entity> {
+		let mut ejected = resize_slots(&mut self.slots, cards);
+		if self.slots.is_empty() {
+			ejected.push(card.item);
+			return ejected;
+		}
+		let index = self.slots.iter().position(Option::is_none).unwrap_or(0);
+		ejected.extend(self.slots[index].replace(card).map(|card| card.item));
+		ejected
+	}
+
+	fn can_retrieve(&self, item: Entity) -> bool {
+		slots_contain(&self.slots, item)
+	}
+
+	fn remove(&mut self, item: Entity) -> bool {
+		remove_slot(&mut self.slots, item)
+	}
+
+	fn items(&self) -> Vec<Entity> {
+		slot_items(&self.slots)
+	}
+
+	fn layout(&self, cards: usize) -> Vec<Vec<Option<Entity>>> {
+		slot_layout(&self.slots, cards)
+	}
+
+	fn clear(&mut self) {
+		self.slots.clear();
+	}
+}
+
+/// Any card can be taken out. Each item goes on the card its name hashes to, ejecting whatever
+/// was already there.
+#[derive(Default)]
+pub struct HashMapModus {
+	slots: Vec<Option<Card>>,
+}
+
+impl HashMapModus {
+	/// Vowels are worth one and consonants two, so names that sound alike tend to collide.
+	fn hash(name: &str) -> usize {
+		name.chars()
+			.filter(char::is_ascii_alphabetic)
+			.map(|letter| match letter.to_ascii_lowercase() {
+				'a' | 'e' | 'i' | 'o' | 'u' => 1,
+				_ => 2,
+			})
+			.sum()
+	}
+}
+
+impl FetchModus for HashMapModus {
+	fn name(&self) -> &'static str {
+		"Hash Map"
+	}
+
+	fn insert(&mut self, card: Card, cards: usize) -> Vec<Entity> {
+		let mut ejected = resize_slots(&mut self.slots, cards);
+		if self.slots.is_empty() {
+			ejected.push(card.item);
+			return ejected;
+		}
+		let index = Self::hash(&card.name) % self.slots.len();
+		ejected.extend(self.slots[index].replace(card).map(|card| card.item));
+		ejected
+	}
+
+	fn can_retrieve(&self, item: Entity) -> bool {
+		slots_contain(&self.slots, item)
+	}
+
+	fn remove(&mut self, item: Entity) -> bool {
+		remove_slot(&mut self.slots, item)
+	}
+
+	fn items(&self) -> Vec<Entity> {
+		slot_items(&self.slots)
+	}
+
+	fn layout(&self, cards: usize) -> Vec<Vec<Option<Entity>>> {
+		slot_layout(&self.slots, cards)
+	}
+
+	fn clear(&mut self) {
+		self.slots.clear();
+	}
+}
+
+/// Items are sorted into a binary tree by name, and only leaves can be taken out. A full deck
+/// loses its root to make room.
+#[derive(Default)]
+pub struct TreeModus {
+	/// Oldest first, which makes the first card the root.
+	cards: Vec<Card>,
+}
+
+struct TreeNode {
+	card: usize,
+	depth: usize,
+	/// Where the node sits left to right, if every level were full.
+	position: usize,
+	children: [Option<usize>; 2],
+}
+
+impl TreeModus {
+	fn nodes(&self) -> Vec<TreeNode> {
+		let mut nodes: Vec<TreeNode> = Vec::with_capacity(self.cards.len());
+		for (card_index, card) in self.cards.iter().enumerate() {
+			let mut depth = 0;
+			let mut position = 0;
+			let mut parent = None;
+			let mut current = (!nodes.is_empty()).then_some(0);
+			while let Some(node_index) = current {
+				let side = (card.name >= self.cards[nodes[node_index].card].name) as usize;
+				depth += 1;
+				position = position * 2 + side;
+				parent = Some((node_index, side));
+				current = nodes[node_index].children[side];
+			}
+
+			if let Some((parent, side)) = parent {
+				nodes[parent].children[side] = Some(nodes.len());
+			}
+			nodes.push(TreeNode {
+				card: card_index,
+				depth,
+				position,
+				children: [None, None],
+			});
+		}
+		nodes
+	}
+}
+
+impl FetchModus for TreeModus {
+	fn name(&self) -> &'static str {
+		"Tree"
+	}
+
+	fn insert(&mut self, card: Card, cards: usize) -> Vec<Entity> {
+		self.cards.push(card);
+		let overflow = self.cards.len().saturating_sub(cards);
+		// Dropping the oldest cards rebuilds the tree around the next one as the root
+		self.cards.drain(..overflow).map(|card| card.item).collect()
+	}
+
+	fn can_retrieve(&self, item: Entity) -> bool {
+		self.nodes().iter().any(|node| {
+			self.cards[node.card].item == item && node.children.iter().all(Option::is_none)
+		})
+	}
+
+	fn remove(&mut self, item: Entity) -> bool {
+		remove_card(&mut self.cards, item)
+	}
+
+	fn items(&self) -> Vec<Entity> {
+		self.cards.iter().map(|card| card.item).collect()
+	}
+
+	fn layout(&self, cards: usize) -> Vec<Vec<Option<Entity>>> {
+		let mut nodes = self.nodes();
+		nodes.sort_by_key(|node| (node.depth, node.position));
+
+		let mut rows: Vec<Vec<Option<Entity>>> = Vec::new();
+		for node in nodes {
+			if rows.len() <= node.depth {
+				rows.push(Vec::new());
+			}
+			rows[node.depth].push(Some(self.cards[node.card].item));
+		}
+
+		let empty_cards = cards.saturating_sub(self.cards.len());
+		if empty_cards > 0 {
+			rows.push(vec![None; empty_cards]);
+		}
+		rows
+	}
+
+	fn clear(&mut self) {
+		self.cards.clear();
+	}
+}
+
+fn remove_card(cards: &mut Vec<Card>, item: Entity) -> bool {
+	let Some(index) = cards.iter().position(|card| card.item == item) else {
+		return false;
+	};
+	cards.remove(index);
+	true
+}
+
+/// Makes sure there's one slot per card, returning anything on cards that aren't there anymore.
+fn resize_slots(slots: &mut Vec<Option<Card>>, cards: usize) -> Vec<Entity> {
+	let ejected = slots
+		.drain(cards.min(slots.len())..)
+		.flatten()
+		.map(|card| card.item)
+		.collect();
+	slots.resize(cards, None);
+	ejected
+}
+
+fn slots_contain(slots: &[Option<Card>], item: Entity) -> bool {
+	slots.iter().flatten().any(|card| card.item == item)
+}
+
+fn remove_slot(slots: &mut [Option<Card>], item: Entity) -> bool {
+	let Some(slot) = slots
+		.iter_mut()
+		.find(|slot| slot.as_ref().is_some_and(|card| card.item == item))
+	else {
+		return false;
+	};
+	*slot = None;
+	true
+}
+
+fn slot_items(slots: &[Option<Card>]) -> Vec<Entity> {
+	slots.iter().flatten().map(|card| card.item).collect()
+}
+
+fn slot_layout(slots: &[Option<Card>], cards: usize) -> Vec<Vec<Option<Entity>>> {
+	let mut slots = slots
+		.iter()
+		.map(|slot| slot.as_ref().map(|card| card.item))
+		.collect::<Vec<_>>();
+	slots.resize(cards.max(slots.len()), None);
+	slots.chunks(ROW_LENGTH).map(|row| row.to_vec()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn card(index: u32, name: &str) -> Card {
+		Card {
+			item: Entity::from_raw(index),
+			name: name.to_owned(),
+		}
+	}
+
+	fn item(index: u32) -> Entity {
+		Entity::from_raw(index)
+	}
+
+	#[test]
+	fn stack_ejects_the_bottom_card() {
+		let mut modus = StackModus::default();
+		assert!(modus.insert(card(0, "a"), 2).is_empty());
+		assert!(modus.insert(card(1, "b"), 2).is_empty());
+		assert_eq!(modus.insert(card(2, "c"), 2), vec![item(0)]);
+		assert!(modus.can_retrieve(item(2)));
+		assert!(!modus.can_retrieve(item(1)));
+		assert_eq!(
+			modus.layout(3),
+			vec![vec![Some(item(2))], vec![Some(item(1))], vec![None]]
+		);
+	}
+
+	#[test]
+	fn queue_ejects_the_oldest_card() {
+		let mut modus = QueueModus::default();
+		modus.insert(card(0, "a"), 2);
+		modus.insert(card(1, "b"), 2);
+		assert_eq!(modus.insert(card(2, "c"), 2), vec![item(0)]);
+		assert!(modus.can_retrieve(item(1)));
+		assert!(!modus.can_retrieve(item(2)));
+	}
+
+	#[test]
+	fn array_fills_gaps_first() {
+		let mut modus = ArrayModus::default();
+		modus.insert(card(0, "a"), 2);
+		modus.insert(card(1, "b"), 2);
+		modus.remove(item(0));
+		assert!(modus.insert(card(2, "c"), 2).is_empty());
+		assert_eq!(modus.items(), vec![item(2), item(1)]);
+		assert_eq!(modus.insert(card(3, "d"), 2), vec![item(2)]);
+		assert!(modus.can_retrieve(item(1)));
+	}
+
+	#[test]
+	fn hash_map_collisions_eject() {
+		let mut modus = HashMapModus::default();
+		// "ab" and "ba" both hash to 3
+		assert!(modus.insert(card(0, "ab"), 4).is_empty());
+		assert!(modus.insert(card(1, "aa"), 4).is_empty());
+		assert_eq!(modus.insert(card(2, "ba"), 4), vec![item(0)]);
+		assert_eq!(
+			modus.layout(4),
+			vec![vec![None, None, Some(item(1)), Some(item(2))]]
+		);
+	}
+
+	#[test]
+	fn tree_only_gives_up_leaves() {
+		let mut modus = TreeModus::default();
+		modus.insert(card(0, "m"), 3);
+		modus.insert(card(1, "c"), 3);
+		modus.insert(card(2, "x"), 3);
+		assert!(!modus.can_retrieve(item(0)));
+		assert!(modus.can_retrieve(item(1)));
+		assert!(modus.can_retrieve(item(2)));
+		assert_eq!(
+			modus.layout(4),
+			vec![
+				vec![Some(item(0))],
+				vec![Some(item(1)), Some(item(2))],
+				vec![None]
+			]
+		);
+	}
+
+	#[test]
+	fn tree_ejects_its_root() {
+		let mut modus = TreeModus::default();
+		modus.insert(card(0, "m"), 2);
+		modus.insert(card(1, "c"), 2);
+		assert_eq!(modus.insert(card(2, "x"), 2), vec![item(0)]);
+		assert!(!modus.can_retrieve(item(1)));
+		assert!(modus.can_retrieve(item(2)));
+	}
+}
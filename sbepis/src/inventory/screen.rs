@@ -1,37 +1,66 @@
 use bevy::color::palettes::css;
 use bevy::prelude::*;
 use bevy_butler::*;
-use leafwing_input_manager::prelude::InputMap;
 
+use crate::alchemy::{Alchemize, AlchemizeSet, Operation};
 use crate::camera::PlayerCameraNode;
-use crate::input::input_manager_bundle;
-use crate::inventory::{pick_up_items, InventoryPlugin, Item, ItemPickedUp};
+use crate::inventory::definitions::ItemDefinition;
+use crate::inventory::{
+	Inventory, InventoryChangedSet, InventoryPlugin, Item, ItemAction, ItemRegistry, RetrieveItem,
+	RetrieveItemSet,
+};
+use crate::keybinds::{keybound_input_manager, Keybinds};
 use crate::menus::*;
+use crate::{ok_or_continue, some_or_return};
 
 #[derive(Component)]
 pub struct InventoryScreen;
 
+/// A card on the inventory screen, which shows what its item is when hovered.
+#[derive(Component)]
+pub struct InventoryCard(pub Entity);
+
+/// The text at the bottom of the inventory screen describing the hovered card.
+#[derive(Component)]
+pub struct InventoryItemDetails;
+
+/// A button under a card on the inventory screen that takes its item out.
+#[derive(Component)]
+pub struct InventoryCardAction {
+	pub item: Entity,
+	pub action: ItemAction,
+}
+
+/// A button that alchemizes the two selected cards together.
+#[derive(Component)]
+pub struct AlchemyButton(pub Operation);
+
+/// The cards picked out for alchemy, at most two.
+#[derive(Resource, Default)]
+#[resource(plugin = InventoryPlugin)]
+pub struct SelectedCards(pub Vec<Entity>);
+
+/// How wide and tall each card on the inventory screen is.
+const CARD_SIZE: f32 = 100.0;
+
 #[system(
 	plugin = InventoryPlugin, schedule = Startup,
 )]
-fn spawn_inventory_screen(mut commands: Commands) {
+fn spawn_inventory_screen(mut commands: Commands, keybinds: Res<Keybinds>) {
 	commands
 		.spawn((
 			Node {
 				width: Val::Percent(100.0),
 				height: Val::Percent(100.0),
 				margin: UiRect::all(Val::Px(10.0)),
+				flex_direction: FlexDirection::Column,
+				align_items: AlignItems::Center,
 				row_gap: Val::Px(10.0),
-				column_gap: Val::Px(10.0),
-				flex_wrap: FlexWrap::Wrap,
 				..default()
 			},
 			BackgroundColor(css::GRAY.with_alpha(0.5).into()),
 			Visibility::Hidden,
-			input_manager_bundle(
-				InputMap::default().with(CloseMenuAction, KeyCode::KeyV),
-				false,
-			),
+			keybound_input_manager(&keybinds, |keybinds| &keybinds.close_inventory, false),
 			PlayerCameraNode,
 			Menu,
 			MenuWithMouse,
@@ -42,31 +71,281 @@ fn spawn_inventory_screen(mut commands: Commands) {
 		.insert(Name::new("Inventory Screen"));
 }
 
+/// Lays the deck's cards out however its modus arranges them, with buttons under the ones that can
+/// be taken out.
 #[system(
 	plugin = InventoryPlugin, schedule = Update,
-	after = pick_up_items,
+	after = InventoryChangedSet,
 )]
-fn add_item_to_inventory_screen(
-	mut ev_picked_up: EventReader<ItemPickedUp>,
+fn redraw_inventory_screen(
 	mut commands: Commands,
-	items: Query<&Item>,
 	inventory_screen: Query<Entity, With<InventoryScreen>>,
+	inventories: Query<Ref<Inventory>>,
+	items: Query<&Item>,
+	selected_cards: Res<SelectedCards>,
 ) {
 	let inventory_screen = inventory_screen.single();
+	let inventory = some_or_return!(inventories.get_single().ok());
+	if !inventory.is_changed() && !selected_cards.is_changed() {
+		return;
+	}
+
+	commands
+		.entity(inventory_screen)
+		.despawn_descendants()
+		.with_children(|parent| {
+			parent.spawn((
+				Text::new(format!(
+					"{} Modus ({}/{} cards)",
+					inventory.modus_name(),
+					inventory.items().len(),
+					inventory.cards
+				)),
+				TextFont::from_font_size(24.0),
+			));
+
+			for row in inventory.layout() {
+				parent
+					.spawn(Node {
+						column_gap: Val::Px(10.0),
+						..default()
+					})
+					.with_children(|parent| {
+						for card in row {
+							let card_node = Node {
+								width: Val::Px(CARD_SIZE),
+								height: Val::Px(CARD_SIZE),
+								border: UiRect::all(Val::Px(4.0)),
+								..default()
+							};
+							let Some(item_entity) = card else {
+								parent.spawn((card_node, BackgroundColor(css::DIM_GRAY.into())));
+								continue;
+							};
+							let item = ok_or_continue!(items.get(item_entity));
+							let can_retrieve = inventory.can_retrieve(item_entity);
+							let border = if selected_cards.0.contains(&item_entity) {
+								css::GOLD
+							} else if can_retrieve {
+								css::WHITE
+							} else {
+								css::DARK_GRAY
+							};
+
+							parent
+								.spawn(Node {
+									flex_direction: FlexDirection::Column,
+									row_gap: Val::Px(4.0),
+									..default()
+								})
+								.with_children(|parent| {
+									parent.spawn((
+										ImageNode::new(item.icon.clone()),
+										card_node,
+										BackgroundColor(css::DARK_GRAY.into()),
+										BorderColor(border.into()),
+										Interaction::default(),
+										InventoryCard(item_entity),
+									));
+
+									if !can_retrieve {
+										return;
+									}
+									for action in ItemAction::ALL {
+										parent
+											.spawn((
+												Button,
+												Node {
+													padding: UiRect::all(Val::Px(4.0)),
+													justify_content: JustifyContent::Center,
+													..default()
+												},
+												BackgroundColor(css::GRAY.into()),
+												InventoryCardAction {
+													item: item_entity,
+													action,
+												},
+											))
+											.with_children(|parent| {
+												parent.spawn((
+													Text::new(action.label()),
+													TextColor(Color::WHITE),
+													TextFont::from_font_size(16.0),
+												));
+											});
+									}
+								});
+						}
+					});
+			}
+
+			if selected_cards.0.len() == 2 {
+				parent
+					.spawn(Node {
+						column_gap: Val::Px(10.0),
+						..default()
+					})
+					.with_children(|parent| {
+						for operation in Operation::ALL {
+							parent
+								.spawn((
+									Button,
+									Node {
+										padding: UiRect::all(Val::Px(8.0)),
+										..default()
+									},
+									BackgroundColor(css::GRAY.into()),
+									AlchemyButton(operation),
+								))
+								.with_children(|parent| {
+									parent.spawn((
+										Text::new(format!("Alchemize {}", operation.symbol())),
+										TextColor(Color::WHITE),
+										TextFont::from_font_size(20.0),
+									));
+								});
+						}
+					});
+			}
+
+			parent.spawn((
+				Text::default(),
+				TextFont::from_font_size(16.0),
+				InventoryItemDetails,
+			));
+		});
+}
+
+/// Cards that leave the deck can't stay picked out.
+#[system(
+	plugin = InventoryPlugin, schedule = Update,
+	after = InventoryChangedSet,
+	before = redraw_inventory_screen,
+)]
+fn deselect_removed_cards(
+	inventories: Query<&Inventory, Changed<Inventory>>,
+	mut selected_cards: ResMut<SelectedCards>,
+) {
+	let inventory = some_or_return!(inventories.get_single().ok());
+	if selected_cards
+		.0
+		.iter()
+		.any(|item| !inventory.contains(*item))
+	{
+		selected_cards.0.retain(|item| inventory.contains(*item));
+	}
+}
+
+/// Clicking a card picks it out for alchemy, or puts it back if it already was.
+#[system(
+	plugin = InventoryPlugin, schedule = Update,
+	before = redraw_inventory_screen,
+)]
+fn select_cards(
+	cards: Query<(&InventoryCard, &Interaction), Changed<Interaction>>,
+	mut selected_cards: ResMut<SelectedCards>,
+) {
+	for (card, &interaction) in cards.iter() {
+		if interaction != Interaction::Pressed {
+			continue;
+		}
+
+		if let Some(index) = selected_cards.0.iter().position(|item| *item == card.0) {
+			selected_cards.0.remove(index);
+		} else {
+			selected_cards.0.push(card.0);
+			if selected_cards.0.len() > 2 {
+				selected_cards.0.remove(0);
+			}
+		}
+	}
+}
+
+#[system(
+	plugin = InventoryPlugin, schedule = Update,
+	in_set = AlchemizeSet,
+)]
+fn press_alchemy_buttons(
+	buttons: Query<(&AlchemyButton, &Interaction), Changed<Interaction>>,
+	selected_cards: Res<SelectedCards>,
+	mut ev_alchemize: EventWriter<Alchemize>,
+) {
+	let &[first, second] = selected_cards.0.as_slice() else {
+		return;
+	};
+	for (button, &interaction) in buttons.iter() {
+		if interaction == Interaction::Pressed {
+			ev_alchemize.send(Alchemize {
+				first,
+				second,
+				operation: button.0,
+			});
+		}
+	}
+}
+
+#[system(
+	plugin = InventoryPlugin, schedule = Update,
+	after = redraw_inventory_screen,
+)]
+fn show_hovered_item_details(
+	changed_cards: Query<(), (With<InventoryCard>, Changed<Interaction>)>,
+	cards: Query<(&InventoryCard, &Interaction)>,
+	items: Query<&Item>,
+	registry: Res<ItemRegistry>,
+	mut details: Query<&mut Text, With<InventoryItemDetails>>,
+) {
+	if changed_cards.is_empty() {
+		return;
+	}
+	let mut details = some_or_return!(details.get_single_mut().ok());
+
+	details.0 = cards
+		.iter()
+		.find(|(_, &interaction)| interaction != Interaction::None)
+		.and_then(|(card, _)| items.get(card.0).ok())
+		.and_then(|item| registry.get(&item.id))
+		.map_or_else(String::new, item_details);
+}
 
-	for ItemPickedUp(item_entity) in ev_picked_up.read() {
-		let item = items.get(*item_entity).expect("Item not found");
-
-		commands
-			.spawn((
-				ImageNode::new(item.icon.clone()),
-				Node {
-					width: Val::Px(100.0),
-					height: Val::Px(100.0),
-					..default()
-				},
-				BackgroundColor(css::DARK_GRAY.into()),
-			))
-			.set_parent(inventory_screen);
+fn item_details(definition: &ItemDefinition) -> String {
+	let cost = if definition.grist_cost.is_empty() {
+		"nothing".to_owned()
+	} else {
+		definition
+			.grist_cost
+			.iter()
+			.map(|(grist, amount)| format!("{amount} {}", grist.name()))
+			.collect::<Vec<_>>()
+			.join(", ")
+	};
+	let mut details = format!(
+		"{} ({})\n{}\nCosts {cost}",
+		definition.name, definition.captcha, definition.description
+	);
+	if let Some(weapon) = definition.weapon {
+		details += &format!(
+			"\nDeals {:.1} damage up to {:.1}m away",
+			weapon.damage, weapon.reach
+		);
+	}
+	details
+}
+
+#[system(
+	plugin = InventoryPlugin, schedule = Update,
+	in_set = RetrieveItemSet,
+)]
+fn press_card_actions(
+	buttons: Query<(&InventoryCardAction, &Interaction), Changed<Interaction>>,
+	mut ev_retrieve: EventWriter<RetrieveItem>,
+) {
+	for (button, &interaction) in buttons.iter() {
+		if interaction == Interaction::Pressed {
+			ev_retrieve.send(RetrieveItem {
+				item: button.item,
+				action: button.action,
+			});
+		}
 	}
 }
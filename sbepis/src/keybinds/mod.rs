@@ -0,0 +1,703 @@
+use std::fmt::{self, Display};
+use std::path::Path;
+
+use bevy::prelude::*;
+use bevy_butler::*;
+use leafwing_input_manager::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::fray::TrackSwitcherAction;
+use crate::input::input_manager_bundle;
+use crate::menus::{CloseMenuAction, MenuNavigationAction};
+use crate::player_commands::{CloseStaffAction, PlayNoteAction, RadialNoteAction};
+use crate::player_controller::PlayerAction;
+use crate::questing::QuestProposalAction;
+use crate::save::SaveAction;
+
+mod screen;
+
+const KEYBINDS_PATH: &str = "keybinds.ron";
+/// Sticks go from -1 to 1 every frame, but looking around expects mouse motion in pixels.
+const GAMEPAD_LOOK_SENSITIVITY: f32 = 10.0;
+
+#[butler_plugin]
+pub struct KeybindsPlugin;
+
+/// A single physical input that an action can be bound to.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum Binding {
+	Key(KeyCode),
+	Mouse(MouseButton),
+	Gamepad(GamepadButton),
+	ScrollUp,
+	ScrollDown,
+	Wasd,
+	ArrowKeys,
+	MouseMove,
+	DPad,
+	LeftStick,
+	RightStick,
+	/// The right stick scaled up to stand in for [`Binding::MouseMove`].
+	RightStickLook,
+}
+
+impl Binding {
+	fn insert_into<A: Actionlike>(self, input_map: &mut InputMap<A>, action: A) {
+		match self {
+			Binding::Key(key) => {
+				input_map.insert(action, key);
+			}
+			Binding::Mouse(button) => {
+				input_map.insert(action, button);
+			}
+			Binding::Gamepad(button) => {
+				input_map.insert(action, button);
+			}
+			Binding::ScrollUp => {
+				input_map.insert(action, MouseScrollDirection::UP);
+			}
+			Binding::ScrollDown => {
+				input_map.insert(action, MouseScrollDirection::DOWN);
+			}
+			Binding::Wasd => {
+				input_map.insert_dual_axis(action, VirtualDPad::wasd());
+			}
+			Binding::ArrowKeys => {
+				input_map.insert_dual_axis(action, VirtualDPad::arrow_keys());
+			}
+			Binding::MouseMove => {
+				input_map.insert_dual_axis(action, MouseMove::default());
+			}
+			Binding::DPad => {
+				input_map.insert_dual_axis(action, VirtualDPad::dpad());
+			}
+			Binding::LeftStick => {
+				input_map.insert_dual_axis(action, GamepadStick::LEFT);
+			}
+			Binding::RightStick => {
+				input_map.insert_dual_axis(action, GamepadStick::RIGHT);
+			}
+			Binding::RightStickLook => {
+				input_map.insert_dual_axis(
+					action,
+					GamepadStick::RIGHT.sensitivity(GAMEPAD_LOOK_SENSITIVITY),
+				);
+			}
+		}
+	}
+
+	/// Whether this can be replaced by pressing a button in the rebinding menu.
+	pub fn is_rebindable(&self) -> bool {
+		matches!(
+			self,
+			Binding::Key(_) | Binding::Mouse(_) | Binding::Gamepad(_)
+		)
+	}
+}
+
+impl Display for Binding {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Binding::Key(key) => {
+				let name = format!("{key:?}");
+				let name = name
+					.strip_prefix("Key")
+					.or_else(|| name.strip_prefix("Digit"))
+					.unwrap_or(&name);
+				write!(f, "{name}")
+			}
+			Binding::Mouse(button) => write!(f, "Mouse {button:?}"),
+			Binding::Gamepad(button) => write!(f, "Pad {button:?}"),
+			Binding::ScrollUp => write!(f, "Scroll Up"),
+			Binding::ScrollDown => write!(f, "Scroll Down"),
+			Binding::Wasd => write!(f, "WASD"),
+			Binding::ArrowKeys => write!(f, "Arrow Keys"),
+			Binding::MouseMove => write!(f, "Mouse Move"),
+			Binding::DPad => write!(f, "D-Pad"),
+			Binding::LeftStick => write!(f, "Left Stick"),
+			Binding::RightStick | Binding::RightStickLook => write!(f, "Right Stick"),
+		}
+	}
+}
+
+/// Every binding for one set of actions. An action can have more than one binding.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ActionBindings<A>(pub Vec<(A, Binding)>);
+
+impl<A: Actionlike + Copy> ActionBindings<A> {
+	pub fn new(bindings: impl IntoIterator<Item = (A, Binding)>) -> Self {
+		Self(bindings.into_iter().collect())
+	}
+
+	pub fn input_map(&self) -> InputMap<A> {
+		let mut input_map = InputMap::default();
+		for (action, binding) in self.0.iter() {
+			binding.insert_into(&mut input_map, *action);
+		}
+		input_map
+	}
+
+	/// All of the bindings for an action, for showing in button prompts.
+	pub fn describe(&self, action: A) -> String {
+		self.0
+			.iter()
+			.filter(|(other, _)| *other == action)
+			.map(|(_, binding)| binding.to_string())
+			.collect::<Vec<_>>()
+			.join("/")
+	}
+
+	/// Pairs of different actions that are bound to the same input.
+	pub fn conflicts(&self) -> Vec<(A, A, Binding)> {
+		self.0
+			.iter()
+			.enumerate()
+			.flat_map(|(i, (a, binding))| {
+				self.0[i + 1..]
+					.iter()
+					.filter(move |(b, other)| a != b && binding == other)
+					.map(move |(b, _)| (*a, *b, *binding))
+			})
+			.collect()
+	}
+}
+
+/// Type-erased access to an [`ActionBindings`] so the rebinding menu can list every set together.
+pub trait BindingList {
+	fn count(&self) -> usize;
+	fn action_name(&self, index: usize) -> String;
+	fn binding(&self, index: usize) -> Binding;
+	fn set_binding(&mut self, index: usize, binding: Binding);
+	fn remove(&mut self, index: usize);
+	fn is_conflicting(&self, index: usize) -> bool;
+	/// Whether removing this would leave its action with no way to trigger it.
+	fn is_last_binding(&self, index: usize) -> bool;
+}
+
+impl<A: Actionlike + Copy> BindingList for ActionBindings<A> {
+	fn count(&self) -> usize {
+		self.0.len()
+	}
+
+	fn action_name(&self, index: usize) -> String {
+		format!("{:?}", self.0[index].0)
+	}
+
+	fn binding(&self, index: usize) -> Binding {
+		self.0[index].1
+	}
+
+	fn set_binding(&mut self, index: usize, binding: Binding) {
+		self.0[index].1 = binding;
+	}
+
+	fn remove(&mut self, index: usize) {
+		self.0.remove(index);
+	}
+
+	fn is_conflicting(&self, index: usize) -> bool {
+		let (action, binding) = self.0[index];
+		self.0.iter().any(|(other_action, other_binding)| {
+			*other_action != action && *other_binding == binding
+		})
+	}
+
+	fn is_last_binding(&self, index: usize) -> bool {
+		let action = self.0[index].0;
+		self.0
+			.iter()
+			.filter(|(other_action, _)| *other_action == action)
+			.count() == 1
+	}
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum KeybindContext {
+	Player,
+	Staff,
+	RadialNotes,
+	CloseStaff,
+	CloseQuestScreen,
+	CloseInventory,
+	CloseKeybinds,
+	QuestProposal,
+	TrackSwitcher,
+	MenuNavigation,
+	Saves,
+}
+
+impl KeybindContext {
+	pub const ALL: [KeybindContext; 11] = [
+		KeybindContext::Player,
+		KeybindContext::Staff,
+		KeybindContext::RadialNotes,
+		KeybindContext::CloseStaff,
+		KeybindContext::CloseQuestScreen,
+		KeybindContext::CloseInventory,
+		KeybindContext::CloseKeybinds,
+		KeybindContext::QuestProposal,
+		KeybindContext::TrackSwitcher,
+		KeybindContext::MenuNavigation,
+		KeybindContext::Saves,
+	];
+
+	pub fn name(&self) -> &'static str {
+		match self {
+			KeybindContext::Player => "Player",
+			KeybindContext::Staff => "Staff",
+			KeybindContext::RadialNotes => "Staff (Controller)",
+			KeybindContext::CloseStaff => "Close Staff",
+			KeybindContext::CloseQuestScreen => "Close Quest Screen",
+			KeybindContext::CloseInventory => "Close Inventory",
+			KeybindContext::CloseKeybinds => "Close Keybinds",
+			KeybindContext::QuestProposal => "Quest Proposal",
+			KeybindContext::TrackSwitcher => "Track Switcher",
+			KeybindContext::MenuNavigation => "Menu Navigation",
+			KeybindContext::Saves => "Saving",
+		}
+	}
+}
+
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+#[resource(plugin = KeybindsPlugin, init = Keybinds::load())]
+#[serde(default)]
+pub struct Keybinds {
+	pub player: ActionBindings<PlayerAction>,
+	pub staff: ActionBindings<PlayNoteAction>,
+	pub radial_notes: ActionBindings<RadialNoteAction>,
+	pub close_staff: ActionBindings<CloseStaffAction>,
+	pub close_quest_screen: ActionBindings<CloseMenuAction>,
+	pub close_inventory: ActionBindings<CloseMenuAction>,
+	pub close_keybinds: ActionBindings<CloseMenuAction>,
+	pub quest_proposal: ActionBindings<QuestProposalAction>,
+	pub track_switcher: ActionBindings<TrackSwitcherAction>,
+	pub menu_navigation: ActionBindings<MenuNavigationAction>,
+	pub saves: ActionBindings<SaveAction>,
+}
+
+impl Keybinds {
+	/// Falls back to the defaults if there's no saved file or it can't be read.
+	pub fn load() -> Self {
+		Self::load_from(KEYBINDS_PATH)
+	}
+
+	fn load_from(path: impl AsRef<Path>) -> Self {
+		let path = path.as_ref();
+		let Ok(file) = std::fs::read_to_string(path) else {
+			return Self::default();
+		};
+		match ron::from_str(&file) {
+			Ok(keybinds) => keybinds,
+			Err(err) => {
+				warn!(
+					"Couldn't parse {}, using default keybinds: {err}",
+					path.display()
+				);
+				Self::default()
+			}
+		}
+	}
+
+	pub fn save(&self) {
+		let file = match ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()) {
+			Ok(file) => file,
+			Err(err) => {
+				error!("Couldn't serialize keybinds: {err}");
+				return;
+			}
+		};
+		if let Err(err) = std::fs::write(KEYBINDS_PATH, file) {
+			error!("Couldn't write {KEYBINDS_PATH}: {err}");
+		}
+	}
+
+	pub fn context(&self, context: KeybindContext) -> &dyn BindingList {
+		match context {
+			KeybindContext::Player => &self.player,
+			KeybindContext::Staff => &self.staff,
+			KeybindContext::RadialNotes => &self.radial_notes,
+			KeybindContext::CloseStaff => &self.close_staff,
+			KeybindContext::CloseQuestScreen => &self.close_quest_screen,
+			KeybindContext::CloseInventory => &self.close_inventory,
+			KeybindContext::CloseKeybinds => &self.close_keybinds,
+			KeybindContext::QuestProposal => &self.quest_proposal,
+			KeybindContext::TrackSwitcher => &self.track_switcher,
+			KeybindContext::MenuNavigation => &self.menu_navigation,
+			KeybindContext::Saves => &self.saves,
+		}
+	}
+
+	pub fn context_mut(&mut self, context: KeybindContext) -> &mut dyn BindingList {
+		match context {
+			KeybindContext::Player => &mut self.player,
+			KeybindContext::Staff => &mut self.staff,
+			KeybindContext::RadialNotes => &mut self.radial_notes,
+			KeybindContext::CloseStaff => &mut self.close_staff,
+			KeybindContext::CloseQuestScreen => &mut self.close_quest_screen,
+			KeybindContext::CloseInventory => &mut self.close_inventory,
+			KeybindContext::CloseKeybinds => &mut self.close_keybinds,
+			KeybindContext::QuestProposal => &mut self.quest_proposal,
+			KeybindContext::TrackSwitcher => &mut self.track_switcher,
+			KeybindContext::MenuNavigation => &mut self.menu_navigation,
+			KeybindContext::Saves => &mut self.saves,
+		}
+	}
+
+	pub fn conflict_messages(&self) -> Vec<String> {
+		fn messages<A: Actionlike + Copy>(
+			context: KeybindContext,
+			bindings: &ActionBindings<A>,
+		) -> Vec<String> {
+			bindings
+				.conflicts()
+				.into_iter()
+				.map(|(a, b, binding)| {
+					format!(
+						"{}: {a:?} and {b:?} are both bound to {binding}",
+						context.name()
+					)
+				})
+				.collect()
+		}
+
+		[
+			messages(KeybindContext::Player, &self.player),
+			messages(KeybindContext::Staff, &self.staff),
+			messages(KeybindContext::RadialNotes, &self.radial_notes),
+			messages(KeybindContext::CloseStaff, &self.close_staff),
+			messages(KeybindContext::CloseQuestScreen, &self.close_quest_screen),
+			messages(KeybindContext::CloseInventory, &self.close_inventory),
+			messages(KeybindContext::CloseKeybinds, &self.close_keybinds),
+			messages(KeybindContext::QuestProposal, &self.quest_proposal),
+			messages(KeybindContext::TrackSwitcher, &self.track_switcher),
+			messages(KeybindContext::MenuNavigation, &self.menu_navigation),
+			messages(KeybindContext::Saves, &self.saves),
+		]
+		.concat()
+	}
+}
+
+impl Default for Keybinds {
+	fn default() -> Self {
+		use Binding::*;
+		use KeyCode::*;
+
+		Self {
+			player: ActionBindings::new([
+				(PlayerAction::Move, Wasd),
+				(PlayerAction::Jump, Key(Space)),
+				(PlayerAction::Look, MouseMove),
+				(PlayerAction::Sprint, Key(ShiftLeft)),
+				(PlayerAction::Use, Mouse(MouseButton::Left)),
+				(PlayerAction::Interact, Key(KeyE)),
+				(PlayerAction::NextWeapon, ScrollUp),
+				(PlayerAction::PrevWeapon, ScrollDown),
+				(PlayerAction::OpenQuestScreen, Key(KeyJ)),
+				(PlayerAction::OpenInventory, Key(KeyV)),
+				(PlayerAction::OpenStaff, Key(Backquote)),
+				(PlayerAction::OpenKeybinds, Key(F1)),
+				(PlayerAction::Move, LeftStick),
+				(PlayerAction::Look, RightStickLook),
+				(PlayerAction::Jump, Gamepad(GamepadButton::South)),
+				(PlayerAction::Sprint, Gamepad(GamepadButton::LeftThumb)),
+				(PlayerAction::Use, Gamepad(GamepadButton::RightTrigger2)),
+				(PlayerAction::Interact, Gamepad(GamepadButton::West)),
+				(
+					PlayerAction::NextWeapon,
+					Gamepad(GamepadButton::RightTrigger),
+				),
+				(
+					PlayerAction::PrevWeapon,
+					Gamepad(GamepadButton::LeftTrigger),
+				),
+				(
+					PlayerAction::OpenQuestScreen,
+					Gamepad(GamepadButton::Select),
+				),
+				(PlayerAction::OpenInventory, Gamepad(GamepadButton::North)),
+				(PlayerAction::OpenStaff, Gamepad(GamepadButton::DPadUp)),
+				(PlayerAction::OpenKeybinds, Gamepad(GamepadButton::Start)),
+			]),
+			staff: ActionBindings::new([
+				(PlayNoteAction::C4, Key(KeyZ)),
+				(PlayNoteAction::CS4, Key(KeyS)),
+				(PlayNoteAction::D4, Key(KeyX)),
+				(PlayNoteAction::DS4, Key(KeyD)),
+				(PlayNoteAction::E4, Key(KeyC)),
+				(PlayNoteAction::F4, Key(KeyV)),
+				(PlayNoteAction::FS4, Key(KeyG)),
+				(PlayNoteAction::G4, Key(KeyB)),
+				(PlayNoteAction::GS4, Key(KeyH)),
+				(PlayNoteAction::A4, Key(KeyN)),
+				(PlayNoteAction::AS4, Key(KeyJ)),
+				(PlayNoteAction::B4, Key(KeyM)),
+				(PlayNoteAction::C5, Key(Comma)),
+				(PlayNoteAction::CS5, Key(KeyL)),
+				(PlayNoteAction::D5, Key(Period)),
+				(PlayNoteAction::DS5, Key(Semicolon)),
+				(PlayNoteAction::E5, Key(Slash)),
+				(PlayNoteAction::C5, Key(KeyQ)),
+				(PlayNoteAction::CS5, Key(Digit2)),
+				(PlayNoteAction::D5, Key(KeyW)),
+				(PlayNoteAction::DS5, Key(Digit3)),
+				(PlayNoteAction::E5, Key(KeyE)),
+				(PlayNoteAction::F5, Key(KeyR)),
+				(PlayNoteAction::FS5, Key(Digit5)),
+				(PlayNoteAction::G5, Key(KeyT)),
+				(PlayNoteAction::GS5, Key(Digit6)),
+				(PlayNoteAction::A5, Key(KeyY)),
+				(PlayNoteAction::AS5, Key(Digit7)),
+				(PlayNoteAction::B5, Key(KeyU)),
+				(PlayNoteAction::C6, Key(KeyI)),
+				(PlayNoteAction::CS6, Key(Digit9)),
+				(PlayNoteAction::D6, Key(KeyO)),
+				(PlayNoteAction::DS6, Key(Digit0)),
+				(PlayNoteAction::E6, Key(KeyP)),
+			]),
+			radial_notes: ActionBindings::new([
+				(RadialNoteAction::Aim, RightStick),
+				(RadialNoteAction::Play, Gamepad(GamepadButton::South)),
+				(
+					RadialNoteAction::OctaveUp,
+					Gamepad(GamepadButton::RightTrigger),
+				),
+				(
+					RadialNoteAction::OctaveDown,
+					Gamepad(GamepadButton::LeftTrigger),
+				),
+			]),
+			close_staff: ActionBindings::new([
+				(CloseStaffAction, Key(Backquote)),
+				(CloseStaffAction, Gamepad(GamepadButton::East)),
+			]),
+			close_quest_screen: ActionBindings::new([
+				(CloseMenuAction, Key(KeyJ)),
+				(CloseMenuAction, Gamepad(GamepadButton::East)),
+			]),
+			close_inventory: ActionBindings::new([
+				(CloseMenuAction, Key(KeyV)),
+				(CloseMenuAction, Gamepad(GamepadButton::East)),
+			]),
+			close_keybinds: ActionBindings::new([
+				(CloseMenuAction, Key(F1)),
+				(CloseMenuAction, Gamepad(GamepadButton::East)),
+			]),
+			quest_proposal: ActionBindings::new([
+				(QuestProposalAction::Accept, Key(KeyE)),
+				(QuestProposalAction::Decline, Key(Space)),
+			]),
+			track_switcher: ActionBindings::new([
+				(TrackSwitcherAction::FourFour, Key(Digit4)),
+				(TrackSwitcherAction::SixEight, Key(Digit6)),
+			]),
+			menu_navigation: ActionBindings::new([
+				(MenuNavigationAction::Navigate, ArrowKeys),
+				(MenuNavigationAction::Navigate, DPad),
+				(MenuNavigationAction::Navigate, LeftStick),
+				(MenuNavigationAction::Select, Key(Enter)),
+				(MenuNavigationAction::Select, Gamepad(GamepadButton::South)),
+			]),
+			saves: ActionBindings::new([
+				(SaveAction::QuickSave, Key(F5)),
+				(SaveAction::QuickLoad, Key(F9)),
+				(SaveAction::PreviousSlot, Key(F6)),
+				(SaveAction::NextSlot, Key(F7)),
+			]),
+		}
+	}
+}
+
+/// Keeps an input map in sync with one set of bindings when they get changed.
+#[derive(Component)]
+pub struct Keybound<A: Actionlike>(fn(&Keybinds) -> &ActionBindings<A>);
+
+impl<A: Actionlike> Keybound<A> {
+	/// Which of the [`Keybinds`] these are.
+	pub fn context(&self, keybinds: &Keybinds) -> Option<KeybindContext> {
+		let bindings = (self.0)(keybinds);
+		KeybindContext::ALL
+			.into_iter()
+			.find(|context| std::ptr::addr_eq(keybinds.context(*context), bindings))
+	}
+}
+
+pub fn keybound_input_manager<A: Actionlike + Copy>(
+	keybinds: &Keybinds,
+	bindings: fn(&Keybinds) -> &ActionBindings<A>,
+	start_enabled: bool,
+) -> (InputManagerBundle<A>, Keybound<A>) {
+	(
+		input_manager_bundle(bindings(keybinds).input_map(), start_enabled),
+		Keybound(bindings),
+	)
+}
+
+#[system(
+	plugin = KeybindsPlugin, schedule = Update,
+	generics = PlayerAction,
+	run_if = resource_changed::<Keybinds>,
+)]
+#[system(
+	plugin = KeybindsPlugin, schedule = Update,
+	generics = PlayNoteAction,
+	run_if = resource_changed::<Keybinds>,
+)]
+#[system(
+	plugin = KeybindsPlugin, schedule = Update,
+	generics = CloseStaffAction,
+	run_if = resource_changed::<Keybinds>,
+)]
+#[system(
+	plugin = KeybindsPlugin, schedule = Update,
+	generics = CloseMenuAction,
+	run_if = resource_changed::<Keybinds>,
+)]
+#[system(
+	plugin = KeybindsPlugin, schedule = Update,
+	generics = RadialNoteAction,
+	run_if = resource_changed::<Keybinds>,
+)]
+#[system(
+	plugin = KeybindsPlugin, schedule = Update,
+	generics = MenuNavigationAction,
+	run_if = resource_changed::<Keybinds>,
+)]
+#[system(
+	plugin = KeybindsPlugin, schedule = Update,
+	generics = SaveAction,
+	run_if = resource_changed::<Keybinds>,
+)]
+fn apply_keybinds<A: Actionlike + Copy>(
+	keybinds: Res<Keybinds>,
+	mut input_maps: Query<(&mut InputMap<A>, &Keybound<A>)>,
+) {
+	for (mut input_map, keybound) in input_maps.iter_mut() {
+		*input_map = (keybound.0)(&keybinds).input_map();
+	}
+}
+
+#[system(
+	plugin = KeybindsPlugin, schedule = Update,
+	run_if = resource_changed::<Keybinds>,
+)]
+fn save_keybinds(keybinds: Res<Keybinds>) {
+	for message in keybinds.conflict_messages() {
+		warn!("Keybind conflict: {message}");
+	}
+
+	// Don't write out the file until something actually gets rebound
+	if !keybinds.is_added() {
+		keybinds.save();
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn to_ron(keybinds: &Keybinds) -> String {
+		ron::to_string(keybinds).unwrap()
+	}
+
+	#[test]
+	fn different_actions_on_one_input_conflict() {
+		let bindings = ActionBindings::new([
+			(PlayerAction::Jump, Binding::Key(KeyCode::Space)),
+			(PlayerAction::Jump, Binding::Gamepad(GamepadButton::South)),
+			(PlayerAction::Interact, Binding::Key(KeyCode::KeyE)),
+			(PlayerAction::Use, Binding::Key(KeyCode::Space)),
+		]);
+
+		let conflicts = bindings.conflicts();
+		assert_eq!(conflicts.len(), 1);
+		assert!(matches!(
+			conflicts[0],
+			(
+				PlayerAction::Jump,
+				PlayerAction::Use,
+				Binding::Key(KeyCode::Space)
+			)
+		));
+		assert!(bindings.is_conflicting(0));
+		assert!(!bindings.is_conflicting(1));
+		assert!(bindings.is_conflicting(3));
+	}
+
+	#[test]
+	fn one_action_bound_twice_to_one_input_is_not_a_conflict() {
+		let bindings = ActionBindings::new([
+			(PlayerAction::Jump, Binding::Key(KeyCode::Space)),
+			(PlayerAction::Jump, Binding::Key(KeyCode::Space)),
+		]);
+		assert!(bindings.conflicts().is_empty());
+	}
+
+	#[test]
+	fn default_keybinds_have_no_conflicts() {
+		assert_eq!(
+			Keybinds::default().conflict_messages(),
+			Vec::<String>::new()
+		);
+	}
+
+	#[test]
+	fn last_bindings_are_known() {
+		let bindings = ActionBindings::new([
+			(PlayerAction::Jump, Binding::Key(KeyCode::Space)),
+			(PlayerAction::Jump, Binding::Gamepad(GamepadButton::South)),
+			(PlayerAction::Interact, Binding::Key(KeyCode::KeyE)),
+		]);
+		assert!(!bindings.is_last_binding(0));
+		assert!(!bindings.is_last_binding(1));
+		assert!(bindings.is_last_binding(2));
+	}
+
+	#[test]
+	fn keybinds_round_trip() {
+		let mut keybinds = Keybinds::default();
+		keybinds
+			.close_keybinds
+			.set_binding(0, Binding::Key(KeyCode::KeyK));
+		keybinds.player.remove(0);
+
+		let file =
+			ron::ser::to_string_pretty(&keybinds, ron::ser::PrettyConfig::default()).unwrap();
+		let loaded: Keybinds = ron::from_str(&file).unwrap();
+		assert_eq!(to_ron(&loaded), to_ron(&keybinds));
+		assert_eq!(
+			loaded.close_keybinds.describe(CloseMenuAction),
+			"K/Pad East"
+		);
+	}
+
+	#[test]
+	fn bad_keybinds_files_fall_back_to_defaults() {
+		let defaults = to_ron(&Keybinds::default());
+		let directory = std::env::temp_dir();
+
+		let missing = directory.join("sbepis_missing_keybinds.ron");
+		let _ = std::fs::remove_file(&missing);
+		assert_eq!(to_ron(&Keybinds::load_from(&missing)), defaults);
+
+		let garbled = directory.join("sbepis_garbled_keybinds.ron");
+		std::fs::write(&garbled, "(player: [(Jump, Key(").unwrap();
+		assert_eq!(to_ron(&Keybinds::load_from(&garbled)), defaults);
+
+		// Anything missing from an older file gets its defaults
+		let partial = directory.join("sbepis_partial_keybinds.ron");
+		std::fs::write(
+			&partial,
+			"(close_keybinds: ([(CloseMenuAction, Key(KeyK))]))",
+		)
+		.unwrap();
+		let keybinds = Keybinds::load_from(&partial);
+		assert_eq!(keybinds.close_keybinds.describe(CloseMenuAction), "K");
+		assert_eq!(
+			to_ron(&Keybinds {
+				close_keybinds: Keybinds::default().close_keybinds,
+				..keybinds
+			}),
+			defaults
+		);
+	}
+}
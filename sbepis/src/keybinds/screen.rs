@@ -0,0 +1,256 @@
+use bevy::color::palettes::css;
+use bevy::prelude::*;
+use bevy_butler::*;
+
+use crate::camera::PlayerCameraNode;
+use crate::keybinds::{keybound_input_manager, Binding, KeybindContext, Keybinds, KeybindsPlugin};
+use crate::menus::*;
+use crate::player_controller::PlayerAction;
+
+#[derive(Component)]
+pub struct KeybindsScreen;
+
+#[derive(Component)]
+pub struct KeybindsScreenList;
+
+#[derive(Component)]
+pub struct KeybindsScreenConflicts;
+
+#[derive(Component)]
+pub struct KeybindButton {
+	pub context: KeybindContext,
+	pub index: usize,
+}
+
+/// The binding that's waiting on the next key or mouse button press.
+#[derive(Resource, Default)]
+#[resource(plugin = KeybindsPlugin)]
+pub struct Rebinding(Option<(KeybindContext, usize)>);
+
+pub struct OpenKeybindsScreenBinding;
+impl OpenMenuBinding for OpenKeybindsScreenBinding {
+	type Action = PlayerAction;
+	type Menu = KeybindsScreen;
+	fn action() -> Self::Action {
+		PlayerAction::OpenKeybinds
+	}
+}
+
+#[system(
+	plugin = KeybindsPlugin, schedule = Update,
+	generics = OpenKeybindsScreenBinding,
+	in_set = MenuManipulationSet,
+)]
+use crate::menus::show_menu_on_action;
+
+#[system(
+	plugin = KeybindsPlugin, schedule = Startup,
+)]
+fn spawn_keybinds_screen(mut commands: Commands, keybinds: Res<Keybinds>) {
+	commands
+		.spawn((
+			Node {
+				width: Val::Percent(100.0),
+				height: Val::Percent(100.0),
+				padding: UiRect::all(Val::Px(10.0)),
+				flex_direction: FlexDirection::Column,
+				row_gap: Val::Px(10.0),
+				..default()
+			},
+			BackgroundColor(css::GRAY.with_alpha(0.8).into()),
+			Visibility::Hidden,
+			keybound_input_manager(&keybinds, |keybinds| &keybinds.close_keybinds, false),
+			PlayerCameraNode,
+			Menu,
+			MenuWithMouse,
+			MenuWithInputManager,
+			MenuHidesWhenClosed,
+			KeybindsScreen,
+		))
+		.insert(Name::new("Keybinds Screen"))
+		.with_children(|parent| {
+			parent.spawn((
+				Text(
+					"Click a binding, then press a key or button. Press Delete to remove an extra one."
+						.to_owned(),
+				),
+				TextColor(Color::WHITE),
+				TextFont {
+					font_size: 20.0,
+					..default()
+				},
+			));
+			parent.spawn((
+				Text::default(),
+				TextColor(css::RED.into()),
+				TextFont {
+					font_size: 16.0,
+					..default()
+				},
+				KeybindsScreenConflicts,
+			));
+			parent.spawn((
+				Node {
+					flex_grow: 1.0,
+					flex_direction: FlexDirection::Column,
+					flex_wrap: FlexWrap::Wrap,
+					align_content: AlignContent::FlexStart,
+					row_gap: Val::Px(2.0),
+					column_gap: Val::Px(10.0),
+					..default()
+				},
+				KeybindsScreenList,
+			));
+		});
+}
+
+#[system(
+	plugin = KeybindsPlugin, schedule = Update,
+	run_if = resource_changed::<Keybinds>,
+)]
+fn update_keybinds_screen(
+	mut commands: Commands,
+	keybinds: Res<Keybinds>,
+	list: Query<Entity, With<KeybindsScreenList>>,
+	mut conflicts: Query<&mut Text, With<KeybindsScreenConflicts>>,
+) {
+	let list = list.single();
+	commands.entity(list).despawn_descendants();
+
+	conflicts.single_mut().0 = keybinds.conflict_messages().join("\n");
+
+	for context in KeybindContext::ALL {
+		commands
+			.spawn((
+				Text(context.name().to_owned()),
+				TextColor(Color::WHITE),
+				TextFont {
+					font_size: 18.0,
+					..default()
+				},
+				Node {
+					margin: UiRect::top(Val::Px(6.0)),
+					..default()
+				},
+			))
+			.set_parent(list);
+
+		let bindings = keybinds.context(context);
+		for index in 0..bindings.count() {
+			let color = if bindings.is_conflicting(index) {
+				css::DARK_RED
+			} else {
+				css::DARK_GRAY
+			};
+
+			commands
+				.spawn((
+					Button,
+					Node {
+						padding: UiRect::axes(Val::Px(6.0), Val::Px(2.0)),
+						..default()
+					},
+					BackgroundColor(color.into()),
+					KeybindButton { context, index },
+				))
+				.set_parent(list)
+				.with_children(|parent| {
+					parent.spawn((
+						Text(format!(
+							"{}: {}",
+							bindings.action_name(index),
+							bindings.binding(index)
+						)),
+						TextColor(Color::WHITE),
+						TextFont {
+							font_size: 14.0,
+							..default()
+						},
+					));
+				});
+		}
+	}
+}
+
+#[system(
+	plugin = KeybindsPlugin, schedule = Update,
+)]
+fn rebind_keys(
+	mut rebinding: ResMut<Rebinding>,
+	mut keybinds: ResMut<Keybinds>,
+	keys: Res<ButtonInput<KeyCode>>,
+	mouse_buttons: Res<ButtonInput<MouseButton>>,
+	gamepads: Query<&Gamepad>,
+	mut buttons: Query<(&KeybindButton, &Interaction, &mut BackgroundColor), Changed<Interaction>>,
+) {
+	// Checked before the buttons so the click that starts rebinding doesn't get bound
+	if let Some((context, index)) = rebinding.0 {
+		let binding = if keys.just_pressed(KeyCode::Delete) || keys.just_pressed(KeyCode::Backspace)
+		{
+			None
+		} else if let Some(key) = keys.get_just_pressed().next() {
+			Some(Binding::Key(*key))
+		} else if let Some(button) = mouse_buttons.get_just_pressed().next() {
+			Some(Binding::Mouse(*button))
+		} else if let Some(button) = gamepads
+			.iter()
+			.find_map(|gamepad| gamepad.get_just_pressed().next())
+		{
+			Some(Binding::Gamepad(*button))
+		} else {
+			return;
+		};
+
+		let bindings = keybinds.context_mut(context);
+		match binding {
+			Some(binding) => bindings.set_binding(index, binding),
+			// Otherwise unbinding something like closing this screen could never be undone
+			None if bindings.is_last_binding(index) => {
+				warn!(
+					"Can't remove the only binding for {} in {}",
+					bindings.action_name(index),
+					context.name()
+				);
+			}
+			None => bindings.remove(index),
+		}
+		rebinding.0 = None;
+		return;
+	}
+
+	for (button, interaction, mut background) in buttons.iter_mut() {
+		if *interaction != Interaction::Pressed {
+			continue;
+		}
+
+		if !keybinds
+			.context(button.context)
+			.binding(button.index)
+			.is_rebindable()
+		{
+			continue;
+		}
+
+		rebinding.0 = Some((button.context, button.index));
+		background.0 = css::GOLDENROD.into();
+	}
+}
+
+#[system(
+	plugin = KeybindsPlugin, schedule = Update,
+	after = MenuDeactivatedSet,
+)]
+fn cancel_rebinding(
+	mut ev_deactivated: EventReader<MenuDeactivated>,
+	screens: Query<(), With<KeybindsScreen>>,
+	mut rebinding: ResMut<Rebinding>,
+	mut keybinds: ResMut<Keybinds>,
+) {
+	for MenuDeactivated(menu) in ev_deactivated.read() {
+		if screens.get(*menu).is_ok() && rebinding.0.is_some() {
+			rebinding.0 = None;
+			// Rebuild the list so the highlighted button goes back to normal
+			keybinds.set_changed();
+		}
+	}
+}
@@ -1,25 +1,34 @@
 #![cfg_attr(not(feature = "terminal"), windows_subsystem = "windows")]
 
+#[cfg(not(feature = "headless"))]
 use std::io::Cursor;
 
 use ::blenvy::blueprints::spawn_from_blueprints::{BlueprintInfo, HideUntilReady, SpawnBlueprint};
+use bevy::app::PluginGroupBuilder;
 use bevy::input::common_conditions::input_just_pressed;
 use bevy::log::LogPlugin;
 use bevy::prelude::*;
+#[cfg(not(feature = "headless"))]
 use bevy::winit::WinitWindows;
 use bevy_rapier3d::prelude::*;
+#[cfg(not(feature = "headless"))]
 use winit::window::Icon;
 
 use self::main_bundles::*;
 
+mod alchemy;
 mod blenvy;
 mod camera;
 mod dialogue;
 mod entity;
 mod fray;
 mod gravity;
+mod grist;
+#[cfg(any(test, feature = "headless"))]
+mod headless;
 mod input;
 mod inventory;
+mod keybinds;
 mod main_bundles;
 mod menus;
 mod npcs;
@@ -28,6 +37,8 @@ mod overview_camera;
 mod player_commands;
 mod player_controller;
 mod questing;
+mod replay;
+mod save;
 mod skybox;
 pub mod util;
 
@@ -41,14 +52,7 @@ fn main() {
 	let mut app = App::new();
 	app
 		.add_plugins((
-			DefaultPlugins
-				.set(WindowPlugin {
-					primary_window: Some(Window {
-						title: "SBEPIS".to_string(),
-						..default()
-					}),
-					..default()
-				})
+			default_plugins()
 				.set(ImagePlugin {
 					default_sampler: bevy::image::ImageSamplerDescriptor {
 						address_mode_u: bevy::image::ImageAddressMode::Repeat,
@@ -61,17 +65,41 @@ fn main() {
 					filter: "info,sbepis=debug,avian3d=debug,wgpu=error,naga=warn,calloop=error,symphonia_core=warn,symphonia_bundle_mp3=warn,blenvy=error".into(),
 					..default()
 				}),
-			RapierPhysicsPlugin::<NoUserData>::default(),
 			#[cfg(feature = "rapier_debug")]
 			RapierDebugRenderPlugin::default(),
 			#[cfg(feature = "inspector")]
 			bevy_inspector_egui::quick::WorldInspectorPlugin::new(),
 			#[cfg(feature = "overview_camera")]
 			overview_camera::OverviewCameraPlugin,
+			#[cfg(not(feature = "headless"))]
 			bevy_hanabi::HanabiPlugin,
 		));
+	add_game_plugins(&mut app)
+		.add_systems(Startup, setup)
+		.add_systems(
+			Update,
+			(
+				quit.run_if(input_just_pressed(KeyCode::Escape)),
+				util::despawn_after_timer,
+				util::billboard,
+			),
+		);
+
+	#[cfg(not(feature = "headless"))]
+	app.add_systems(Startup, set_window_icon);
+	#[cfg(feature = "headless")]
+	app.add_plugins(headless::HeadlessPlugin);
+
+	app.run();
+}
 
+/// Physics and everything that makes up the game itself, on top of the engine's plugins.
+fn add_game_plugins(app: &mut App) -> &mut App {
 	app.add_plugins((
+		RapierPhysicsPlugin::<NoUserData>::default(),
+		marching_cubes::MarchingCubesPlugin,
+	))
+	.add_plugins((
 		player_commands::PlayerCommandsPlugin,
 		camera::PlayerCameraPlugin,
 		skybox::SkyboxPlugin,
@@ -84,19 +112,30 @@ fn main() {
 		menus::MenusPlugin,
 		inventory::InventoryPlugin,
 		blenvy::BlenvyPlugin,
+		keybinds::KeybindsPlugin,
+		save::SavePlugin,
+		replay::ReplayPlugin,
 	))
-	.add_systems(Startup, (set_window_icon, setup))
-	.add_systems(
-		Update,
-		(
-			quit.run_if(input_just_pressed(KeyCode::Escape)),
-			util::despawn_after_timer,
-			util::billboard,
-		),
-	)
-	.run();
+	.add_plugins((alchemy::AlchemyPlugin, grist::GristPlugin))
+}
+
+#[cfg(not(feature = "headless"))]
+fn default_plugins() -> PluginGroupBuilder {
+	DefaultPlugins.set(WindowPlugin {
+		primary_window: Some(Window {
+			title: "SBEPIS".to_string(),
+			..default()
+		}),
+		..default()
+	})
+}
+
+#[cfg(feature = "headless")]
+fn default_plugins() -> PluginGroupBuilder {
+	headless::headless_plugins()
 }
 
+#[cfg(not(feature = "headless"))]
 fn set_window_icon(windows: NonSend<WinitWindows>) {
 	let icon_buf = Cursor::new(include_bytes!("../assets/house.png"));
 	let image = image::load(icon_buf, image::ImageFormat::Png).unwrap();
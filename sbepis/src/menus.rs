@@ -1,16 +1,24 @@
 use std::time::Instant;
 
 use bevy::prelude::*;
+use bevy::ui::UiSystem;
 use bevy::window::{CursorGrabMode, PrimaryWindow};
 use bevy_butler::*;
 use leafwing_input_manager::plugin::{InputManagerPlugin, InputManagerSystem};
 use leafwing_input_manager::prelude::{ActionState, InputMap};
 use leafwing_input_manager::{Actionlike, InputControlKind};
+use serde::{Deserialize, Serialize};
 
 use crate::input::InputManagerReference;
+use crate::keybinds::{keybound_input_manager, Keybinds};
+use crate::{ok_or_return, some_or_return};
+
+/// How far a stick has to be pushed to move between buttons.
+const NAVIGATION_DEADZONE: f32 = 0.5;
 
 #[butler_plugin(build(
 	add_plugins(InputManagerMenuPlugin::<CloseMenuAction>::default()),
+	add_plugins(InputManagerMenuPlugin::<MenuNavigationAction>::default()),
 ))]
 pub struct MenusPlugin;
 
@@ -76,6 +84,10 @@ impl MenuStack {
 		self.stack.contains(&menu)
 	}
 
+	pub fn current(&self) -> Option<Entity> {
+		self.current
+	}
+
 	pub fn toggle(&mut self, menu: Entity) {
 		if self.contains(menu) {
 			self.remove(menu);
@@ -100,7 +112,7 @@ pub struct MenuDeactivatedSet;
 #[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
 pub struct MenuManipulationSet;
 
-#[derive(Clone, Copy, Eq, PartialEq, Hash, Reflect, Debug)]
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Reflect, Debug, Serialize, Deserialize)]
 pub struct CloseMenuAction;
 impl Actionlike for CloseMenuAction {
 	fn input_control_kind(&self) -> InputControlKind {
@@ -149,7 +161,8 @@ fn show_mouse(
 	menus: Query<(), With<MenuWithMouse>>,
 	mut window: Query<&mut Window, With<PrimaryWindow>>,
 ) {
-	let mut window = window.get_single_mut().expect("No primary window found");
+	// There's no window to grab the mouse in when running headless
+	let mut window = ok_or_return!(window.get_single_mut());
 	for MenuActivated(menu) in ev_activated.read() {
 		if menus.get(*menu).is_ok() {
 			window.cursor_options.grab_mode = CursorGrabMode::None;
@@ -167,7 +180,8 @@ fn hide_mouse(
 	menus: Query<(), With<MenuWithoutMouse>>,
 	mut window: Query<&mut Window, With<PrimaryWindow>>,
 ) {
-	let mut window = window.get_single_mut().expect("No primary window found");
+	// There's no window to grab the mouse in when running headless
+	let mut window = ok_or_return!(window.get_single_mut());
 	for MenuActivated(menu) in ev_activated.read() {
 		if menus.get(*menu).is_ok() {
 			window.cursor_options.grab_mode = CursorGrabMode::Locked;
@@ -297,3 +311,271 @@ fn despawn_menus(
 		}
 	}
 }
+
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Reflect, Debug, Serialize, Deserialize)]
+pub enum MenuNavigationAction {
+	Navigate,
+	Select,
+}
+impl Actionlike for MenuNavigationAction {
+	fn input_control_kind(&self) -> InputControlKind {
+		match self {
+			MenuNavigationAction::Navigate => InputControlKind::DualAxis,
+			MenuNavigationAction::Select => InputControlKind::Button,
+		}
+	}
+}
+
+/// The button in the current menu that controller input acts on.
+/// Follows the cursor too, so switching between the two doesn't jump around.
+#[derive(Resource, Default, Debug)]
+#[resource(plugin = MenusPlugin)]
+pub struct MenuFocus {
+	focused: Option<Entity>,
+	/// Pressed by [`MenuNavigationAction::Select`] last frame and needs to be let go.
+	pressed: Option<Entity>,
+}
+impl MenuFocus {
+	pub fn focused(&self) -> Option<Entity> {
+		self.focused
+	}
+}
+
+#[derive(Component)]
+pub struct MenuFocusOutline;
+
+#[system(
+	plugin = MenusPlugin, schedule = Startup,
+)]
+fn spawn_menu_navigation(mut commands: Commands, keybinds: Res<Keybinds>) {
+	// Not a menu itself, so it stays enabled no matter what's open
+	commands.spawn((
+		Name::new("Menu Navigation"),
+		keybound_input_manager(&keybinds, |keybinds| &keybinds.menu_navigation, true),
+	));
+}
+
+#[system(
+	plugin = MenusPlugin, schedule = Update,
+	after = MenuActivatedSet,
+)]
+fn clear_menu_focus(mut ev_activated: EventReader<MenuActivated>, mut focus: ResMut<MenuFocus>) {
+	if ev_activated.read().last().is_some() {
+		focus.focused = None;
+	}
+}
+
+#[system(
+	plugin = MenusPlugin, schedule = Update,
+)]
+fn focus_hovered_buttons(
+	buttons: Query<(Entity, &Interaction), (Changed<Interaction>, With<Button>)>,
+	mut focus: ResMut<MenuFocus>,
+) {
+	for (button, interaction) in buttons.iter() {
+		if *interaction != Interaction::None && focus.focused != Some(button) {
+			focus.focused = Some(button);
+		}
+	}
+}
+
+#[system(
+	plugin = MenusPlugin, schedule = Update,
+)]
+fn navigate_menu(
+	input: Query<&ActionState<MenuNavigationAction>>,
+	menu_stack: Res<MenuStack>,
+	mut focus: ResMut<MenuFocus>,
+	buttons: Query<(Entity, &GlobalTransform, &ViewVisibility), With<Button>>,
+	parents: Query<&Parent>,
+	mut was_centered: Local<bool>,
+) {
+	let input = some_or_return!(input.iter().find(|input| !input.disabled()));
+	let direction = input.axis_pair(&MenuNavigationAction::Navigate);
+	if !stick_pushed(direction, &mut was_centered) {
+		return;
+	}
+
+	let menu = some_or_return!(menu_stack.current());
+	let candidates: Vec<(Entity, Vec2)> = buttons
+		.iter()
+		.filter(|(button, _, visibility)| {
+			visibility.get() && parents.iter_ancestors(*button).any(|parent| parent == menu)
+		})
+		.map(|(button, transform, _)| (button, transform.translation().truncate()))
+		.collect();
+
+	if let Some(next) = next_focus(&candidates, focus.focused, direction) {
+		focus.focused = Some(next);
+	}
+}
+
+/// Whether the stick has just been pushed out of the deadzone, so holding it only moves once.
+fn stick_pushed(direction: Vec2, was_centered: &mut bool) -> bool {
+	if direction.length() < NAVIGATION_DEADZONE {
+		*was_centered = true;
+		return false;
+	}
+	std::mem::replace(was_centered, false)
+}
+
+/// Which of the buttons at these positions the stick moves the focus to.
+fn next_focus(
+	candidates: &[(Entity, Vec2)],
+	focused: Option<Entity>,
+	direction: Vec2,
+) -> Option<Entity> {
+	let current =
+		focused.and_then(|focused| candidates.iter().find(|(button, _)| *button == focused));
+
+	let next = match current {
+		// Start from the top left
+		None => candidates
+			.iter()
+			.min_by(|(_, a), (_, b)| a.y.total_cmp(&b.y).then(a.x.total_cmp(&b.x))),
+		Some(&(current, from)) => {
+			// UI space goes down, sticks go up
+			let direction = Vec2::new(direction.x, -direction.y).normalize();
+			candidates
+				.iter()
+				.filter(|(button, _)| *button != current)
+				.filter_map(|candidate| {
+					let offset = candidate.1 - from;
+					let along = offset.dot(direction);
+					let across = offset.perp_dot(direction).abs();
+					(along > 0.0).then_some((candidate, along + across * 2.0))
+				})
+				.min_by(|(_, a), (_, b)| a.total_cmp(b))
+				.map(|(candidate, _)| candidate)
+		}
+	};
+	next.map(|(next, _)| *next)
+}
+
+/// Runs before anything in [`Update`] so buttons pressed this way look the same as clicks.
+#[system(
+	plugin = MenusPlugin, schedule = PreUpdate,
+	after = UiSystem::Focus,
+	after = InputManagerSystem::Update,
+)]
+fn press_focused_button(
+	input: Query<&ActionState<MenuNavigationAction>>,
+	menu_stack: Res<MenuStack>,
+	mut focus: ResMut<MenuFocus>,
+	mut buttons: Query<&mut Interaction, With<Button>>,
+	parents: Query<&Parent>,
+) {
+	if let Some(pressed) = focus.pressed {
+		if let Ok(mut interaction) = buttons.get_mut(pressed) {
+			*interaction = Interaction::None;
+		}
+		focus.pressed = None;
+	}
+
+	let input = some_or_return!(input.iter().find(|input| !input.disabled()));
+	if !input.just_pressed(&MenuNavigationAction::Select) {
+		return;
+	}
+
+	// Select shares its buttons with things like jumping, so only press buttons in an open menu
+	let menu = some_or_return!(menu_stack.current());
+	let focused = some_or_return!(focus.focused);
+	if !parents.iter_ancestors(focused).any(|parent| parent == menu) {
+		return;
+	}
+	if let Ok(mut interaction) = buttons.get_mut(focused) {
+		*interaction = Interaction::Pressed;
+		focus.pressed = Some(focused);
+	}
+}
+
+#[system(
+	plugin = MenusPlugin, schedule = Update,
+	run_if = resource_changed::<MenuFocus>,
+)]
+fn outline_focused_button(
+	mut commands: Commands,
+	focus: Res<MenuFocus>,
+	outlined: Query<Entity, With<MenuFocusOutline>>,
+	buttons: Query<(), With<Button>>,
+) {
+	for button in outlined.iter() {
+		commands
+			.entity(button)
+			.remove::<(Outline, MenuFocusOutline)>();
+	}
+
+	let focused = some_or_return!(focus.focused);
+	if buttons.get(focused).is_ok() {
+		commands.entity(focused).insert((
+			Outline::new(Val::Px(2.0), Val::ZERO, Color::WHITE),
+			MenuFocusOutline,
+		));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// A row of three buttons above a row of two, in UI space where down is positive.
+	fn grid() -> Vec<(Entity, Vec2)> {
+		[
+			Vec2::new(0.0, 0.0),
+			Vec2::new(100.0, 0.0),
+			Vec2::new(200.0, 0.0),
+			Vec2::new(40.0, 50.0),
+			Vec2::new(160.0, 50.0),
+		]
+		.into_iter()
+		.enumerate()
+		.map(|(index, position)| (Entity::from_raw(index as u32), position))
+		.collect()
+	}
+
+	fn button(index: u32) -> Option<Entity> {
+		Some(Entity::from_raw(index))
+	}
+
+	#[test]
+	fn focus_starts_at_the_top_left() {
+		let mut buttons = grid();
+		buttons.reverse();
+		assert_eq!(next_focus(&buttons, None, Vec2::X), button(0));
+		// Even if the focused button isn't in this menu
+		assert_eq!(next_focus(&buttons, button(9), Vec2::X), button(0));
+	}
+
+	#[test]
+	fn focus_moves_to_the_nearest_button_that_way() {
+		let buttons = grid();
+		assert_eq!(next_focus(&buttons, button(0), Vec2::X), button(1));
+		assert_eq!(next_focus(&buttons, button(1), Vec2::NEG_X), button(0));
+		// Sticks point up, UI space points down
+		assert_eq!(next_focus(&buttons, button(0), Vec2::NEG_Y), button(3));
+		assert_eq!(next_focus(&buttons, button(4), Vec2::Y), button(2));
+		// Straight across beats closer but off to the side
+		assert_eq!(next_focus(&buttons, button(3), Vec2::X), button(4));
+	}
+
+	#[test]
+	fn focus_stays_put_at_the_edge() {
+		let buttons = grid();
+		assert_eq!(next_focus(&buttons, button(2), Vec2::X), None);
+		assert_eq!(next_focus(&buttons, button(0), Vec2::Y), None);
+	}
+
+	#[test]
+	fn sticks_move_once_per_push() {
+		let mut was_centered = true;
+		assert!(stick_pushed(Vec2::X, &mut was_centered));
+		assert!(!stick_pushed(Vec2::X, &mut was_centered));
+		assert!(!stick_pushed(Vec2::Y, &mut was_centered));
+
+		assert!(!stick_pushed(
+			Vec2::X * (NAVIGATION_DEADZONE * 0.5),
+			&mut was_centered
+		));
+		assert!(stick_pushed(Vec2::NEG_X, &mut was_centered));
+	}
+}
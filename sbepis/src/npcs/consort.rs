@@ -2,18 +2,26 @@ use bevy::prelude::*;
 use bevy::render::mesh::CapsuleUvProfile;
 use bevy_butler::*;
 use bevy_rapier3d::geometry::Collider;
+use jack_noir::plugin::{ChooseActionSet, ChosenAction};
 
+use crate::entity::movement::{ExecuteMovementSet, RandomInputSet};
 use crate::entity::spawner::{
 	EntitySpawned, EntitySpawnedSet, SpawnerActivated, SpawnerActivatedSet,
 };
-use crate::entity::{Healing, RandomInput, RotateTowardMovement, SpawnHealthBar};
-use crate::gridbox_material;
+use crate::entity::{Healing, Movement, RandomInput, RotateTowardMovement, SpawnHealthBar};
 use crate::main_bundles::Mob;
+use crate::npcs::decisions::NpcDecisions;
 use crate::npcs::NpcPlugin;
 use crate::questing::{QuestGiver, SpawnQuestMarker};
+use crate::{gridbox_material, ok_or_continue, some_or_continue};
 
 use super::name_tags::SpawnNameTag;
 
+const CONSORT_FOLLOW_SPEED: f32 = 1.0;
+/// How close a consort gets to whoever it's following before it stops.
+const CONSORT_FOLLOW_DISTANCE: f32 = 2.0;
+const CONSORT_FLEE_SPEED: f32 = 1.5;
+
 #[derive(Component)]
 pub struct Consort;
 
@@ -33,6 +41,7 @@ fn spawn_consort(
 	mut meshes: ResMut<Assets<Mesh>>,
 	mut materials: ResMut<Assets<StandardMaterial>>,
 	asset_server: Res<AssetServer>,
+	decisions: Res<NpcDecisions>,
 ) {
 	for ev in ev_spawner.read() {
 		if spawners.get(ev.spawner).is_err() {
@@ -53,6 +62,7 @@ fn spawn_consort(
 				QuestGiver::default(),
 				SpawnQuestMarker,
 				SpawnNameTag,
+				decisions.consort(),
 			))
 			.with_child((
 				Transform::from_translation(Vec3::Y * 0.5),
@@ -72,3 +82,32 @@ fn spawn_consort(
 		ev_spawned.send(EntitySpawned(ev.entity));
 	}
 }
+
+#[system(
+	plugin = NpcPlugin, schedule = Update,
+	after = ChooseActionSet,
+	after = RandomInputSet,
+	before = ExecuteMovementSet,
+)]
+fn update_consort_movement(
+	mut consorts: Query<(&Transform, &ChosenAction, &mut Movement), With<Consort>>,
+	targets: Query<&GlobalTransform>,
+	decisions: Res<NpcDecisions>,
+) {
+	for (transform, chosen_action, mut movement) in consorts.iter_mut() {
+		// Doing nothing in particular leaves them wandering around
+		let target = some_or_continue!(chosen_action.target);
+		let target_transform = ok_or_continue!(targets.get(target));
+		let to_target =
+			(target_transform.translation() - transform.translation).reject_from(*transform.up());
+		let action_type = &chosen_action.action.action_type;
+
+		movement.0 = if *action_type == decisions.flee {
+			-to_target.normalize_or_zero() * CONSORT_FLEE_SPEED
+		} else if *action_type == decisions.follow && to_target.length() > CONSORT_FOLLOW_DISTANCE {
+			to_target.normalize_or_zero() * CONSORT_FOLLOW_SPEED
+		} else {
+			Vec3::ZERO
+		};
+	}
+}
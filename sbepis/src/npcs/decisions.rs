@@ -0,0 +1,179 @@
+use std::sync::Arc;
+
+use bevy::prelude::*;
+use bevy_butler::*;
+use jack_noir::plugin::{Allegiances, ChooseActionSet, Decider, TargetStats};
+use jack_noir::{
+	ActionType, ActionWeight, Consideration, Curve, FactionId, FactionRegistry, PersonalValue,
+	UtilityCurve, WorldState,
+};
+
+use crate::entity::GelViscosity;
+use crate::npcs::NpcPlugin;
+use crate::prelude::PlayerBody;
+
+/// How far away NPCs notice other entities from.
+const NPC_PERCEPTION_RADIUS: f32 = 20.0;
+
+/// The factions and actions that NPCs decide between.
+///
+/// The factions themselves live in the [`FactionRegistry`], along with how they feel about each
+/// other.
+#[derive(Resource)]
+#[resource(plugin = NpcPlugin)]
+pub struct NpcDecisions {
+	pub consorts: FactionId,
+	pub imps: FactionId,
+	pub players: FactionId,
+
+	pub attack: Arc<ActionType>,
+	pub follow: Arc<ActionType>,
+	pub flee: Arc<ActionType>,
+	pub wander: Arc<ActionType>,
+}
+
+impl FromWorld for NpcDecisions {
+	fn from_world(world: &mut World) -> Self {
+		let mut factions = world.get_resource_or_insert_with(FactionRegistry::default);
+		let consorts = factions.add("Consorts");
+		let imps = factions.add("Imps");
+		let players = factions.add("Players");
+
+		factions.set_relation(consorts, players, 10);
+		factions.set_relation(consorts, imps, -10);
+		factions.set_relation(imps, imps, 5);
+		factions.set_relation(imps, players, -10);
+		factions.set_relation(imps, consorts, -5);
+
+		// Whoever's closest matters most
+		let nearest_first = UtilityCurve {
+			consideration: Consideration::Distance,
+			curve: Curve::Linear {
+				slope: -1.0 / NPC_PERCEPTION_RADIUS,
+				intercept: 1.0,
+			},
+			weight: 1.0,
+		};
+
+		Self {
+			consorts,
+			imps,
+			players,
+
+			attack: Arc::new(
+				ActionType::new("Attack", Some(false))
+					.with_utility_curve(nearest_first)
+					// Going in for the kill
+					.with_utility_curve(UtilityCurve {
+						consideration: Consideration::Health,
+						curve: Curve::Linear {
+							slope: -1.0,
+							intercept: 1.0,
+						},
+						weight: 1.0,
+					}),
+			),
+			follow: Arc::new(
+				ActionType::new("Follow", Some(true)).with_utility_curve(nearest_first),
+			),
+			flee: Arc::new(
+				ActionType::new("Flee", Some(false))
+					.with_utility_curve(nearest_first)
+					.with_utility_curve(UtilityCurve {
+						consideration: Consideration::Threat,
+						curve: Curve::Linear {
+							slope: 1.0,
+							intercept: 0.0,
+						},
+						weight: 1.0,
+					}),
+			),
+			wander: Arc::new(ActionType::new("Wander", None)),
+		}
+	}
+}
+
+impl NpcDecisions {
+	fn personal_value(&self, name: &str, weights: &[(&Arc<ActionType>, i32)]) -> PersonalValue {
+		PersonalValue {
+			name: name.to_string(),
+			weights: weights
+				.iter()
+				.map(|(action_type, weight)| ActionWeight {
+					action_type: (*action_type).clone(),
+					weight: *weight,
+				})
+				.collect(),
+		}
+	}
+
+	/// Consorts run from imps, and otherwise tag along with the player.
+	pub fn consort(&self) -> (Decider, Allegiances, TargetStats) {
+		(
+			Decider {
+				personal_values: vec![
+					self.personal_value("Cowardice", &[(&self.flee, 5)]),
+					self.personal_value("Friendliness", &[(&self.follow, 1)]),
+					self.personal_value("Idleness", &[(&self.wander, 0)]),
+				],
+				perception_radius: NPC_PERCEPTION_RADIUS,
+				state: WorldState::new(),
+				goal: WorldState::new(),
+			},
+			Allegiances::from_iter([(self.consorts, 1)]),
+			TargetStats::default(),
+		)
+	}
+
+	/// Imps go after anything that isn't an imp, the player most of all.
+	pub fn imp(&self) -> (Decider, Allegiances, TargetStats) {
+		(
+			Decider {
+				personal_values: vec![
+					self.personal_value("Mischief", &[(&self.attack, 1)]),
+					self.personal_value("Idleness", &[(&self.wander, 0)]),
+				],
+				perception_radius: NPC_PERCEPTION_RADIUS,
+				state: WorldState::new(),
+				goal: WorldState::new(),
+			},
+			Allegiances::from_iter([(self.imps, 1)]),
+			TargetStats {
+				health: 1.0,
+				threat: 1.0,
+			},
+		)
+	}
+
+	pub fn player(&self) -> (Allegiances, TargetStats) {
+		(
+			Allegiances::from_iter([(self.players, 1)]),
+			TargetStats::default(),
+		)
+	}
+}
+
+#[system(
+	plugin = NpcPlugin, schedule = Update,
+)]
+fn give_player_allegiances(
+	players: Query<Entity, Added<PlayerBody>>,
+	mut commands: Commands,
+	decisions: Res<NpcDecisions>,
+) {
+	for player in players.iter() {
+		commands.entity(player).insert(decisions.player());
+	}
+}
+
+#[system(
+	plugin = NpcPlugin, schedule = Update,
+	before = ChooseActionSet,
+)]
+fn update_target_stats(
+	mut targets: Query<(&GelViscosity, &mut TargetStats), Changed<GelViscosity>>,
+) {
+	for (viscosity, mut stats) in targets.iter_mut() {
+		stats.health = (viscosity.value / viscosity.max).clamp(0.0, 1.0);
+	}
+}
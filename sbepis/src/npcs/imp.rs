@@ -1,22 +1,32 @@
-use std::f32::consts::PI;
+use std::f32::consts::{PI, TAU};
 use std::time::Duration;
 
+use bevy::ecs::entity::EntityHashSet;
 use bevy::gltf::GltfMaterialName;
 use bevy::prelude::*;
 use bevy::scene::SceneInstanceReady;
 use bevy_butler::*;
 use bevy_rapier3d::geometry::Collider;
+use bevy_rapier3d::plugin::RapierContext;
+use bevy_rapier3d::prelude::QueryFilter;
+use jack_noir::plugin::{ChooseActionSet, ChosenAction};
+use rand::Rng;
 
+use crate::entity::movement::ExecuteMovementSet;
 use crate::entity::spawner::{
 	EntitySpawned, EntitySpawnedSet, SpawnerActivated, SpawnerActivatedSet,
 };
 use crate::entity::{
 	EntityKilled, EntityKilledSet, GelViscosity, Movement, RotateTowardMovement, SpawnHealthBar,
-	TargetPlayer,
 };
+use crate::fray::FrayMusic;
+use crate::grist::{DropsGrist, GristAmounts, GristType};
 use crate::main_bundles::Mob;
+use crate::npcs::decisions::NpcDecisions;
 use crate::npcs::NpcPlugin;
-use crate::player_controller::weapons::EntityDamaged;
+use crate::player_controller::death::PlayerDead;
+use crate::player_controller::weapons::{EntityDamaged, EntityHit, EntityHitSet};
+use crate::replay::GameRng;
 use crate::util::AnimationRootReference;
 use crate::{ok_or_continue, some_or_return};
 
@@ -38,6 +48,8 @@ pub struct ImpAssets {
 	pub ambient_sound_2: Handle<AudioSource>,
 	pub hurt_sound: Handle<AudioSource>,
 	pub death_sound: Handle<AudioSource>,
+	/// One for each kind of grist imps can be made of.
+	pub variants: Vec<ImpVariant>,
 
 	pub sound_effect_variance: f32,
 
@@ -46,27 +58,58 @@ pub struct ImpAssets {
 }
 
 impl ImpAssets {
-	pub fn random_ambient_sound(&self) -> &Handle<AudioSource> {
-		if rand::random::<f32>() < 0.5 {
+	pub fn random_ambient_sound(&self, rng: &mut impl Rng) -> &Handle<AudioSource> {
+		if rng.gen::<f32>() < 0.5 {
 			&self.ambient_sound_1
 		} else {
 			&self.ambient_sound_2
 		}
 	}
 
-	pub fn random_sound_effect_variance(&self) -> f32 {
-		rand::random::<f32>() * self.sound_effect_variance * 2.0 + 1.0 - self.sound_effect_variance
+	pub fn random_sound_effect_variance(&self, rng: &mut impl Rng) -> f32 {
+		rng.gen::<f32>() * self.sound_effect_variance * 2.0 + 1.0 - self.sound_effect_variance
 	}
 
-	pub fn random_ambient_sound_time(&self) -> Duration {
+	pub fn random_ambient_sound_time(&self, rng: &mut impl Rng) -> Duration {
 		Duration::from_secs_f32(
-			rand::random::<f32>() * self.ambient_sound_time_variance.as_secs_f32() * 2.0
+			rng.gen::<f32>() * self.ambient_sound_time_variance.as_secs_f32() * 2.0
 				+ self.ambient_sound_time.as_secs_f32()
 				- self.ambient_sound_time_variance.as_secs_f32(),
 		)
 	}
+
+	pub fn random_variant(&self, rng: &mut impl Rng) -> &ImpVariant {
+		&self.variants[rng.gen_range(0..self.variants.len())]
+	}
+}
+
+/// What kind of grist an imp is made of, which colors its body and decides what it drops. Shale
+/// imps, the black ones from the concept art in `art/Shaleimp.png`, are the original.
+#[derive(Component, Clone)]
+pub struct ImpVariant {
+	pub grist_type: GristType,
+	pub body_material: Handle<StandardMaterial>,
+}
+
+impl ImpVariant {
+	/// Some build grist, like every underling, plus some of its own kind.
+	pub fn drops(&self) -> GristAmounts {
+		let mut drops = GristAmounts::from([(GristType::Build, IMP_BUILD_GRIST_DROP)]);
+		*drops.entry(self.grist_type).or_default() += IMP_GRIST_DROP;
+		drops
+	}
 }
 
+/// The kinds of grist imps come in.
+const IMP_GRIST_TYPES: [GristType; 4] = [
+	GristType::Shale,
+	GristType::Amber,
+	GristType::Ruby,
+	GristType::Cobalt,
+];
+const IMP_BUILD_GRIST_DROP: u32 = 4;
+const IMP_GRIST_DROP: u32 = 2;
+
 #[derive(Component)]
 pub struct ImpAnimations {
 	pub idle: AnimationNodeIndex,
@@ -77,13 +120,28 @@ pub struct ImpAnimations {
 #[system(
 	plugin = NpcPlugin, schedule = Startup,
 )]
-fn setup_imp_assets(mut commands: Commands, asset_server: Res<AssetServer>) {
+fn setup_imp_assets(
+	mut commands: Commands,
+	asset_server: Res<AssetServer>,
+	mut materials: ResMut<Assets<StandardMaterial>>,
+) {
 	commands.insert_resource(ImpAssets {
 		model: asset_server.load("imp.glb"),
 		ambient_sound_1: asset_server.load("imp_ambient_1.ogg"),
 		ambient_sound_2: asset_server.load("imp_ambient_2.ogg"),
 		hurt_sound: asset_server.load("imp_hurt.ogg"),
 		death_sound: asset_server.load("imp_death.ogg"),
+		variants: IMP_GRIST_TYPES
+			.into_iter()
+			.map(|grist_type| ImpVariant {
+				grist_type,
+				body_material: materials.add(StandardMaterial {
+					base_color: grist_type.color().into(),
+					perceptual_roughness: 0.6,
+					..default()
+				}),
+			})
+			.collect(),
 
 		sound_effect_variance: 0.3,
 		ambient_sound_time: Duration::from_secs_f32(5.0),
@@ -124,21 +182,28 @@ fn spawn_imp(
 	mut commands: Commands,
 	imp_assets: Res<ImpAssets>,
 	gltfs: Res<Assets<Gltf>>,
+	decisions: Res<NpcDecisions>,
+	mut rng: GameRng,
 ) {
 	let imp_gltf = some_or_return!(gltfs.get(&imp_assets.model));
+	let rng = rng.stream("spawn_imp");
 
 	for imp in imps.iter() {
+		let variant = imp_assets.random_variant(rng);
 		commands
 			.entity(imp)
 			.insert((
 				SceneRoot(imp_gltf.scenes[0].clone()),
 				Mob,
 				SpawnHealthBar,
-				TargetPlayer,
+				ImpBehaviour::default(),
+				decisions.imp(),
 				RotateTowardMovement,
 				Imp,
 				SpawnNameTag,
 				AmbientSoundTimer::default(),
+				DropsGrist(variant.drops()),
+				variant.clone(),
 			))
 			.remove::<InsertImpAssets>()
 			.with_child((
@@ -153,6 +218,7 @@ fn spawn_imp(
 				 mut animation_graphs: ResMut<Assets<AnimationGraph>>,
 				 children: Query<&Children>,
 				 material_names: Query<&GltfMaterialName>,
+				 variants: Query<&ImpVariant>,
 				 name_tag_assets: Res<NameTagAssets>| {
 					let imp_gltf = gltfs
 						.get(&imp_assets.model)
@@ -187,15 +253,26 @@ fn spawn_imp(
 						imp_animations,
 					));
 
-					for child in children.iter_descendants(trigger.entity()).filter(|child| {
-						material_names
-							.get(*child)
-							.is_ok_and(|name| name.0 == "Candy")
-					}) {
-						commands
-							.entity(child)
-							.remove::<MeshMaterial3d<StandardMaterial>>()
-							.insert(MeshMaterial3d(name_tag_assets.master_material.clone()));
+					let variant = variants.get(trigger.entity()).ok();
+					for child in children.iter_descendants(trigger.entity()) {
+						match material_names.get(child).map(|name| name.0.as_str()) {
+							Ok("Candy") => {
+								commands
+									.entity(child)
+									.remove::<MeshMaterial3d<StandardMaterial>>()
+									.insert(MeshMaterial3d(
+										name_tag_assets.master_material.clone(),
+									));
+							}
+							Ok("Imp Body") => {
+								if let Some(variant) = variant {
+									commands
+										.entity(child)
+										.insert(MeshMaterial3d(variant.body_material.clone()));
+								}
+							}
+							_ => {}
+						}
 					}
 
 					commands
@@ -213,18 +290,31 @@ fn spawn_imp(
 	after = EntitySpawnedSet,
 )]
 fn update_imp_animations(
-	mut imps: Query<(&Movement, &AnimationRootReference), With<Imp>>,
+	mut imps: Query<(&Movement, &ImpBehaviour, &AnimationRootReference), With<Imp>>,
 	mut animations: Query<(
 		&mut AnimationPlayer,
 		&mut AnimationTransitions,
 		&ImpAnimations,
 	)>,
 ) {
-	for (movement, scene_root) in imps.iter_mut() {
+	for (movement, behaviour, scene_root) in imps.iter_mut() {
 		let (mut animation_player, mut transitions, animations) =
-			animations.get_mut(scene_root.0).unwrap();
+			ok_or_continue!(animations.get_mut(scene_root.0));
 
-		if movement.0.length() > 0.0 {
+		if matches!(behaviour.state, ImpState::WindUp | ImpState::Attack) {
+			// Once through per swing, the wind-up is the start of the animation
+			if transitions
+				.get_main_animation()
+				.map(|index| index != animations.attack)
+				.unwrap_or(true)
+			{
+				transitions.play(
+					&mut animation_player,
+					animations.attack,
+					Duration::from_secs_f32(0.1),
+				);
+			}
+		} else if movement.0.length() > 0.0 {
 			if transitions
 				.get_main_animation()
 				.map(|index| index != animations.run)
@@ -263,7 +353,10 @@ fn imp_hurt_sound(
 	mut imps: Query<(&GelViscosity, &GlobalTransform, &mut AmbientSoundTimer), With<Imp>>,
 	mut commands: Commands,
 	imp_assets: Res<ImpAssets>,
+	mut rng: GameRng,
 ) {
+	let rng = rng.stream("imp_hurt_sound");
+
 	for ev in ev_damaged.read() {
 		let (health, transform, mut sound_timer) = ok_or_continue!(imps.get_mut(ev.victim));
 
@@ -276,11 +369,11 @@ fn imp_hurt_sound(
 			Transform::from_translation(transform.translation()),
 			AudioPlayer(imp_assets.hurt_sound.clone()),
 			PlaybackSettings::DESPAWN
-				.with_speed(imp_assets.random_sound_effect_variance())
+				.with_speed(imp_assets.random_sound_effect_variance(rng))
 				.with_spatial(true),
 		));
 
-		sound_timer.0 = imp_assets.random_ambient_sound_time();
+		sound_timer.0 = imp_assets.random_ambient_sound_time(rng);
 	}
 }
 
@@ -293,7 +386,10 @@ fn imp_kill_sound(
 	mut imps: Query<(&GlobalTransform, &mut AmbientSoundTimer), With<Imp>>,
 	mut commands: Commands,
 	imp_assets: Res<ImpAssets>,
+	mut rng: GameRng,
 ) {
+	let rng = rng.stream("imp_kill_sound");
+
 	for ev in ev_damaged.read() {
 		let (transform, mut sound_timer) = ok_or_continue!(imps.get_mut(ev.0));
 
@@ -301,11 +397,11 @@ fn imp_kill_sound(
 			Transform::from_translation(transform.translation()),
 			AudioPlayer(imp_assets.death_sound.clone()),
 			PlaybackSettings::DESPAWN
-				.with_speed(imp_assets.random_sound_effect_variance())
+				.with_speed(imp_assets.random_sound_effect_variance(rng))
 				.with_spatial(true),
 		));
 
-		sound_timer.0 = imp_assets.random_ambient_sound_time();
+		sound_timer.0 = imp_assets.random_ambient_sound_time(rng);
 	}
 }
 
@@ -320,21 +416,352 @@ fn imp_ambient_sound(
 	mut commands: Commands,
 	imp_assets: Res<ImpAssets>,
 	time: Res<Time>,
+	mut rng: GameRng,
 ) {
+	let rng = rng.stream("imp_ambient_sound");
+
 	for (transform, mut sound_timer) in imps.iter_mut() {
 		sound_timer.0 = match sound_timer.0.checked_sub(time.delta()) {
 			Some(time) => time,
 			None => {
 				commands.spawn((
 					Transform::from_translation(transform.translation()),
-					AudioPlayer(imp_assets.random_ambient_sound().clone()),
+					AudioPlayer(imp_assets.random_ambient_sound(rng).clone()),
 					PlaybackSettings::DESPAWN
-						.with_speed(imp_assets.random_sound_effect_variance())
+						.with_speed(imp_assets.random_sound_effect_variance(rng))
 						.with_spatial(true),
 				));
 
-				imp_assets.random_ambient_sound_time()
+				imp_assets.random_ambient_sound_time(rng)
+			}
+		}
+	}
+}
+
+/// How far away an imp can see its target from.
+const IMP_SIGHT_RANGE: f32 = 15.0;
+/// Half the angle of the cone in front of a wandering imp that it can see in.
+const IMP_SIGHT_HALF_ANGLE: f32 = PI / 3.0;
+const IMP_EYE_HEIGHT: f32 = 0.75;
+const IMP_ATTACK_RANGE: f32 = 1.2;
+const IMP_DAMAGE: f32 = 0.5;
+/// Imps run away once their gel viscosity drops below this fraction of the max.
+const IMP_FLEE_VISCOSITY: f32 = 0.2;
+
+const IMP_WANDER_SPEED: f32 = 0.4;
+const IMP_CHASE_SPEED: f32 = 1.0;
+const IMP_FLEE_SPEED: f32 = 1.2;
+
+const IMP_NOTICE_TIME: Duration = Duration::from_millis(500);
+const IMP_WIND_UP_TIME: Duration = Duration::from_millis(400);
+const IMP_ATTACK_TIME: Duration = Duration::from_millis(300);
+const IMP_RECOVER_TIME: Duration = Duration::from_millis(800);
+/// How long an imp keeps chasing or fleeing after it loses sight of its target.
+const IMP_LOSE_INTEREST_TIME: Duration = Duration::from_secs(3);
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ImpState {
+	#[default]
+	Wander,
+	/// Stops and stares for a moment after spotting its target.
+	Notice,
+	Chase,
+	/// Telegraphs the attack, so the target has a chance to get out of the way.
+	WindUp,
+	Attack,
+	Recover,
+	Flee,
+}
+
+#[derive(Component, Default, Debug)]
+pub struct ImpBehaviour {
+	pub state: ImpState,
+	pub time_in_state: Duration,
+	/// Whoever the imp decided to attack.
+	pub target: Option<Entity>,
+	pub sees_target: bool,
+	/// Where the target was last seen, so imps can chase them around corners.
+	pub last_seen: Option<Vec3>,
+	pub time_since_seen: Duration,
+	wander_direction: Vec3,
+	wander_time: Duration,
+}
+
+impl ImpBehaviour {
+	fn set_state(&mut self, state: ImpState) {
+		if self.state != state {
+			self.state = state;
+			self.time_in_state = Duration::ZERO;
+		}
+	}
+
+	/// Moves on to whatever state comes next, given how far away the target was last seen and
+	/// whether the imp is hurt badly enough to run. Returns the target if a swing just landed.
+	fn advance(
+		&mut self,
+		delta: Duration,
+		target_distance: f32,
+		badly_hurt: bool,
+	) -> Option<Entity> {
+		self.time_in_state += delta;
+		if self.sees_target {
+			self.time_since_seen = Duration::ZERO;
+		} else {
+			self.time_since_seen += delta;
+		}
+
+		let lost_interest = self.time_since_seen >= IMP_LOSE_INTEREST_TIME;
+		let in_reach = self.sees_target && target_distance <= IMP_ATTACK_RANGE;
+		let mut hit = None;
+
+		match self.state {
+			ImpState::Wander => {
+				if self.sees_target {
+					self.set_state(ImpState::Notice);
+				}
+			}
+			ImpState::Notice => {
+				if self.time_in_state >= IMP_NOTICE_TIME {
+					self.set_state(ImpState::Chase);
+				}
+			}
+			ImpState::Chase => {
+				if lost_interest {
+					self.set_state(ImpState::Wander);
+				} else if in_reach {
+					self.set_state(ImpState::WindUp);
+				}
+			}
+			ImpState::WindUp => {
+				if self.time_in_state >= IMP_WIND_UP_TIME {
+					self.set_state(ImpState::Attack);
+					// The target might have stepped back during the wind-up
+					hit = self.target.filter(|_| in_reach);
+				}
+			}
+			ImpState::Attack => {
+				if self.time_in_state >= IMP_ATTACK_TIME {
+					self.set_state(ImpState::Recover);
+				}
+			}
+			ImpState::Recover => {
+				if self.time_in_state >= IMP_RECOVER_TIME {
+					self.set_state(ImpState::Chase);
+				}
+			}
+			ImpState::Flee => {
+				if lost_interest {
+					self.set_state(ImpState::Wander);
+				}
 			}
 		}
+
+		if self.sees_target && badly_hurt {
+			self.set_state(ImpState::Flee);
+		}
+
+		hit
+	}
+}
+
+#[system(
+	plugin = NpcPlugin, schedule = Update,
+	after = ChooseActionSet,
+	before = update_imp_behaviour,
+)]
+fn perceive_target(
+	mut imps: Query<(&Transform, &ChosenAction, &mut ImpBehaviour)>,
+	targets: Query<(&GlobalTransform, Has<PlayerDead>)>,
+	parents: Query<&Parent>,
+	rapier_context: Query<&RapierContext>,
+	decisions: Res<NpcDecisions>,
+) {
+	let rapier_context = some_or_return!(rapier_context.get_single().ok());
+
+	for (transform, chosen_action, mut behaviour) in imps.iter_mut() {
+		behaviour.target = chosen_action
+			.target
+			.filter(|_| chosen_action.action.action_type == decisions.attack);
+		let Some((target, (target_transform, target_dead))) = behaviour
+			.target
+			.and_then(|target| Some((target, targets.get(target).ok()?)))
+		else {
+			behaviour.sees_target = false;
+			continue;
+		};
+
+		let eye = transform.translation + transform.up() * IMP_EYE_HEIGHT;
+		let to_target = target_transform.translation() - eye;
+		let distance = to_target.length();
+		let direction = to_target.normalize_or_zero();
+
+		// Once it's alerted, an imp keeps track of its target even when it isn't facing them
+		let in_view = behaviour.state != ImpState::Wander
+			|| transform.forward().angle_between(direction) <= IMP_SIGHT_HALF_ANGLE;
+		// Starting just outside the imp so it doesn't see its own collider
+		let unobstructed = || {
+			rapier_context
+				.cast_ray(
+					eye + direction * 0.3,
+					direction,
+					distance,
+					true,
+					QueryFilter::new().exclude_sensors(),
+				)
+				.is_some_and(|(hit_entity, _)| {
+					hit_entity == target
+						|| parents
+							.iter_ancestors(hit_entity)
+							.any(|ancestor| ancestor == target)
+				})
+		};
+
+		// Imps lose interest in a dead player, so they wander off while it respawns
+		behaviour.sees_target =
+			!target_dead && distance <= IMP_SIGHT_RANGE && in_view && unobstructed();
+		if behaviour.sees_target {
+			behaviour.last_seen = Some(target_transform.translation());
+		}
+	}
+}
+
+#[system(
+	plugin = NpcPlugin, schedule = Update,
+	before = ExecuteMovementSet,
+	in_set = EntityHitSet,
+)]
+fn update_imp_behaviour(
+	mut imps: Query<(
+		Entity,
+		&mut Transform,
+		&GelViscosity,
+		&mut Movement,
+		&mut ImpBehaviour,
+	)>,
+	fray_music: Query<&FrayMusic>,
+	time: Res<Time>,
+	mut ev_hit: EventWriter<EntityHit>,
+	mut rng: GameRng,
+) {
+	let fray_music = some_or_return!(fray_music.get_single().ok());
+	let rng = rng.stream("update_imp_behaviour");
+
+	for (imp, mut transform, viscosity, mut movement, mut behaviour) in imps.iter_mut() {
+		let up = transform.up();
+		let to_last_seen = behaviour
+			.last_seen
+			.map(|last_seen| (last_seen - transform.translation).reject_from(*up))
+			.unwrap_or_default();
+		let badly_hurt = viscosity.value < viscosity.max * IMP_FLEE_VISCOSITY;
+
+		if let Some(target) = behaviour.advance(time.delta(), to_last_seen.length(), badly_hurt) {
+			ev_hit.send(EntityHit {
+				victim: target,
+				perpetrator: imp,
+				allies: EntityHashSet::from_iter([imp]),
+				damage: fray_music.modify_fray_damage(IMP_DAMAGE),
+				fray_modifier: fray_music.modify_fray_damage(1.0),
+			});
+		}
+
+		movement.0 = match behaviour.state {
+			ImpState::Wander => {
+				behaviour.wander_time = behaviour.wander_time.saturating_sub(time.delta());
+				if behaviour.wander_time.is_zero() {
+					// Sometimes it just stands around for a bit
+					behaviour.wander_direction = if rng.gen_bool(0.3) {
+						Vec3::ZERO
+					} else {
+						Quat::from_axis_angle(*up, rng.gen_range(0.0..TAU)) * *transform.forward()
+					};
+					behaviour.wander_time = Duration::from_secs_f32(rng.gen_range(1.0..3.0));
+				}
+				behaviour.wander_direction * IMP_WANDER_SPEED
+			}
+			ImpState::Chase => to_last_seen.normalize_or_zero() * IMP_CHASE_SPEED,
+			ImpState::Flee => -to_last_seen.normalize_or_zero() * IMP_FLEE_SPEED,
+			ImpState::Notice | ImpState::WindUp | ImpState::Attack | ImpState::Recover => {
+				// Standing still, so turn toward the target by hand
+				if to_last_seen.length() > 0.0 {
+					transform.look_to(to_last_seen, up);
+				}
+				Vec3::ZERO
+			}
+		};
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const FRAME: Duration = Duration::from_millis(16);
+
+	fn spotted_target() -> ImpBehaviour {
+		ImpBehaviour {
+			target: Some(Entity::from_raw(1)),
+			sees_target: true,
+			..default()
+		}
+	}
+
+	#[test]
+	fn imps_notice_chase_and_attack() {
+		let mut behaviour = spotted_target();
+		let target = behaviour.target;
+
+		assert_eq!(behaviour.advance(FRAME, 5.0, false), None);
+		assert_eq!(behaviour.state, ImpState::Notice);
+
+		behaviour.advance(IMP_NOTICE_TIME, 5.0, false);
+		assert_eq!(behaviour.state, ImpState::Chase);
+
+		behaviour.advance(FRAME, 5.0, false);
+		assert_eq!(behaviour.state, ImpState::Chase);
+		behaviour.advance(FRAME, 1.0, false);
+		assert_eq!(behaviour.state, ImpState::WindUp);
+
+		assert_eq!(behaviour.advance(FRAME, 1.0, false), None);
+		assert_eq!(behaviour.advance(IMP_WIND_UP_TIME, 1.0, false), target);
+		assert_eq!(behaviour.state, ImpState::Attack);
+
+		behaviour.advance(IMP_ATTACK_TIME, 1.0, false);
+		assert_eq!(behaviour.state, ImpState::Recover);
+		behaviour.advance(IMP_RECOVER_TIME, 1.0, false);
+		assert_eq!(behaviour.state, ImpState::Chase);
+	}
+
+	#[test]
+	fn attacks_miss_targets_that_step_back() {
+		let mut behaviour = spotted_target();
+		behaviour.set_state(ImpState::WindUp);
+
+		assert_eq!(behaviour.advance(IMP_WIND_UP_TIME, 3.0, false), None);
+		assert_eq!(behaviour.state, ImpState::Attack);
+	}
+
+	#[test]
+	fn hurt_imps_flee_until_they_lose_sight() {
+		let mut behaviour = spotted_target();
+		behaviour.set_state(ImpState::Chase);
+
+		behaviour.advance(FRAME, 5.0, true);
+		assert_eq!(behaviour.state, ImpState::Flee);
+
+		behaviour.sees_target = false;
+		behaviour.advance(IMP_LOSE_INTEREST_TIME - FRAME, 5.0, true);
+		assert_eq!(behaviour.state, ImpState::Flee);
+		behaviour.advance(FRAME, 5.0, true);
+		assert_eq!(behaviour.state, ImpState::Wander);
+	}
+
+	#[test]
+	fn chasing_imps_give_up_on_lost_targets() {
+		let mut behaviour = spotted_target();
+		behaviour.set_state(ImpState::Chase);
+		behaviour.sees_target = false;
+
+		behaviour.advance(IMP_LOSE_INTEREST_TIME, 5.0, false);
+		assert_eq!(behaviour.state, ImpState::Wander);
 	}
 }
@@ -1,14 +1,18 @@
 use bevy::pbr::MaterialPlugin;
 use bevy_butler::*;
 use bevy_common_assets::ron::RonAssetPlugin;
+use jack_noir::plugin::JackNoirPlugin;
 use name_tags::{AvailableNames, CandyMaterial};
 
 pub mod consort;
+pub mod decisions;
 pub mod imp;
 pub mod name_tags;
+pub mod reputation;
 
 #[butler_plugin(build(
 	add_plugins(RonAssetPlugin::<AvailableNames>::new(&["names.ron"])),
 	add_plugins(MaterialPlugin::<CandyMaterial>::default()),
+	add_plugins(JackNoirPlugin),
 ))]
 pub struct NpcPlugin;
@@ -10,11 +10,13 @@ use bevy_hanabi::prelude::*;
 use faker_rand::en_us::names::FirstName;
 use meshtext::{Face, MeshGenerator, MeshText, TextSection};
 use rand::seq::{IteratorRandom, SliceRandom};
-use serde::Deserialize;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
 
 use crate::entity::spawner::EntitySpawnedSet;
 use crate::entity::{EntityKilled, EntityKilledSet};
 use crate::npcs::NpcPlugin;
+use crate::replay::GameRng;
 use crate::some_or_return;
 
 #[derive(Resource)]
@@ -38,7 +40,7 @@ pub struct AvailableNames {
 	names: Vec<NameTag>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 enum NameTier {
 	Past,
 	Pgo,
@@ -54,12 +56,18 @@ pub struct SpawnNameTag;
 #[derive(Component)]
 pub struct NameTagged(pub NameTag);
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NameTag {
 	name: String,
 	tier: Option<NameTier>,
 }
 
+impl NameTag {
+	pub fn name(&self) -> &str {
+		&self.name
+	}
+}
+
 #[derive(Resource)]
 #[resource(plugin = NpcPlugin)]
 pub struct FontMeshGenerator {
@@ -260,26 +268,32 @@ fn spawn_name_tags(
 	mut commands: Commands,
 	asset: Res<NameTagAssets>,
 	mut assets: ResMut<Assets<AvailableNames>>,
-	entities: Query<Entity, With<SpawnNameTag>>,
+	entities: Query<(Entity, Option<&NameTagged>), With<SpawnNameTag>>,
 	mut meshes: ResMut<Assets<Mesh>>,
 	mut font_mesh_generator: ResMut<FontMeshGenerator>,
+	mut rng: GameRng,
 ) {
 	let names = some_or_return!(assets.get_mut(&asset.names));
-
-	for entity in entities.iter() {
-		let name_tag = {
+	let rng = rng.stream("spawn_name_tags");
+
+	for (entity, name_tagged) in entities.iter() {
+		// Entities loaded from a save already know their name
+		let name_tag = if let Some(NameTagged(name_tag)) = name_tagged {
+			names.names.retain(|name| name.name != name_tag.name);
+			name_tag.clone()
+		} else {
 			let opt = names
 				.names
 				.iter()
 				.enumerate()
-				.choose(&mut rand::thread_rng())
+				.choose(rng)
 				.map(|(i, name)| (i, name.clone()));
 			if let Some((i, name_tag)) = opt {
 				names.names.swap_remove(i);
 				name_tag
 			} else {
 				NameTag {
-					name: rand::random::<FirstName>().to_string(),
+					name: rng.gen::<FirstName>().to_string(),
 					tier: None,
 				}
 			}
@@ -302,13 +316,9 @@ fn spawn_name_tags(
 			Some(NameTier::Alchemiter) => {
 				NameTagShader::Standard(asset.alchemiter_material.clone())
 			}
-			Some(NameTier::Denizen) => NameTagShader::Standard(
-				asset
-					.denizen_materials
-					.choose(&mut rand::thread_rng())
-					.unwrap()
-					.clone(),
-			),
+			Some(NameTier::Denizen) => {
+				NameTagShader::Standard(asset.denizen_materials.choose(rng).unwrap().clone())
+			}
 			Some(NameTier::Master) => NameTagShader::Candy(asset.master_material.clone()),
 		};
 		let scale = match name_tag.tier {
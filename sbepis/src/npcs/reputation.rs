@@ -0,0 +1,89 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use bevy_butler::*;
+use jack_noir::plugin::{Allegiances, ChooseActionSet};
+use jack_noir::FactionRegistry;
+
+use crate::entity::{EntityKilled, EntityKilledSet};
+use crate::npcs::NpcPlugin;
+use crate::player_controller::weapons::EntityHit;
+use crate::util::find_in_ancestors;
+use crate::{ok_or_continue, some_or_continue};
+
+/// How much a faction sours on someone for hitting one of its friends.
+const HIT_ALLY_REPUTATION: i32 = -1;
+/// How much a faction sours on someone for killing one of its members.
+const KILL_MEMBER_REPUTATION: i32 = -3;
+/// How much a faction warms up to someone for killing one of its enemies.
+const KILL_ENEMY_REPUTATION: i32 = 1;
+
+#[system(
+	plugin = NpcPlugin, schedule = Update,
+	after = EntityKilledSet,
+	before = ChooseActionSet,
+)]
+fn update_reputation(
+	mut ev_hit: EventReader<EntityHit>,
+	mut ev_killed: EventReader<EntityKilled>,
+	allegiances: Query<(Entity, &Allegiances)>,
+	parents: Query<&Parent>,
+	mut factions: ResMut<FactionRegistry>,
+) {
+	// Kills come from this frame's hits
+	let mut killers = HashMap::new();
+
+	for ev in ev_hit.read() {
+		let (_, perpetrator) = ok_or_continue!(allegiances.get(ev.perpetrator));
+		let (victim_entity, victim) =
+			some_or_continue!(find_in_ancestors(ev.victim, &allegiances, &parents));
+		killers.insert(victim_entity, ev.perpetrator);
+
+		for victim_faction in victim.members() {
+			for perpetrator_faction in perpetrator.members() {
+				if victim_faction != perpetrator_faction
+					&& factions.relation(victim_faction, perpetrator_faction) > 0
+				{
+					factions.change_reputation(
+						victim_faction,
+						perpetrator_faction,
+						HIT_ALLY_REPUTATION,
+					);
+				}
+			}
+		}
+	}
+
+	for EntityKilled(victim) in ev_killed.read() {
+		let (_, victim_allegiances) = ok_or_continue!(allegiances.get(*victim));
+		let killer = some_or_continue!(killers.get(victim));
+		let (_, killer_allegiances) = ok_or_continue!(allegiances.get(*killer));
+
+		let victim_factions = victim_allegiances.members().collect::<Vec<_>>();
+		let enemies = factions
+			.iter()
+			.map(|faction| faction.id)
+			.filter(|faction| {
+				victim_factions
+					.iter()
+					.any(|victim_faction| factions.relation(*faction, *victim_faction) < 0)
+			})
+			.collect::<Vec<_>>();
+
+		for killer_faction in killer_allegiances.members() {
+			for victim_faction in victim_factions.iter() {
+				if *victim_faction != killer_faction {
+					factions.change_reputation(
+						*victim_faction,
+						killer_faction,
+						KILL_MEMBER_REPUTATION,
+					);
+				}
+			}
+			for enemy in enemies.iter() {
+				if *enemy != killer_faction {
+					factions.change_reputation(*enemy, killer_faction, KILL_ENEMY_REPUTATION);
+				}
+			}
+		}
+	}
+}
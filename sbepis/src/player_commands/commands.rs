@@ -1,31 +1,52 @@
 use bevy::prelude::*;
 use bevy_butler::*;
+use bevy_rapier3d::prelude::*;
+use serde::Deserialize;
 use soundyrust::Note;
 
+use crate::camera::PlayerCamera;
+use crate::entity::GelViscosity;
+use crate::inventory::ItemSpawner;
+use crate::npcs::imp::InsertImpAssets;
+use crate::player_commands::registry::{NoteCommandInvoked, NoteCommandRegistry};
 use crate::player_commands::{NotePlayedSet, NotesCleared, NotesClearedSet, PlayerCommandsPlugin};
+use crate::prelude::PlayerBody;
 use crate::some_or_return;
+use crate::util::find_in_ancestors;
 
-use crate::player_commands::notes::NotePlayed;
+use crate::player_commands::notes::{NoteDuration, NotePlayed, NoteReleased};
 
 #[system(
 	plugin = PlayerCommandsPlugin, schedule = Update,
-	generics = PingCommandEvent,
 	in_set = CommandSentSet,
-	run_if = on_event::<NotePlayed>,
+	run_if = on_event::<NotePlayed>.or(on_event::<NoteReleased>),
 )]
-#[system(
-	plugin = PlayerCommandsPlugin, schedule = Update,
-	generics = KillCommandEvent,
-	in_set = CommandSentSet,
-	run_if = on_event::<NotePlayed>,
-)]
-fn check_note_patterns<T: Event + NotePatternEvent>(
+fn check_note_patterns(
 	note_holder: Res<NotePatternPlayer>,
-	mut ev_command: EventWriter<T>,
+	registry: Res<NoteCommandRegistry>,
+	rapier_context: Query<&RapierContext>,
+	player_camera: Query<&GlobalTransform, With<PlayerCamera>>,
+	player_body: Query<Entity, With<PlayerBody>>,
+	healths: Query<Entity, With<GelViscosity>>,
+	parents: Query<&Parent>,
+	mut ev_command: EventWriter<NoteCommandInvoked>,
 	mut ev_command_sent: EventWriter<CommandSent>,
 ) {
-	let event = T::compare_notes(note_holder.current_pattern.as_slice());
-	let event = some_or_return!(event);
+	let event = registry.compare_notes(note_holder.current_pattern.as_slice());
+	let mut event = some_or_return!(event);
+
+	for arg in event.args.iter_mut() {
+		if let NoteArgument::Target(target) = arg {
+			*target = crosshair_target(
+				rapier_context.single(),
+				player_camera.single(),
+				player_body.single(),
+				&healths,
+				&parents,
+			);
+		}
+	}
+
 	ev_command.send(event);
 	ev_command_sent.send(CommandSent);
 }
@@ -33,7 +54,14 @@ fn check_note_patterns<T: Event + NotePatternEvent>(
 #[derive(Resource, Default)]
 #[resource(plugin = PlayerCommandsPlugin)]
 pub struct NotePatternPlayer {
-	pub current_pattern: Vec<Note>,
+	pub current_pattern: Vec<PlayedNote>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlayedNote {
+	pub note: Note,
+	/// `None` while it's still held, or if it was played before the fray music started keeping time.
+	pub duration: Option<NoteDuration>,
 }
 
 #[derive(Event)]
@@ -50,9 +78,25 @@ pub struct CommandSentSet;
 fn add_note_to_player(
 	mut player: ResMut<NotePatternPlayer>,
 	mut ev_note_played: EventReader<NotePlayed>,
+	mut ev_note_released: EventReader<NoteReleased>,
 ) {
+	for ev in ev_note_released.read() {
+		// The same note can't be held twice, so the latest one is the one being let go of
+		if let Some(played) = player
+			.current_pattern
+			.iter_mut()
+			.rev()
+			.find(|played| played.note == ev.note)
+		{
+			played.duration = ev.duration;
+		}
+	}
+
 	for ev in ev_note_played.read() {
-		player.current_pattern.push(ev.note);
+		player.current_pattern.push(PlayedNote {
+			note: ev.note,
+			duration: None,
+		});
 	}
 }
 
@@ -65,12 +109,6 @@ fn clear_player_notes(mut player: ResMut<NotePatternPlayer>) {
 	player.current_pattern.clear();
 }
 
-pub trait NotePatternEvent {
-	fn compare_notes(notes: &[Note]) -> Option<Self>
-	where
-		Self: Sized;
-}
-
 pub trait NoteSequence {
 	fn eat(self, notes: &[Note]) -> Option<Self>
 	where
@@ -93,49 +131,192 @@ pub trait NoteSequenceTyped<T> {
 		Self: Sized;
 }
 
-impl NoteSequenceTyped<bool> for &[Note] {
-	fn eat_type(self) -> Option<(bool, Self)>
+/// Types whose every value is played as a single note.
+pub trait NamedNotes: Sized + Copy + 'static {
+	const NAMED_NOTES: &'static [(Note, Self)];
+}
+
+impl<T: NamedNotes> NoteSequenceTyped<T> for &[Note] {
+	fn eat_type(self) -> Option<(T, Self)>
 	where
 		Self: Sized,
 	{
-		if self.starts_with(&[Note::A4]) {
-			Some((true, &self[1..]))
-		} else if self.starts_with(&[Note::C5]) {
-			Some((false, &self[1..]))
-		} else {
-			None
-		}
+		let (first, rest) = self.split_first()?;
+		T::NAMED_NOTES
+			.iter()
+			.find(|(note, _)| note == first)
+			.map(|(_, value)| (*value, rest))
 	}
 }
 
-#[derive(Event)]
-#[event(plugin = PlayerCommandsPlugin)]
-pub struct PingCommandEvent;
-
-impl PingCommandEvent {
-	const PATTERN: &'static [Note] = &[Note::C4, Note::D4, Note::E4];
+impl NamedNotes for bool {
+	const NAMED_NOTES: &'static [(Note, Self)] = &[(Note::A4, true), (Note::C5, false)];
 }
 
-impl NotePatternEvent for PingCommandEvent {
-	fn compare_notes(notes: &[Note]) -> Option<Self>
+/// Digits are the C major scale from C4 (0) up to E5 (9).
+pub const DIGIT_NOTES: [Note; 10] = [
+	Note::C4,
+	Note::D4,
+	Note::E4,
+	Note::F4,
+	Note::G4,
+	Note::A4,
+	Note::B4,
+	Note::C5,
+	Note::D5,
+	Note::E5,
+];
+/// Ends a number, since otherwise we couldn't tell where its digits stop.
+pub const NUMBER_TERMINATOR: Note = Note::F5;
+
+impl NoteSequenceTyped<u32> for &[Note] {
+	fn eat_type(self) -> Option<(u32, Self)>
 	where
 		Self: Sized,
 	{
-		let _notes = notes.eat(PingCommandEvent::PATTERN)?;
-		Some(PingCommandEvent)
+		let mut notes = self;
+		let mut value: Option<u32> = None;
+		loop {
+			let (first, rest) = notes.split_first()?;
+			if *first == NUMBER_TERMINATOR {
+				return value.map(|value| (value, rest));
+			}
+
+			let digit = DIGIT_NOTES.iter().position(|note| note == first)? as u32;
+			value = Some(value.unwrap_or(0).checked_mul(10)?.checked_add(digit)?);
+			notes = rest;
+		}
 	}
 }
 
+/// North is wherever the player is facing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelativeDirection {
+	North,
+	East,
+	South,
+	West,
+}
+
+impl RelativeDirection {
+	pub fn relative_to(self, transform: &GlobalTransform) -> Vec3 {
+		match self {
+			RelativeDirection::North => transform.forward().into(),
+			RelativeDirection::East => transform.right().into(),
+			RelativeDirection::South => transform.back().into(),
+			RelativeDirection::West => transform.left().into(),
+		}
+	}
+}
+
+impl NamedNotes for RelativeDirection {
+	const NAMED_NOTES: &'static [(Note, Self)] = &[
+		(Note::C5, RelativeDirection::North),
+		(Note::G4, RelativeDirection::East),
+		(Note::C4, RelativeDirection::South),
+		(Note::F4, RelativeDirection::West),
+	];
+}
+
+/// Whatever the player is looking at when the command gets sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CrosshairTarget;
+
+impl NamedNotes for CrosshairTarget {
+	const NAMED_NOTES: &'static [(Note, Self)] = &[(Note::B4, CrosshairTarget)];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum NoteArgumentType {
+	Bool,
+	UnsignedInt,
+	Direction,
+	Target,
+}
+
+impl NoteArgumentType {
+	pub fn eat(self, notes: &[Note]) -> Option<(NoteArgument, &[Note])> {
+		match self {
+			NoteArgumentType::Bool => notes
+				.eat_type()
+				.map(|(value, notes)| (NoteArgument::Bool(value), notes)),
+			NoteArgumentType::UnsignedInt => notes
+				.eat_type()
+				.map(|(value, notes)| (NoteArgument::UnsignedInt(value), notes)),
+			NoteArgumentType::Direction => notes
+				.eat_type()
+				.map(|(value, notes)| (NoteArgument::Direction(value), notes)),
+			NoteArgumentType::Target => NoteSequenceTyped::<CrosshairTarget>::eat_type(notes)
+				.map(|(_, notes)| (NoteArgument::Target(None), notes)),
+		}
+	}
+
+	/// Every note that could come next while playing this argument, and whether that note would
+	/// finish it. Numbers need a digit before they can be terminated.
+	pub fn next_notes(self, digits_played: bool) -> Vec<(Note, bool)> {
+		match self {
+			NoteArgumentType::Bool => named_notes::<bool>(),
+			NoteArgumentType::UnsignedInt => {
+				let mut notes = DIGIT_NOTES
+					.iter()
+					.map(|note| (*note, false))
+					.collect::<Vec<_>>();
+				if digits_played {
+					notes.push((NUMBER_TERMINATOR, true));
+				}
+				notes
+			}
+			NoteArgumentType::Direction => named_notes::<RelativeDirection>(),
+			NoteArgumentType::Target => named_notes::<CrosshairTarget>(),
+		}
+	}
+}
+
+fn named_notes<T: NamedNotes>() -> Vec<(Note, bool)> {
+	T::NAMED_NOTES
+		.iter()
+		.map(|(note, _)| (*note, true))
+		.collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoteArgument {
+	Bool(bool),
+	UnsignedInt(u32),
+	Direction(RelativeDirection),
+	/// Filled in with the entity under the crosshair once the command is matched.
+	Target(Option<Entity>),
+}
+
+fn crosshair_target(
+	rapier_context: &RapierContext,
+	player_camera: &GlobalTransform,
+	player_body: Entity,
+	healths: &Query<Entity, With<GelViscosity>>,
+	parents: &Query<&Parent>,
+) -> Option<Entity> {
+	let (entity, _) = rapier_context.cast_ray(
+		player_camera.translation(),
+		player_camera.forward().into(),
+		CROSSHAIR_TARGET_DISTANCE,
+		true,
+		QueryFilter::default().exclude_rigid_body(player_body),
+	)?;
+	Some(find_in_ancestors(entity, healths, parents).unwrap_or(entity))
+}
+
+pub const CROSSHAIR_TARGET_DISTANCE: f32 = 50.0;
+
 #[system(
 	plugin = PlayerCommandsPlugin, schedule = Update,
 	after = CommandSentSet,
 )]
 fn ping(
-	mut ev_ping: EventReader<PingCommandEvent>,
+	mut ev_command: EventReader<NoteCommandInvoked>,
 	mut commands: Commands,
 	asset_server: Res<AssetServer>,
 ) {
-	for _ in ev_ping.read() {
+	for _ in ev_command.read().filter(|ev| ev.id == "ping") {
 		commands.spawn((
 			AudioPlayer::new(asset_server.load("pester_notif.mp3")),
 			PlaybackSettings::DESPAWN,
@@ -143,34 +324,193 @@ fn ping(
 	}
 }
 
-#[derive(Event)]
-#[event(plugin = PlayerCommandsPlugin)]
-pub struct KillCommandEvent(pub bool);
-
-impl KillCommandEvent {
-	const PATTERN: &'static [Note] = &[Note::D4, Note::D4, Note::D5];
+#[system(
+	plugin = PlayerCommandsPlugin, schedule = Update,
+	after = CommandSentSet,
+)]
+fn kill(mut ev_command: EventReader<NoteCommandInvoked>, mut ev_quit: EventWriter<AppExit>) {
+	for ev in ev_command.read().filter(|ev| ev.id == "kill") {
+		let Some(&NoteArgument::Bool(actually_kill)) = ev.args.first() else {
+			continue;
+		};
+		println!("Tried to kill {}", actually_kill);
+		if actually_kill {
+			ev_quit.send(AppExit::Success);
+		}
+	}
 }
 
-impl NotePatternEvent for KillCommandEvent {
-	fn compare_notes(notes: &[Note]) -> Option<Self>
-	where
-		Self: Sized,
-	{
-		let notes = notes.eat(KillCommandEvent::PATTERN)?;
-		let (actually_kill, _notes) = notes.eat_type()?;
-		Some(KillCommandEvent(actually_kill))
+pub const MAX_SUMMONED_IMPS: u32 = 20;
+
+#[system(
+	plugin = PlayerCommandsPlugin, schedule = Update,
+	after = CommandSentSet,
+)]
+fn summon(
+	mut ev_command: EventReader<NoteCommandInvoked>,
+	mut commands: Commands,
+	player: Query<&GlobalTransform, With<PlayerBody>>,
+) {
+	for ev in ev_command.read().filter(|ev| ev.id == "summon") {
+		let &[NoteArgument::UnsignedInt(amount), NoteArgument::Direction(direction)] =
+			ev.args.as_slice()
+		else {
+			continue;
+		};
+
+		let player = player.single();
+		let position = player.translation() + direction.relative_to(player) * 5.0;
+		for _ in 0..amount.min(MAX_SUMMONED_IMPS) {
+			commands.spawn((
+				Name::new("Imp"),
+				Transform::from_translation(position),
+				InsertImpAssets,
+			));
+		}
 	}
 }
 
+/// How far in front of the player conjured items appear.
+const CONJURE_DISTANCE: f32 = 2.0;
+
+/// Spawns whichever item is at this index in the item registry.
 #[system(
 	plugin = PlayerCommandsPlugin, schedule = Update,
 	after = CommandSentSet,
 )]
-fn kill(mut ev_kill: EventReader<KillCommandEvent>, mut ev_quit: EventWriter<AppExit>) {
-	for ev in ev_kill.read() {
-		println!("Tried to kill {}", ev.0);
-		if ev.0 {
-			ev_quit.send(AppExit::Success);
-		}
+fn conjure(
+	mut ev_command: EventReader<NoteCommandInvoked>,
+	mut item_spawner: ItemSpawner,
+	player: Query<&GlobalTransform, With<PlayerBody>>,
+) {
+	for ev in ev_command.read().filter(|ev| ev.id == "conjure") {
+		let &[NoteArgument::UnsignedInt(index)] = ev.args.as_slice() else {
+			continue;
+		};
+		let item_registry = item_spawner.registry();
+		let Some(id) = item_registry
+			.iter()
+			.nth(index as usize)
+			.map(|item| item.id.clone())
+		else {
+			warn!("There are only {} items", item_registry.iter().count());
+			continue;
+		};
+
+		let player = player.single();
+		let position = player.translation() + player.forward() * CONJURE_DISTANCE;
+		item_spawner.spawn(&id, Transform::from_translation(position));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn eat<T>(notes: &[Note]) -> Option<(T, &[Note])>
+	where
+		for<'a> &'a [Note]: NoteSequenceTyped<T>,
+	{
+		notes.eat_type()
+	}
+
+	#[test]
+	fn eat_bool() {
+		assert!(eat::<bool>(&[Note::A4]) == Some((true, &[][..])));
+		assert!(eat::<bool>(&[Note::C5]) == Some((false, &[][..])));
+	}
+
+	#[test]
+	fn eat_bool_malformed() {
+		assert!(eat::<bool>(&[Note::D4]).is_none());
+		assert!(eat::<bool>(&[]).is_none());
+	}
+
+	#[test]
+	fn eat_bool_leaves_extra_notes() {
+		assert!(
+			eat::<bool>(&[Note::A4, Note::C5, Note::D4]) == Some((true, &[Note::C5, Note::D4][..]))
+		);
+	}
+
+	#[test]
+	fn eat_unsigned_int() {
+		assert!(
+			eat::<u32>(&[Note::D4, Note::C4, Note::E5, NUMBER_TERMINATOR]) == Some((109, &[][..]))
+		);
+		assert!(eat::<u32>(&[Note::C4, NUMBER_TERMINATOR]) == Some((0, &[][..])));
+	}
+
+	#[test]
+	fn eat_unsigned_int_malformed() {
+		// No digits
+		assert!(eat::<u32>(&[NUMBER_TERMINATOR]).is_none());
+		// No terminator
+		assert!(eat::<u32>(&[Note::D4, Note::E4]).is_none());
+		// Not a digit
+		assert!(eat::<u32>(&[Note::D4, Note::CS4, NUMBER_TERMINATOR]).is_none());
+	}
+
+	#[test]
+	fn eat_unsigned_int_overflow() {
+		let mut notes = vec![Note::E5; 9];
+		notes.push(NUMBER_TERMINATOR);
+		assert!(eat::<u32>(&notes) == Some((999_999_999, &[][..])));
+
+		let mut notes = vec![Note::E5; 10];
+		notes.push(NUMBER_TERMINATOR);
+		assert!(eat::<u32>(&notes).is_none());
+	}
+
+	#[test]
+	fn eat_unsigned_int_leaves_extra_notes() {
+		assert!(
+			eat::<u32>(&[Note::E4, NUMBER_TERMINATOR, Note::E4, NUMBER_TERMINATOR])
+				== Some((2, &[Note::E4, NUMBER_TERMINATOR][..]))
+		);
+	}
+
+	#[test]
+	fn eat_direction() {
+		assert!(eat::<RelativeDirection>(&[Note::G4]) == Some((RelativeDirection::East, &[][..])));
+	}
+
+	#[test]
+	fn eat_direction_malformed() {
+		assert!(eat::<RelativeDirection>(&[Note::A4]).is_none());
+		assert!(eat::<RelativeDirection>(&[]).is_none());
+	}
+
+	#[test]
+	fn eat_direction_leaves_extra_notes() {
+		assert!(
+			eat::<RelativeDirection>(&[Note::C5, Note::C5])
+				== Some((RelativeDirection::North, &[Note::C5][..]))
+		);
+	}
+
+	#[test]
+	fn eat_target() {
+		assert!(
+			NoteArgumentType::Target.eat(&[Note::B4, Note::C4])
+				== Some((NoteArgument::Target(None), &[Note::C4][..]))
+		);
+	}
+
+	#[test]
+	fn eat_target_malformed() {
+		assert!(NoteArgumentType::Target.eat(&[Note::C4]).is_none());
+		assert!(NoteArgumentType::Target.eat(&[]).is_none());
+	}
+
+	#[test]
+	fn eat_composed() {
+		// Summon 12 imps to the west
+		let notes: &[Note] = &[Note::D4, Note::E4, NUMBER_TERMINATOR, Note::F4];
+		let (amount, notes) = eat::<u32>(notes).unwrap();
+		let (direction, notes) = eat::<RelativeDirection>(notes).unwrap();
+		assert_eq!(amount, 12);
+		assert_eq!(direction, RelativeDirection::West);
+		assert!(notes.is_empty());
 	}
 }
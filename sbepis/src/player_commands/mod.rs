@@ -1,18 +1,30 @@
 mod commands;
 mod note_holder;
 mod notes;
+mod radial;
+mod registry;
 mod staff;
+mod synth;
 
 use bevy_butler::*;
+use bevy_common_assets::ron::RonAssetPlugin;
 
 use crate::menus::InputManagerMenuPlugin;
 
 use self::commands::*;
 use self::notes::*;
+use self::registry::*;
 use self::staff::*;
+use self::synth::*;
+
+pub use self::notes::{NotePlayed, NotePlayedSet, PlayNoteAction};
+pub use self::radial::RadialNoteAction;
+pub use self::staff::CloseStaffAction;
 
 #[butler_plugin(build(
 	add_plugins(InputManagerMenuPlugin::<CloseStaffAction>::default()),
 	add_plugins(InputManagerMenuPlugin::<PlayNoteAction>::default()),
+	add_plugins(InputManagerMenuPlugin::<RadialNoteAction>::default()),
+	add_plugins(RonAssetPlugin::<NoteCommandDefinitions>::new(&["commands.ron"])),
 ))]
 pub struct PlayerCommandsPlugin;
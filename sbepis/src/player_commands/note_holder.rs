@@ -2,14 +2,17 @@ use bevy::prelude::*;
 use bevy_butler::*;
 use soundyrust::Note;
 
-use crate::player_commands::notes::NotePlayed;
-use crate::player_commands::{staff::*, NotesCleared, NotesClearedSet};
+use crate::player_commands::notes::{NotePlayed, NoteReleased, RestPlayed};
+use crate::player_commands::{staff::*, NoteDuration, NotesCleared, NotesClearedSet};
 use crate::player_commands::{NotePlayedSet, PlayerCommandsPlugin};
+use crate::some_or_continue;
 use crate::util::MapRange;
 
 #[derive(Component, Default)]
 pub struct NoteNodeHolder {
 	note_entities: Vec<Entity>,
+	/// Notes that are still being held, so their glyph isn't known yet.
+	held_notes: Vec<(Note, Entity)>,
 }
 
 impl NoteNodeHolder {
@@ -33,12 +36,31 @@ impl NoteNodeHolder {
 )]
 fn add_note_to_holder(
 	mut commands: Commands,
+	mut ev_rest_played: EventReader<RestPlayed>,
 	mut ev_note_played: EventReader<NotePlayed>,
 	mut note_holder: Query<(&mut NoteNodeHolder, Entity)>,
 	asset_server: Res<AssetServer>,
 ) {
 	let (mut note_holder, note_holder_entity) = note_holder.single_mut();
 
+	for ev in ev_rest_played.read() {
+		let rest_entity = commands
+			.spawn((
+				ImageNode::new(asset_server.load(ev.duration.rest_glyph())),
+				Node {
+					position_type: PositionType::Absolute,
+					left: Val::Px(note_holder.next_note_left()),
+					top: Val::Px(note_holder.note_top(&REST_NOTE)),
+					height: Val::Px(QUARTER_NOTE_HEIGHT),
+					..default()
+				},
+			))
+			.set_parent(note_holder_entity)
+			.id();
+
+		note_holder.note_entities.push(rest_entity);
+	}
+
 	for ev in ev_note_played.read() {
 		let note = ev.note;
 
@@ -49,9 +71,10 @@ fn add_note_to_holder(
 			note_holder.note_top(&note)
 		);
 
+		// We don't know how long it'll be held yet
 		let note_entity = commands
 			.spawn((
-				ImageNode::new(asset_server.load("quarter_note.png")),
+				ImageNode::new(asset_server.load(NoteDuration::Quarter.note_glyph())),
 				Node {
 					position_type: PositionType::Absolute,
 					left: Val::Px(note_holder.next_note_left()),
@@ -64,6 +87,38 @@ fn add_note_to_holder(
 			.id();
 
 		note_holder.note_entities.push(note_entity);
+		note_holder.held_notes.push((note, note_entity));
+	}
+}
+
+#[system(
+	plugin = PlayerCommandsPlugin, schedule = Update,
+	after = add_note_to_holder,
+	before = NotesClearedSet,
+)]
+fn update_released_note_glyphs(
+	mut ev_note_released: EventReader<NoteReleased>,
+	mut note_holder: Query<&mut NoteNodeHolder>,
+	mut images: Query<&mut ImageNode>,
+	asset_server: Res<AssetServer>,
+) {
+	let mut note_holder = note_holder.single_mut();
+
+	for ev in ev_note_released.read() {
+		let Some(index) = note_holder
+			.held_notes
+			.iter()
+			.position(|(note, _)| *note == ev.note)
+		else {
+			continue;
+		};
+		let (_, note_entity) = note_holder.held_notes.remove(index);
+
+		// Notes from before the music started stay quarter notes
+		let duration = some_or_continue!(ev.duration);
+		if let Ok(mut image) = images.get_mut(note_entity) {
+			image.image = asset_server.load(duration.note_glyph());
+		}
 	}
 }
 
@@ -78,4 +133,5 @@ fn clear_holder_notes(mut commands: Commands, mut note_holder: Query<&mut NoteNo
 		commands.entity(*note_entity).despawn_recursive();
 	}
 	note_holder.note_entities.clear();
+	note_holder.held_notes.clear();
 }
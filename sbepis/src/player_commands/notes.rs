@@ -1,25 +1,177 @@
+use std::time::Duration;
+
 use bevy::prelude::*;
 use bevy_butler::*;
 use leafwing_input_manager::prelude::*;
+use serde::{Deserialize, Serialize};
 use soundyrust::Note;
 
-use crate::input::{button_just_pressed, MapsToEvent};
+use crate::fray::FrayMusic;
+use crate::input::button_just_pressed;
+use crate::player_commands::radial::RadialNoteSelector;
 use crate::player_commands::{CloseStaffAction, CommandSent, CommandSentSet, PlayerCommandsPlugin};
 
 #[derive(Event)]
 #[event(plugin = PlayerCommandsPlugin)]
 pub struct NotePlayed {
 	pub note: Note,
+	/// Position in the bar of the fray music when the note was pressed.
+	pub beat: f64,
 }
 #[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
 pub struct NotePlayedSet;
 
+#[derive(Event)]
+#[event(plugin = PlayerCommandsPlugin)]
+pub struct NoteReleased {
+	pub note: Note,
+	pub beat: f64,
+	/// `None` if the fray music hasn't started keeping time yet.
+	pub duration: Option<NoteDuration>,
+}
+
+/// Sent right before the [`NotePlayed`] that ended the rest.
+#[derive(Event)]
+#[event(plugin = PlayerCommandsPlugin)]
+pub struct RestPlayed {
+	pub beat: f64,
+	pub duration: NoteDuration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+pub enum NoteDuration {
+	Eighth,
+	Quarter,
+	Half,
+	Whole,
+}
+
+impl NoteDuration {
+	pub fn beats(&self) -> f64 {
+		match self {
+			NoteDuration::Eighth => 0.5,
+			NoteDuration::Quarter => 1.0,
+			NoteDuration::Half => 2.0,
+			NoteDuration::Whole => 4.0,
+		}
+	}
+
+	/// Rounds to the closest duration in log space, so anything shorter than
+	/// an eighth note is still an eighth note.
+	pub fn quantize(beats: f64) -> Self {
+		match beats.max(f64::MIN_POSITIVE).log2().round() as i32 {
+			..=-1 => NoteDuration::Eighth,
+			0 => NoteDuration::Quarter,
+			1 => NoteDuration::Half,
+			_ => NoteDuration::Whole,
+		}
+	}
+
+	/// Like [`NoteDuration::quantize`], but gaps too short to round to an
+	/// eighth rest aren't rests at all.
+	pub fn quantize_rest(beats: f64) -> Option<Self> {
+		if beats.log2().round() < -1.0 {
+			None
+		} else {
+			Some(Self::quantize(beats))
+		}
+	}
+
+	pub fn note_glyph(&self) -> &'static str {
+		match self {
+			NoteDuration::Eighth => "eighth_note.png",
+			NoteDuration::Quarter => "quarter_note.png",
+			NoteDuration::Half => "half_note.png",
+			NoteDuration::Whole => "whole_note.png",
+		}
+	}
+
+	pub fn rest_glyph(&self) -> &'static str {
+		match self {
+			NoteDuration::Eighth => "eighth_rest.png",
+			NoteDuration::Quarter => "quarter_rest.png",
+			NoteDuration::Half => "half_rest.png",
+			NoteDuration::Whole => "whole_rest.png",
+		}
+	}
+}
+
+#[derive(Resource, Default)]
+#[resource(plugin = PlayerCommandsPlugin)]
+pub struct StaffTiming {
+	held_notes: usize,
+	last_release: Option<Duration>,
+}
+
 #[system(
 	plugin = PlayerCommandsPlugin, schedule = Update,
-	generics = <PlayNoteAction, NotePlayed>,
 	in_set = NotePlayedSet,
 )]
-use crate::input::map_action_to_event;
+fn play_notes(
+	input: Query<&ActionState<PlayNoteAction>>,
+	radial_selectors: Query<&RadialNoteSelector>,
+	fray_music: Query<&FrayMusic>,
+	time: Res<Time>,
+	mut timing: ResMut<StaffTiming>,
+	mut ev_note_played: EventWriter<NotePlayed>,
+	mut ev_note_released: EventWriter<NoteReleased>,
+	mut ev_rest_played: EventWriter<RestPlayed>,
+) {
+	// Notes can still be played before the music starts, they just can't be kept in time
+	let fray_music = fray_music.get_single().ok();
+	let beat = fray_music.map_or(0.0, FrayMusic::beat);
+	// Without a tempo every note would look like an eighth note
+	let tempo = fray_music.filter(|fray_music| fray_music.is_keeping_time());
+
+	let just_released = input
+		.iter()
+		.flat_map(|input| {
+			input
+				.get_just_released()
+				.into_iter()
+				.map(|action| (action, input.previous_duration(&action)))
+		})
+		.chain(
+			radial_selectors
+				.iter()
+				.filter_map(|selector| selector.just_released),
+		);
+	let just_pressed = input
+		.iter()
+		.flat_map(|input| input.get_just_pressed())
+		.chain(
+			radial_selectors
+				.iter()
+				.filter_map(|selector| selector.just_pressed),
+		)
+		.collect::<Vec<_>>();
+
+	for (action, held_for) in just_released {
+		timing.held_notes = timing.held_notes.saturating_sub(1);
+		timing.last_release = Some(time.elapsed());
+		ev_note_released.send(NoteReleased {
+			note: action.note(),
+			beat,
+			duration: tempo.map(|tempo| NoteDuration::quantize(tempo.time_to_bpm_beat(held_for))),
+		});
+	}
+
+	for action in just_pressed {
+		if let (0, Some(tempo)) = (timing.held_notes, tempo) {
+			if let Some(last_release) = timing.last_release.take() {
+				let rest = tempo.time_to_bpm_beat(time.elapsed() - last_release);
+				if let Some(duration) = NoteDuration::quantize_rest(rest) {
+					ev_rest_played.send(RestPlayed { beat, duration });
+				}
+			}
+		}
+		timing.held_notes += 1;
+		ev_note_played.send(NotePlayed {
+			note: action.note(),
+			beat,
+		});
+	}
+}
 
 #[derive(Event)]
 #[event(plugin = PlayerCommandsPlugin)]
@@ -27,7 +179,7 @@ pub struct NotesCleared;
 #[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
 pub struct NotesClearedSet;
 
-#[derive(Actionlike, Clone, Copy, Eq, PartialEq, Hash, Reflect, Debug)]
+#[derive(Actionlike, Clone, Copy, Eq, PartialEq, Hash, Reflect, Debug, Serialize, Deserialize)]
 pub enum PlayNoteAction {
 	C0,
 	CS0,
@@ -140,6 +292,30 @@ pub enum PlayNoteAction {
 }
 
 impl PlayNoteAction {
+	/// Every note in order, twelve semitones to an octave starting at C0.
+	#[rustfmt::skip]
+	pub const ALL: [PlayNoteAction; 108] = {
+		use PlayNoteAction::*;
+		[
+			C0, CS0, D0, DS0, E0, F0, FS0, G0, GS0, A0, AS0, B0,
+			C1, CS1, D1, DS1, E1, F1, FS1, G1, GS1, A1, AS1, B1,
+			C2, CS2, D2, DS2, E2, F2, FS2, G2, GS2, A2, AS2, B2,
+			C3, CS3, D3, DS3, E3, F3, FS3, G3, GS3, A3, AS3, B3,
+			C4, CS4, D4, DS4, E4, F4, FS4, G4, GS4, A4, AS4, B4,
+			C5, CS5, D5, DS5, E5, F5, FS5, G5, GS5, A5, AS5, B5,
+			C6, CS6, D6, DS6, E6, F6, FS6, G6, GS6, A6, AS6, B6,
+			C7, CS7, D7, DS7, E7, F7, FS7, G7, GS7, A7, AS7, B7,
+			C8, CS8, D8, DS8, E8, F8, FS8, G8, GS8, A8, AS8, B8,
+		]
+	};
+
+	pub fn from_octave_and_semitone(octave: usize, semitone: usize) -> Option<Self> {
+		if semitone >= 12 {
+			return None;
+		}
+		Self::ALL.get(octave * 12 + semitone).copied()
+	}
+
 	pub fn note(&self) -> Note {
 		match self {
 			PlayNoteAction::C0 => Note::C0,
@@ -253,31 +429,6 @@ impl PlayNoteAction {
 		}
 	}
 }
-impl MapsToEvent<NotePlayed> for PlayNoteAction {
-	fn make_event(&self) -> NotePlayed {
-		NotePlayed { note: self.note() }
-	}
-}
-
-#[system(
-	plugin = PlayerCommandsPlugin, schedule = Update,
-	after = NotePlayedSet,
-)]
-fn spawn_note_audio(
-	mut commands: Commands,
-	mut ev_note_played: EventReader<NotePlayed>,
-	asset_server: Res<AssetServer>,
-) {
-	for ev in ev_note_played.read() {
-		let note = ev.note;
-
-		commands.spawn((
-			AudioPlayer::new(asset_server.load("flute.wav")),
-			PlaybackSettings::DESPAWN.with_speed(note.frequency / Note::C4.frequency),
-		));
-	}
-}
-
 #[system(
 	plugin = PlayerCommandsPlugin, schedule = Update,
 	after = CommandSentSet,
@@ -292,3 +443,12 @@ fn spawn_note_audio(
 fn clear_notes(mut ev_clear_notes: EventWriter<NotesCleared>) {
 	ev_clear_notes.send(NotesCleared);
 }
+
+#[system(
+	plugin = PlayerCommandsPlugin, schedule = Update,
+	after = NotesClearedSet,
+	run_if = on_event::<NotesCleared>,
+)]
+fn clear_staff_timing(mut timing: ResMut<StaffTiming>) {
+	timing.last_release = None;
+}
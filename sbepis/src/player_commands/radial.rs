@@ -0,0 +1,264 @@
+use std::f32::consts::TAU;
+use std::time::Duration;
+
+use bevy::color::palettes::css;
+use bevy::prelude::*;
+use bevy_butler::*;
+use leafwing_input_manager::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::player_commands::notes::{NotePlayedSet, PlayNoteAction};
+use crate::player_commands::PlayerCommandsPlugin;
+
+const SEMITONE_NAMES: [&str; 12] = [
+	"C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+];
+const MAX_OCTAVE: usize = 8;
+/// How far the stick has to be pushed before a note is selected.
+const AIM_DEADZONE: f32 = 0.5;
+const RADIAL_RADIUS: f32 = 80.0;
+const RADIAL_SEGMENT_SIZE: f32 = 36.0;
+
+/// Gamepad controls for picking a note on the staff by pointing a stick at it.
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Reflect, Debug, Serialize, Deserialize)]
+pub enum RadialNoteAction {
+	Aim,
+	Play,
+	OctaveUp,
+	OctaveDown,
+}
+impl Actionlike for RadialNoteAction {
+	fn input_control_kind(&self) -> InputControlKind {
+		match self {
+			RadialNoteAction::Aim => InputControlKind::DualAxis,
+			RadialNoteAction::Play => InputControlKind::Button,
+			RadialNoteAction::OctaveUp => InputControlKind::Button,
+			RadialNoteAction::OctaveDown => InputControlKind::Button,
+		}
+	}
+}
+
+#[derive(Component)]
+pub struct RadialNoteSelector {
+	pub octave: usize,
+	/// Semitone the stick is pointing at, with C at the top going clockwise.
+	pub selected: Option<usize>,
+	held: Option<PlayNoteAction>,
+	held_for: Duration,
+	/// Read by the staff the same way as a [`PlayNoteAction`] being pressed.
+	pub just_pressed: Option<PlayNoteAction>,
+	pub just_released: Option<(PlayNoteAction, Duration)>,
+}
+
+impl Default for RadialNoteSelector {
+	fn default() -> Self {
+		Self {
+			octave: 4,
+			selected: None,
+			held: None,
+			held_for: Duration::ZERO,
+			just_pressed: None,
+			just_released: None,
+		}
+	}
+}
+
+#[derive(Component)]
+pub struct RadialNoteSelectorRoot;
+
+#[derive(Component)]
+pub struct RadialNoteSegment(pub usize);
+
+#[derive(Component)]
+pub struct RadialNoteOctaveText;
+
+pub fn spawn_radial_note_selector(parent: &mut ChildBuilder) {
+	parent
+		.spawn((
+			Name::new("Radial Note Selector"),
+			Node {
+				position_type: PositionType::Absolute,
+				top: Val::Px(120.0),
+				left: Val::Percent(50.0),
+				width: Val::Px(RADIAL_RADIUS * 2.0 + RADIAL_SEGMENT_SIZE),
+				height: Val::Px(RADIAL_RADIUS * 2.0 + RADIAL_SEGMENT_SIZE),
+				margin: UiRect::left(Val::Px(-RADIAL_RADIUS - RADIAL_SEGMENT_SIZE / 2.0)),
+				justify_content: JustifyContent::Center,
+				align_items: AlignItems::Center,
+				..default()
+			},
+			RadialNoteSelectorRoot,
+		))
+		.with_children(|parent| {
+			for (semitone, name) in SEMITONE_NAMES.iter().enumerate() {
+				let angle = semitone as f32 / 12.0 * TAU;
+				let center = Vec2::new(angle.sin(), -angle.cos()) * RADIAL_RADIUS + RADIAL_RADIUS;
+
+				parent
+					.spawn((
+						Node {
+							position_type: PositionType::Absolute,
+							left: Val::Px(center.x),
+							top: Val::Px(center.y),
+							width: Val::Px(RADIAL_SEGMENT_SIZE),
+							height: Val::Px(RADIAL_SEGMENT_SIZE),
+							justify_content: JustifyContent::Center,
+							align_items: AlignItems::Center,
+							..default()
+						},
+						BorderRadius::MAX,
+						BackgroundColor(css::DARK_GRAY.with_alpha(0.8).into()),
+						RadialNoteSegment(semitone),
+					))
+					.with_children(|parent| {
+						parent.spawn((
+							Text(name.to_string()),
+							TextColor(Color::WHITE),
+							TextFont {
+								font_size: 16.0,
+								..default()
+							},
+						));
+					});
+			}
+
+			parent.spawn((
+				Text::default(),
+				TextColor(Color::WHITE),
+				TextFont {
+					font_size: 20.0,
+					..default()
+				},
+				RadialNoteOctaveText,
+			));
+		});
+}
+
+/// The semitone a stick is pointing at, or `None` if it's not pushed far enough.
+fn aimed_semitone(aim: Vec2) -> Option<usize> {
+	if aim.length() < AIM_DEADZONE {
+		return None;
+	}
+	// Clockwise from straight up
+	let angle = aim.x.atan2(aim.y).rem_euclid(TAU);
+	Some((angle / TAU * 12.0).round() as usize % 12)
+}
+
+#[system(
+	plugin = PlayerCommandsPlugin, schedule = Update,
+	before = NotePlayedSet,
+)]
+fn select_radial_note(
+	mut selectors: Query<(&ActionState<RadialNoteAction>, &mut RadialNoteSelector)>,
+	time: Res<Time>,
+) {
+	for (input, mut selector) in selectors.iter_mut() {
+		selector.just_pressed = None;
+		selector.just_released = None;
+
+		if input.just_pressed(&RadialNoteAction::OctaveUp) {
+			selector.octave = (selector.octave + 1).min(MAX_OCTAVE);
+		}
+		if input.just_pressed(&RadialNoteAction::OctaveDown) {
+			selector.octave = selector.octave.saturating_sub(1);
+		}
+
+		selector.selected = aimed_semitone(input.axis_pair(&RadialNoteAction::Aim));
+
+		if let Some(held) = selector.held {
+			selector.held_for += time.delta();
+			if !input.pressed(&RadialNoteAction::Play) {
+				selector.just_released = Some((held, selector.held_for));
+				selector.held = None;
+			}
+		}
+
+		if input.just_pressed(&RadialNoteAction::Play) && selector.held.is_none() {
+			let note = selector.selected.and_then(|semitone| {
+				PlayNoteAction::from_octave_and_semitone(selector.octave, semitone)
+			});
+			if let Some(note) = note {
+				selector.just_pressed = Some(note);
+				selector.held = Some(note);
+				selector.held_for = Duration::ZERO;
+			}
+		}
+	}
+}
+
+#[system(
+	plugin = PlayerCommandsPlugin, schedule = Update,
+	after = NotePlayedSet,
+)]
+fn update_radial_note_selector(
+	selectors: Query<&RadialNoteSelector>,
+	gamepads: Query<(), With<Gamepad>>,
+	mut root: Query<&mut Node, With<RadialNoteSelectorRoot>>,
+	mut segments: Query<(&RadialNoteSegment, &mut BackgroundColor)>,
+	mut octave_text: Query<&mut Text, With<RadialNoteOctaveText>>,
+) {
+	let Ok(selector) = selectors.get_single() else {
+		return;
+	};
+
+	// Only useful with a controller plugged in
+	let display = if gamepads.is_empty() {
+		Display::None
+	} else {
+		Display::Flex
+	};
+	for mut root in root.iter_mut() {
+		if root.display != display {
+			root.display = display;
+		}
+	}
+
+	for (segment, mut background) in segments.iter_mut() {
+		background.0 = if selector.selected == Some(segment.0) {
+			css::GOLDENROD.into()
+		} else {
+			css::DARK_GRAY.with_alpha(0.8).into()
+		};
+	}
+
+	for mut text in octave_text.iter_mut() {
+		text.0 = selector.octave.to_string();
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn pointing(semitone: f32) -> Vec2 {
+		let angle = semitone / 12.0 * TAU;
+		Vec2::new(angle.sin(), angle.cos())
+	}
+
+	#[test]
+	fn sticks_point_at_semitones_clockwise_from_c() {
+		assert_eq!(aimed_semitone(Vec2::Y), Some(0));
+		assert_eq!(aimed_semitone(Vec2::X), Some(3));
+		assert_eq!(aimed_semitone(Vec2::NEG_Y), Some(6));
+		assert_eq!(aimed_semitone(Vec2::NEG_X), Some(9));
+		for semitone in 0..12 {
+			assert_eq!(aimed_semitone(pointing(semitone as f32)), Some(semitone));
+			// Anywhere in the segment counts, not just its middle
+			assert_eq!(
+				aimed_semitone(pointing(semitone as f32 + 0.4)),
+				Some(semitone)
+			);
+			assert_eq!(
+				aimed_semitone(pointing(semitone as f32 - 0.4)),
+				Some(semitone)
+			);
+		}
+	}
+
+	#[test]
+	fn sticks_in_the_deadzone_point_at_nothing() {
+		assert_eq!(aimed_semitone(Vec2::ZERO), None);
+		assert_eq!(aimed_semitone(Vec2::X * (AIM_DEADZONE * 0.9)), None);
+		assert_eq!(aimed_semitone(Vec2::X * (AIM_DEADZONE * 1.1)), Some(3));
+	}
+}
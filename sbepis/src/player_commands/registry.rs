@@ -0,0 +1,366 @@
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+use bevy_butler::*;
+use serde::Deserialize;
+use soundyrust::Note;
+
+use crate::player_commands::commands::{NoteArgument, NoteArgumentType, NoteSequence, PlayedNote};
+use crate::player_commands::notes::{NoteDuration, PlayNoteAction};
+use crate::player_commands::PlayerCommandsPlugin;
+
+#[derive(Asset, Deserialize, TypePath)]
+pub struct NoteCommandDefinitions {
+	commands: Vec<NoteCommand>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NoteCommand {
+	pub id: String,
+	pub pattern: Vec<NotePatternElement>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum NotePatternElement {
+	/// A note, held for this long if there's a duration, like `Note(C4, Some(Half))`.
+	Note(PlayNoteAction, #[serde(default)] Option<NoteDuration>),
+	Arg(NoteArgumentType),
+}
+
+impl NotePatternElement {
+	/// Every note that could come next while playing this element, how long it has to be held,
+	/// and whether that note would finish it.
+	fn next_notes(&self, digits_played: bool) -> Vec<(Note, Option<NoteDuration>, bool)> {
+		match self {
+			NotePatternElement::Note(note, duration) => vec![(note.note(), *duration, true)],
+			NotePatternElement::Arg(arg_type) => arg_type
+				.next_notes(digits_played)
+				.into_iter()
+				.map(|(note, finishes)| (note, None, finishes))
+				.collect(),
+		}
+	}
+}
+
+/// How far through its pattern a command has been played.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+struct PatternPosition {
+	element: usize,
+	/// Whether the number argument being played has had any digits yet.
+	digits_played: bool,
+}
+
+impl NoteCommand {
+	pub fn compare_notes(&self, played: &[PlayedNote]) -> Option<NoteCommandInvoked> {
+		let all_notes = played.iter().map(|played| played.note).collect::<Vec<_>>();
+		let mut notes = all_notes.as_slice();
+		let mut args = Vec::new();
+		for element in self.pattern.iter() {
+			match element {
+				NotePatternElement::Note(note, duration) => {
+					// Still being held counts as the wrong length until it's let go
+					let played_duration = played.get(all_notes.len() - notes.len())?.duration;
+					if duration.is_some_and(|duration| played_duration != Some(duration)) {
+						return None;
+					}
+					notes = notes.eat(&[note.note()])?;
+				}
+				NotePatternElement::Arg(arg_type) => {
+					let (arg, rest) = arg_type.eat(notes)?;
+					args.push(arg);
+					notes = rest;
+				}
+			}
+		}
+		Some(NoteCommandInvoked {
+			id: self.id.clone(),
+			args,
+		})
+	}
+
+	/// Two commands are ambiguous if one of them could finish matching
+	/// while the player is still partway through playing the other.
+	pub fn is_ambiguous_with(&self, other: &NoteCommand) -> bool {
+		// Plays both patterns side by side with every run of notes they could both be partway through
+		let start = (PatternPosition::default(), PatternPosition::default());
+		let mut visited = HashSet::from([start]);
+		let mut unvisited = vec![start];
+		while let Some((position, other_position)) = unvisited.pop() {
+			if self.is_finished(position) || other.is_finished(other_position) {
+				return true;
+			}
+
+			for (note, duration, next) in self.next_positions(position) {
+				for (other_note, other_duration, other_next) in other.next_positions(other_position)
+				{
+					let durations_overlap = duration.is_none()
+						|| other_duration.is_none()
+						|| duration == other_duration;
+					if note == other_note && durations_overlap && visited.insert((next, other_next))
+					{
+						unvisited.push((next, other_next));
+					}
+				}
+			}
+		}
+		false
+	}
+
+	fn is_finished(&self, position: PatternPosition) -> bool {
+		position.element >= self.pattern.len()
+	}
+
+	/// Every note that could be played next, how long it has to be held, and where in the pattern
+	/// each one would lead.
+	fn next_positions(
+		&self,
+		position: PatternPosition,
+	) -> Vec<(Note, Option<NoteDuration>, PatternPosition)> {
+		let Some(element) = self.pattern.get(position.element) else {
+			return Vec::new();
+		};
+		element
+			.next_notes(position.digits_played)
+			.into_iter()
+			.map(|(note, duration, finishes_element)| {
+				let next = if finishes_element {
+					PatternPosition {
+						element: position.element + 1,
+						digits_played: false,
+					}
+				} else {
+					PatternPosition {
+						element: position.element,
+						digits_played: true,
+					}
+				};
+				(note, duration, next)
+			})
+			.collect()
+	}
+}
+
+#[derive(Resource, Default)]
+#[resource(plugin = PlayerCommandsPlugin)]
+pub struct NoteCommandRegistry {
+	commands: Vec<NoteCommand>,
+}
+
+impl NoteCommandRegistry {
+	pub fn new(commands: Vec<NoteCommand>) -> Self {
+		Self { commands }
+	}
+
+	/// Commands earlier in the registry win if more than one matches.
+	pub fn compare_notes(&self, played: &[PlayedNote]) -> Option<NoteCommandInvoked> {
+		self.commands
+			.iter()
+			.find_map(|command| command.compare_notes(played))
+	}
+
+	pub fn ambiguities(&self) -> Vec<(&NoteCommand, &NoteCommand)> {
+		self.commands
+			.iter()
+			.enumerate()
+			.flat_map(|(i, a)| {
+				self.commands[i + 1..]
+					.iter()
+					.filter(move |b| a.is_ambiguous_with(b))
+					.map(move |b| (a, b))
+			})
+			.collect()
+	}
+}
+
+#[derive(Event, Debug, Clone)]
+#[event(plugin = PlayerCommandsPlugin)]
+pub struct NoteCommandInvoked {
+	pub id: String,
+	pub args: Vec<NoteArgument>,
+}
+
+#[derive(Resource)]
+pub struct NoteCommandAssets {
+	pub definitions: Handle<NoteCommandDefinitions>,
+}
+
+#[system(
+	plugin = PlayerCommandsPlugin, schedule = Startup,
+)]
+fn load_note_commands(mut commands: Commands, asset_server: Res<AssetServer>) {
+	commands.insert_resource(NoteCommandAssets {
+		definitions: asset_server.load("staff.commands.ron"),
+	});
+}
+
+#[system(
+	plugin = PlayerCommandsPlugin, schedule = Update,
+	run_if = on_event::<AssetEvent<NoteCommandDefinitions>>,
+)]
+fn update_note_command_registry(
+	mut ev_asset: EventReader<AssetEvent<NoteCommandDefinitions>>,
+	assets: Res<Assets<NoteCommandDefinitions>>,
+	note_command_assets: Res<NoteCommandAssets>,
+	mut registry: ResMut<NoteCommandRegistry>,
+) {
+	for ev in ev_asset.read() {
+		if !ev.is_loaded_with_dependencies(&note_command_assets.definitions)
+			&& !ev.is_modified(&note_command_assets.definitions)
+		{
+			continue;
+		}
+
+		let definitions = assets
+			.get(&note_command_assets.definitions)
+			.expect("Note commands should be loaded by now");
+		*registry = NoteCommandRegistry::new(definitions.commands.clone());
+
+		for (a, b) in registry.ambiguities() {
+			warn!(
+				"Note commands \"{}\" and \"{}\" have ambiguous patterns, so one can cut the other off",
+				a.id, b.id
+			);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::player_commands::commands::{RelativeDirection, NUMBER_TERMINATOR};
+	use crate::player_commands::notes::NoteDuration::*;
+
+	use NotePatternElement::{Arg, Note as N};
+	use PlayNoteAction::*;
+
+	fn played(notes: &[(Note, Option<NoteDuration>)]) -> Vec<PlayedNote> {
+		notes
+			.iter()
+			.map(|&(note, duration)| PlayedNote { note, duration })
+			.collect()
+	}
+
+	fn command(id: &str, pattern: &[NotePatternElement]) -> NoteCommand {
+		NoteCommand {
+			id: id.to_owned(),
+			pattern: pattern.to_vec(),
+		}
+	}
+
+	#[test]
+	fn prefixes_are_ambiguous() {
+		let ping = command("ping", &[N(C4, None), N(D4, None), N(E4, None)]);
+		let longer = command(
+			"longer",
+			&[
+				N(C4, None),
+				N(D4, None),
+				N(E4, None),
+				Arg(NoteArgumentType::Bool),
+			],
+		);
+		assert!(ping.is_ambiguous_with(&longer));
+		assert!(longer.is_ambiguous_with(&ping));
+	}
+
+	#[test]
+	fn numbers_can_swallow_notes() {
+		// C4 D4 F5 is a whole number, which finishes the first command partway through the second
+		let number = command("number", &[N(C4, None), Arg(NoteArgumentType::UnsignedInt)]);
+		let tune = command(
+			"tune",
+			&[N(C4, None), N(D4, None), N(F5, None), N(G4, None)],
+		);
+		assert!(number.is_ambiguous_with(&tune));
+	}
+
+	#[test]
+	fn different_notes_are_not_ambiguous() {
+		let summon = command(
+			"summon",
+			&[
+				N(G4, None),
+				N(C4, None),
+				N(G4, None),
+				Arg(NoteArgumentType::UnsignedInt),
+				Arg(NoteArgumentType::Direction),
+			],
+		);
+		let conjure = command(
+			"conjure",
+			&[
+				N(C4, None),
+				N(E4, None),
+				N(G4, None),
+				Arg(NoteArgumentType::UnsignedInt),
+			],
+		);
+		assert!(!summon.is_ambiguous_with(&conjure));
+	}
+
+	#[test]
+	fn arguments_only_overlap_on_their_own_notes() {
+		// Bools are A4 or C5, so they can never be confused with G4
+		let flag = command("flag", &[N(E4, None), Arg(NoteArgumentType::Bool)]);
+		let tune = command("tune", &[N(E4, None), N(G4, None)]);
+		assert!(!flag.is_ambiguous_with(&tune));
+	}
+
+	#[test]
+	fn durations_only_overlap_when_they_match() {
+		let long = command("long", &[N(C4, Some(Half))]);
+		let short = command("short", &[N(C4, Some(Quarter)), N(D4, None)]);
+		let any = command("any", &[N(C4, None), N(D4, None)]);
+		assert!(!long.is_ambiguous_with(&short));
+		assert!(long.is_ambiguous_with(&any));
+	}
+
+	#[test]
+	fn notes_match_on_rhythm() {
+		let rhythm = command("rhythm", &[N(C4, Some(Half)), N(D4, None)]);
+		assert!(rhythm
+			.compare_notes(&played(&[(Note::C4, Some(Half)), (Note::D4, None)]))
+			.is_some());
+		assert!(rhythm
+			.compare_notes(&played(&[(Note::C4, Some(Quarter)), (Note::D4, None)]))
+			.is_none());
+
+		let timed = command("timed", &[N(C4, Some(Half)), N(D4, Some(Quarter))]);
+		assert!(timed
+			.compare_notes(&played(&[
+				(Note::C4, Some(Half)),
+				(Note::D4, Some(Quarter))
+			]))
+			.is_some());
+		// Still held, so it could turn out to be anything
+		assert!(timed
+			.compare_notes(&played(&[(Note::C4, Some(Half)), (Note::D4, None)]))
+			.is_none());
+	}
+
+	#[test]
+	fn registry_loads_without_ambiguities() {
+		let definitions: NoteCommandDefinitions =
+			ron::from_str(include_str!("../../assets/staff.commands.ron")).unwrap();
+		let registry = NoteCommandRegistry::new(definitions.commands);
+		assert!(registry.ambiguities().is_empty());
+
+		let invoked = registry
+			.compare_notes(&played(&[
+				(Note::G4, Some(Quarter)),
+				(Note::C4, Some(Quarter)),
+				(Note::G4, Some(Quarter)),
+				(Note::D4, Some(Eighth)),
+				(NUMBER_TERMINATOR, Some(Eighth)),
+				(Note::F4, None),
+			]))
+			.unwrap();
+		assert_eq!(invoked.id, "summon");
+		assert_eq!(
+			invoked.args,
+			vec![
+				NoteArgument::UnsignedInt(1),
+				NoteArgument::Direction(RelativeDirection::West),
+			]
+		);
+	}
+}
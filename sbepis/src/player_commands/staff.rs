@@ -1,17 +1,18 @@
 use bevy::color::palettes::css;
 use bevy::prelude::*;
 use bevy_butler::*;
-use leafwing_input_manager::prelude::InputMap;
 use leafwing_input_manager::Actionlike;
+use serde::{Deserialize, Serialize};
+use soundyrust::Note;
 
 use crate::camera::PlayerCameraNode;
-use crate::input::input_manager_bundle;
+use crate::keybinds::{keybound_input_manager, Keybinds};
 use crate::menus::{
 	CloseMenuBinding, Menu, MenuHidesWhenClosed, MenuManipulationSet, MenuWithInputManager,
 	MenuWithoutMouse, OpenMenuBinding,
 };
 use crate::player_commands::note_holder::NoteNodeHolder;
-use crate::player_commands::notes::PlayNoteAction;
+use crate::player_commands::radial::{spawn_radial_note_selector, RadialNoteSelector};
 use crate::player_commands::PlayerCommandsPlugin;
 use crate::player_controller::PlayerAction;
 
@@ -29,13 +30,16 @@ pub const QUARTER_NOTE_HEIGHT: f32 = 55.0;
 pub const QUARTER_NOTE_LEFT_START: f32 = 40.0;
 pub const QUARTER_NOTE_LEFT_SPACING: f32 = 20.0;
 
+/// Rests are drawn centered on the middle line
+pub const REST_NOTE: Note = Note::B4;
+
 // Does top + height not actually equal bottom???
 pub const QUARTER_NOTE_WEIRD_SPACING_OFFSET: f32 = 18.0;
 
 #[system(
 	plugin = PlayerCommandsPlugin, schedule = Startup,
 )]
-fn spawn_staff(mut commands: Commands, asset_server: Res<AssetServer>) {
+fn spawn_staff(mut commands: Commands, asset_server: Res<AssetServer>, keybinds: Res<Keybinds>) {
 	// Background
 	commands
 		.spawn((
@@ -52,48 +56,10 @@ fn spawn_staff(mut commands: Commands, asset_server: Res<AssetServer>) {
 			BackgroundColor(css::BEIGE.into()),
 			CommandStaff,
 			PlayerCameraNode,
-			input_manager_bundle(
-				InputMap::default()
-					.with(PlayNoteAction::C4, KeyCode::KeyZ)
-					.with(PlayNoteAction::CS4, KeyCode::KeyS)
-					.with(PlayNoteAction::D4, KeyCode::KeyX)
-					.with(PlayNoteAction::DS4, KeyCode::KeyD)
-					.with(PlayNoteAction::E4, KeyCode::KeyC)
-					.with(PlayNoteAction::F4, KeyCode::KeyV)
-					.with(PlayNoteAction::FS4, KeyCode::KeyG)
-					.with(PlayNoteAction::G4, KeyCode::KeyB)
-					.with(PlayNoteAction::GS4, KeyCode::KeyH)
-					.with(PlayNoteAction::A4, KeyCode::KeyN)
-					.with(PlayNoteAction::AS4, KeyCode::KeyJ)
-					.with(PlayNoteAction::B4, KeyCode::KeyM)
-					.with(PlayNoteAction::C5, KeyCode::Comma)
-					.with(PlayNoteAction::CS5, KeyCode::KeyL)
-					.with(PlayNoteAction::D5, KeyCode::Period)
-					.with(PlayNoteAction::DS5, KeyCode::Semicolon)
-					.with(PlayNoteAction::E5, KeyCode::Slash)
-					.with(PlayNoteAction::C5, KeyCode::KeyQ)
-					.with(PlayNoteAction::CS5, KeyCode::Digit2)
-					.with(PlayNoteAction::D5, KeyCode::KeyW)
-					.with(PlayNoteAction::DS5, KeyCode::Digit3)
-					.with(PlayNoteAction::E5, KeyCode::KeyE)
-					.with(PlayNoteAction::F5, KeyCode::KeyR)
-					.with(PlayNoteAction::FS5, KeyCode::Digit5)
-					.with(PlayNoteAction::G5, KeyCode::KeyT)
-					.with(PlayNoteAction::GS5, KeyCode::Digit6)
-					.with(PlayNoteAction::A5, KeyCode::KeyY)
-					.with(PlayNoteAction::AS5, KeyCode::Digit7)
-					.with(PlayNoteAction::B5, KeyCode::KeyU)
-					.with(PlayNoteAction::C6, KeyCode::KeyI)
-					.with(PlayNoteAction::CS6, KeyCode::Digit9)
-					.with(PlayNoteAction::D6, KeyCode::KeyO)
-					.with(PlayNoteAction::DS6, KeyCode::Digit0)
-					.with(PlayNoteAction::E6, KeyCode::KeyP),
-				false,
-			),
-			input_manager_bundle(
-				InputMap::default().with(CloseStaffAction, KeyCode::Backquote),
-				false,
-			),
+			keybound_input_manager(&keybinds, |keybinds| &keybinds.staff, false),
+			keybound_input_manager(&keybinds, |keybinds| &keybinds.close_staff, false),
+			keybound_input_manager(&keybinds, |keybinds| &keybinds.radial_notes, false),
+			RadialNoteSelector::default(),
 			Menu,
 			MenuWithInputManager,
 			MenuWithoutMouse,
@@ -111,6 +77,8 @@ fn spawn_staff(mut commands: Commands, asset_server: Res<AssetServer>) {
 				},
 			));
 
+			spawn_radial_note_selector(parent);
+
 			// Staff lines
 			parent
 				.spawn((
@@ -157,7 +125,7 @@ impl OpenMenuBinding for OpenStaffBinding {
 )]
 use crate::menus::show_menu_on_action;
 
-#[derive(Actionlike, Clone, Copy, Eq, PartialEq, Hash, Reflect, Debug)]
+#[derive(Actionlike, Clone, Copy, Eq, PartialEq, Hash, Reflect, Debug, Serialize, Deserialize)]
 pub struct CloseStaffAction;
 
 pub struct CloseStaffBinding;
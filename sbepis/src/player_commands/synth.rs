@@ -0,0 +1,148 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_butler::*;
+use soundyrust::{MidiAudio, Note};
+
+use crate::fray::{FrayMusic, FrayTracks};
+use crate::player_commands::notes::{NotePlayed, NotePlayedSet, NoteReleased};
+use crate::player_commands::registry::NoteCommandInvoked;
+use crate::player_commands::{CommandSentSet, NoteArgument, PlayerCommandsPlugin};
+use crate::some_or_return;
+
+const CHANNEL: i32 = 0;
+const VELOCITY: i32 = 100;
+
+/// Notes get nudged onto this grid so they line up with the backing track.
+const BEAT_GRID: f64 = 0.5;
+
+/// General MIDI bank and program for the staff.
+#[derive(Resource)]
+#[resource(plugin = PlayerCommandsPlugin, init = StaffInstrument {
+	bank: 0,
+	patch: 73, // Flute
+})]
+pub struct StaffInstrument {
+	pub bank: u8,
+	pub patch: u8,
+}
+
+/// Staff notes waiting on the beat grid before they go to the fray's staff track.
+#[derive(Resource, Default)]
+#[resource(plugin = PlayerCommandsPlugin)]
+pub struct StaffNoteQueue {
+	/// Keys that have been pressed, with how long they waited, so they can be released the same
+	/// amount later.
+	held: Vec<(Note, i32, Duration)>,
+	pending: Vec<PendingStaffNote>,
+}
+
+struct PendingStaffNote {
+	key: i32,
+	on: bool,
+	remaining: Duration,
+}
+
+fn midi_key(note: Note) -> i32 {
+	(69.0 + 12.0 * (note.frequency / 440.0).log2()).round() as i32
+}
+
+#[system(
+	plugin = PlayerCommandsPlugin, schedule = Update,
+	run_if = resource_changed::<StaffInstrument>,
+)]
+fn change_staff_instrument(
+	instrument: Res<StaffInstrument>,
+	fray_music: Query<&AudioPlayer<MidiAudio>, With<FrayMusic>>,
+	fray_tracks: Option<Res<FrayTracks>>,
+	mut assets: ResMut<Assets<MidiAudio>>,
+) {
+	let fray_tracks = some_or_return!(fray_tracks);
+	let midi_audio = some_or_return!(fray_music.get_single().ok());
+	let midi_audio = some_or_return!(assets.get_mut(&midi_audio.0));
+
+	midi_audio.set_channel_patch(
+		fray_tracks.staff,
+		CHANNEL,
+		instrument.bank as i32,
+		instrument.patch as i32,
+	);
+}
+
+#[system(
+	plugin = PlayerCommandsPlugin, schedule = Update,
+	after = NotePlayedSet,
+)]
+fn play_staff_notes(
+	mut ev_note_played: EventReader<NotePlayed>,
+	mut ev_note_released: EventReader<NoteReleased>,
+	mut queue: ResMut<StaffNoteQueue>,
+	fray_music: Query<(&FrayMusic, &AudioPlayer<MidiAudio>)>,
+	fray_tracks: Res<FrayTracks>,
+	mut assets: ResMut<Assets<MidiAudio>>,
+	time: Res<Time>,
+) {
+	let (fray_music, midi_audio) = some_or_return!(fray_music.get_single().ok());
+	let midi_audio = some_or_return!(assets.get_mut(&midi_audio.0));
+
+	for ev in ev_note_released.read() {
+		let Some(index) = queue.held.iter().position(|(note, ..)| *note == ev.note) else {
+			continue;
+		};
+		let (_, key, delay) = queue.held.remove(index);
+		queue.pending.push(PendingStaffNote {
+			key,
+			on: false,
+			remaining: delay,
+		});
+	}
+
+	for ev in ev_note_played.read() {
+		// Late notes play right away, early ones wait for the grid
+		let beats_past_grid = ev.beat % BEAT_GRID;
+		let delay = if beats_past_grid < BEAT_GRID * 0.5 {
+			Duration::ZERO
+		} else {
+			fray_music.bpm_beat_to_time(BEAT_GRID - beats_past_grid)
+		};
+
+		let key = fray_music.key().clamp(midi_key(ev.note));
+		queue.held.push((ev.note, key, delay));
+		queue.pending.push(PendingStaffNote {
+			key,
+			on: true,
+			remaining: delay,
+		});
+	}
+
+	let delta = time.delta();
+	queue.pending.retain_mut(|pending| {
+		if pending.remaining > delta {
+			pending.remaining -= delta;
+			return true;
+		}
+
+		if pending.on {
+			midi_audio.note_on(fray_tracks.staff, CHANNEL, pending.key, VELOCITY);
+		} else {
+			midi_audio.note_off(fray_tracks.staff, CHANNEL, pending.key);
+		}
+		false
+	});
+}
+
+#[system(
+	plugin = PlayerCommandsPlugin, schedule = Update,
+	after = CommandSentSet,
+)]
+fn instrument(
+	mut ev_command: EventReader<NoteCommandInvoked>,
+	mut instrument: ResMut<StaffInstrument>,
+) {
+	for ev in ev_command.read().filter(|ev| ev.id == "instrument") {
+		let Some(&NoteArgument::UnsignedInt(patch)) = ev.args.first() else {
+			continue;
+		};
+		instrument.patch = patch.min(127) as u8;
+	}
+}
@@ -0,0 +1,244 @@
+use std::time::Duration;
+
+use bevy::color::palettes::css;
+use bevy::prelude::*;
+use bevy_butler::*;
+use bevy_rapier3d::prelude::Velocity;
+
+use crate::camera::PlayerCameraNode;
+use crate::entity::{EntityKilled, EntityKilledSet, GelViscosity, Invulnerable};
+use crate::menus::{Menu, MenuDespawnsWhenClosed, MenuStack, MenuWithoutMouse};
+use crate::player_controller::weapons::{EntityDamaged, EntityDamagedSet};
+use crate::player_controller::PlayerControllerPlugin;
+use crate::prelude::PlayerBody;
+use crate::some_or_return;
+
+pub const PLAYER_MAX_VISCOSITY: f32 = 6.0;
+/// How long the player can't be hurt for after getting hit.
+const PLAYER_INVULNERABILITY_TIME: Duration = Duration::from_secs(1);
+const PLAYER_RESPAWN_TIME: Duration = Duration::from_secs(3);
+/// Where the player respawns if the level doesn't have a [`PlayerSpawnPoint`].
+pub const DEFAULT_PLAYER_SPAWN: Vec3 = Vec3::new(5.0, 10.0, 0.0);
+
+/// Where the player comes back after dying, placed in the level with a `PlayerSpawnBlundle`.
+#[derive(Component)]
+pub struct PlayerSpawnPoint;
+
+#[derive(Component)]
+pub struct PlayerDead;
+
+#[derive(Component)]
+pub struct DeathScreen {
+	pub respawn_timer: Timer,
+}
+
+#[derive(Component)]
+pub struct DeathScreenCountdown;
+
+#[derive(Component)]
+pub struct PlayerGelVial;
+
+#[derive(Component)]
+pub struct PlayerGelVialText;
+
+#[system(
+	plugin = PlayerControllerPlugin, schedule = Startup,
+)]
+fn spawn_player_gel_vial(mut commands: Commands) {
+	commands
+		.spawn((
+			Name::new("Player Gel Vial"),
+			Node {
+				position_type: PositionType::Absolute,
+				top: Val::Px(5.0),
+				left: Val::Px(5.0),
+				width: Val::Px(200.0),
+				height: Val::Px(24.0),
+				border: UiRect::all(Val::Px(2.0)),
+				..default()
+			},
+			BorderColor(css::DARK_GRAY.into()),
+			BackgroundColor(css::GRAY.with_alpha(0.5).into()),
+			PlayerCameraNode,
+		))
+		.with_children(|parent| {
+			parent.spawn((
+				Node {
+					width: Val::Percent(100.0),
+					height: Val::Percent(100.0),
+					..default()
+				},
+				BackgroundColor(css::RED.into()),
+				PlayerGelVial,
+			));
+			parent.spawn((
+				Node {
+					position_type: PositionType::Absolute,
+					left: Val::Px(5.0),
+					..default()
+				},
+				Text::default(),
+				TextColor(Color::WHITE),
+				TextFont {
+					font_size: 16.0,
+					..default()
+				},
+				PlayerGelVialText,
+			));
+		});
+}
+
+#[system(
+	plugin = PlayerControllerPlugin, schedule = Update,
+	after = EntityDamagedSet,
+)]
+fn update_player_gel_vial(
+	player: Query<&GelViscosity, (With<PlayerBody>, Changed<GelViscosity>)>,
+	mut gel_vials: Query<&mut Node, With<PlayerGelVial>>,
+	mut gel_vial_texts: Query<&mut Text, With<PlayerGelVialText>>,
+) {
+	let viscosity = some_or_return!(player.get_single().ok());
+
+	for mut gel_vial in gel_vials.iter_mut() {
+		gel_vial.width = Val::Percent((viscosity.value / viscosity.max).clamp(0.0, 1.0) * 100.0);
+	}
+	for mut gel_vial_text in gel_vial_texts.iter_mut() {
+		gel_vial_text.0 = format!("{:.1}/{:.1}", viscosity.value.max(0.0), viscosity.max);
+	}
+}
+
+#[system(
+	plugin = PlayerControllerPlugin, schedule = Update,
+	after = EntityDamagedSet,
+)]
+fn give_player_invulnerability_frames(
+	mut ev_damaged: EventReader<EntityDamaged>,
+	mut commands: Commands,
+	players: Query<(), With<PlayerBody>>,
+) {
+	for ev in ev_damaged.read() {
+		if ev.damage > 0.0 && players.contains(ev.victim) {
+			commands
+				.entity(ev.victim)
+				.insert(Invulnerable::new(PLAYER_INVULNERABILITY_TIME));
+		}
+	}
+}
+
+#[system(
+	plugin = PlayerControllerPlugin, schedule = Update,
+	after = EntityKilledSet,
+	after = give_player_invulnerability_frames,
+)]
+fn kill_player(
+	mut ev_killed: EventReader<EntityKilled>,
+	mut commands: Commands,
+	players: Query<(), (With<PlayerBody>, Without<PlayerDead>)>,
+	mut menu_stack: ResMut<MenuStack>,
+) {
+	for EntityKilled(entity) in ev_killed.read() {
+		if !players.contains(*entity) {
+			continue;
+		}
+
+		commands.entity(*entity).insert((
+			PlayerDead,
+			// Nothing can hurt the player while they're dead, or for a moment after respawning
+			Invulnerable::new(PLAYER_RESPAWN_TIME + PLAYER_INVULNERABILITY_TIME),
+		));
+
+		// Being on top of the menu stack disables the player's input
+		let death_screen = commands
+			.spawn((
+				Name::new("Death Screen"),
+				Node {
+					width: Val::Percent(100.0),
+					height: Val::Percent(100.0),
+					flex_direction: FlexDirection::Column,
+					justify_content: JustifyContent::Center,
+					align_items: AlignItems::Center,
+					..default()
+				},
+				BackgroundColor(css::DARK_RED.with_alpha(0.5).into()),
+				PlayerCameraNode,
+				Menu,
+				MenuWithoutMouse,
+				MenuDespawnsWhenClosed,
+				DeathScreen {
+					respawn_timer: Timer::new(PLAYER_RESPAWN_TIME, TimerMode::Once),
+				},
+			))
+			.with_children(|parent| {
+				parent.spawn((
+					Text("Your gel has gone runny".to_owned()),
+					TextColor(Color::WHITE),
+					TextFont {
+						font_size: 40.0,
+						..default()
+					},
+				));
+				parent.spawn((
+					Text::default(),
+					TextColor(Color::WHITE),
+					TextFont {
+						font_size: 20.0,
+						..default()
+					},
+					DeathScreenCountdown,
+				));
+			})
+			.id();
+		menu_stack.push(death_screen);
+	}
+}
+
+#[system(
+	plugin = PlayerControllerPlugin, schedule = Update,
+)]
+fn respawn_player(
+	mut death_screens: Query<(Entity, &mut DeathScreen)>,
+	mut countdowns: Query<&mut Text, With<DeathScreenCountdown>>,
+	mut commands: Commands,
+	mut player: Query<
+		(
+			Entity,
+			&mut Transform,
+			&mut GelViscosity,
+			Option<&mut Velocity>,
+		),
+		With<PlayerDead>,
+	>,
+	spawn_points: Query<&GlobalTransform, With<PlayerSpawnPoint>>,
+	mut menu_stack: ResMut<MenuStack>,
+	time: Res<Time>,
+) {
+	for (death_screen_entity, mut death_screen) in death_screens.iter_mut() {
+		death_screen.respawn_timer.tick(time.delta());
+
+		for mut countdown in countdowns.iter_mut() {
+			countdown.0 = format!(
+				"Respawning in {}",
+				death_screen.respawn_timer.remaining_secs().ceil()
+			);
+		}
+
+		if !death_screen.respawn_timer.finished() {
+			continue;
+		}
+
+		menu_stack.remove(death_screen_entity);
+
+		let (player_entity, mut transform, mut viscosity, velocity) =
+			some_or_return!(player.get_single_mut().ok());
+		transform.translation = spawn_points
+			.iter()
+			.next()
+			.map(|spawn_point| spawn_point.translation())
+			.unwrap_or(DEFAULT_PLAYER_SPAWN);
+		viscosity.value = viscosity.max;
+		if let Some(mut velocity) = velocity {
+			*velocity = Velocity::zero();
+		}
+		commands.entity(player_entity).remove::<PlayerDead>();
+	}
+}
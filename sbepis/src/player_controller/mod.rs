@@ -5,23 +5,30 @@ use bevy::render::mesh::CapsuleUvProfile;
 use bevy_butler::*;
 use bevy_rapier3d::prelude::*;
 use leafwing_input_manager::prelude::*;
+use marching_cubes::TerrainViewer;
+use serde::{Deserialize, Serialize};
 
 use crate::camera::PlayerCamera;
+use crate::entity::GelViscosity;
 use crate::gridbox_material;
-use crate::input::*;
+use crate::grist::GristCache;
 use crate::inventory::Inventory;
+use crate::keybinds::{keybound_input_manager, Keybinds};
 use crate::main_bundles::Mob;
 use crate::menus::{
 	InputManagerMenuPlugin, Menu, MenuStack, MenuWithInputManager, MenuWithoutMouse,
 };
 
 use self::camera_controls::*;
+use self::death::*;
 use self::weapons::hammer::*;
 use self::weapons::rifle::*;
+use self::weapons::shovel::*;
 use self::weapons::sword::*;
 use self::weapons::*;
 
 pub mod camera_controls;
+pub mod death;
 pub mod movement;
 pub mod weapons;
 
@@ -41,24 +48,11 @@ fn setup(
 	mut graphs: ResMut<Assets<AnimationGraph>>,
 	asset_server: Res<AssetServer>,
 	mut menu_stack: ResMut<MenuStack>,
+	keybinds: Res<Keybinds>,
 ) {
 	let input = commands
 		.spawn((
-			input_manager_bundle(
-				InputMap::default()
-					.with_dual_axis(PlayerAction::Move, VirtualDPad::wasd())
-					.with(PlayerAction::Jump, KeyCode::Space)
-					.with_dual_axis(PlayerAction::Look, MouseMove::default())
-					.with(PlayerAction::Sprint, KeyCode::ShiftLeft)
-					.with(PlayerAction::Use, MouseButton::Left)
-					.with(PlayerAction::Interact, KeyCode::KeyE)
-					.with(PlayerAction::NextWeapon, MouseScrollDirection::UP)
-					.with(PlayerAction::PrevWeapon, MouseScrollDirection::DOWN)
-					.with(PlayerAction::OpenQuestScreen, KeyCode::KeyJ)
-					.with(PlayerAction::OpenInventory, KeyCode::KeyV)
-					.with(PlayerAction::OpenStaff, KeyCode::Backquote),
-				false,
-			),
+			keybound_input_manager(&keybinds, |keybinds| &keybinds.player, false),
 			Menu,
 			MenuWithInputManager,
 			MenuWithoutMouse,
@@ -69,7 +63,7 @@ fn setup(
 	let body = commands
 		.spawn((
 			Name::new("Player Body"),
-			Transform::from_translation(Vec3::new(5.0, 10.0, 0.0)),
+			Transform::from_translation(DEFAULT_PLAYER_SPAWN),
 			Mesh3d(
 				meshes.add(
 					Capsule3d::new(0.25, 1.0)
@@ -83,8 +77,13 @@ fn setup(
 			MeshMaterial3d(gridbox_material("white", &mut materials, &asset_server)),
 			Collider::capsule_y(0.5, 0.25),
 			Mob,
+			GelViscosity {
+				value: PLAYER_MAX_VISCOSITY,
+				max: PLAYER_MAX_VISCOSITY,
+			},
 			PlayerBody { is_grounded: false },
-			Inventory::default(),
+			Inventory::from_args(),
+			GristCache::default(),
 		))
 		.id();
 
@@ -100,6 +99,7 @@ fn setup(
 			PlayerCamera,
 			Pitch(0.0),
 			SpatialListener::new(-0.25),
+			TerrainViewer,
 		))
 		.set_parent(body)
 		.id();
@@ -134,9 +134,37 @@ fn setup(
 		body,
 	);
 
+	let (shovel_pivot, _shovel_blade) = spawn_shovel(
+		&mut commands,
+		&asset_server,
+		&mut materials,
+		&mut meshes,
+		&mut animations,
+		&mut graphs,
+		body,
+		ShovelMode::Dig,
+	);
+
+	let (trowel_pivot, _trowel_blade) = spawn_shovel(
+		&mut commands,
+		&asset_server,
+		&mut materials,
+		&mut meshes,
+		&mut animations,
+		&mut graphs,
+		body,
+		ShovelMode::Build,
+	);
+
 	commands.entity(body).insert((
 		WeaponSet {
-			weapons: vec![hammer_pivot, sword_pivot, rifle_pivot],
+			weapons: vec![
+				hammer_pivot,
+				sword_pivot,
+				rifle_pivot,
+				shovel_pivot,
+				trowel_pivot,
+			],
 			active_weapon: 0,
 		},
 		UninitializedWeaponSet,
@@ -189,7 +217,7 @@ fn update_is_grounded(
 	}
 }
 
-#[derive(Clone, Copy, Eq, PartialEq, Hash, Reflect, Debug)]
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Reflect, Debug, Serialize, Deserialize)]
 pub enum PlayerAction {
 	Move,
 	Jump,
@@ -202,6 +230,7 @@ pub enum PlayerAction {
 	OpenQuestScreen,
 	OpenInventory,
 	OpenStaff,
+	OpenKeybinds,
 }
 impl Actionlike for PlayerAction {
 	fn input_control_kind(&self) -> InputControlKind {
@@ -217,6 +246,7 @@ impl Actionlike for PlayerAction {
 			PlayerAction::OpenQuestScreen => InputControlKind::Button,
 			PlayerAction::OpenInventory => InputControlKind::Button,
 			PlayerAction::OpenStaff => InputControlKind::Button,
+			PlayerAction::OpenKeybinds => InputControlKind::Button,
 		}
 	}
 }
@@ -4,10 +4,20 @@ use bevy::animation::{animated_field, AnimationTarget, AnimationTargetId};
 use bevy::ecs::entity::EntityHashSet;
 use bevy::prelude::*;
 use bevy::render::mesh::CapsuleUvProfile;
+use bevy_rapier3d::plugin::RapierContext;
+use marching_cubes::{Brush, DeformTerrain, TerrainChunk};
 
+use crate::camera::PlayerCamera;
 use crate::fray::FrayMusic;
 use crate::gridbox_material;
-use crate::player_controller::weapons::{DamageSweep, EndDamageSweep, SweepPivot, WeaponAnimation};
+use crate::player_controller::weapons::{
+	aim_at_terrain, DamageSweep, EndDamageSweep, SweepPivot, WeaponAnimation,
+};
+
+/// How far in front of the camera the hammer can dent terrain.
+const HAMMER_DENT_REACH: f32 = 2.5;
+const HAMMER_DENT_RADIUS: f32 = 0.75;
+const HAMMER_DENT_DEPTH: f32 = 0.5;
 
 #[derive(Component)]
 struct HammerPivot {
@@ -158,6 +168,10 @@ fn on_hammer_smash(
 	hammers: Query<&Hammer>,
 	fray: Query<&FrayMusic>,
 	mut commands: Commands,
+	rapier_contexts: Query<&RapierContext>,
+	player_cameras: Query<&GlobalTransform, With<PlayerCamera>>,
+	terrain_chunks: Query<(), With<TerrainChunk>>,
+	mut ev_deform: EventWriter<DeformTerrain>,
 ) {
 	let hammer_pivot_entity = trigger.entity();
 	let hammer_pivot = hammer_pivots
@@ -178,4 +192,17 @@ fn on_hammer_smash(
 		AudioPlayer::new(hammer.smash_sound.clone()),
 		PlaybackSettings::DESPAWN,
 	));
+
+	if let Some(point) = aim_at_terrain(
+		rapier_contexts.single(),
+		player_cameras.single(),
+		HAMMER_DENT_REACH,
+		&terrain_chunks,
+	) {
+		ev_deform.send(DeformTerrain(Brush::dig(
+			point,
+			HAMMER_DENT_RADIUS,
+			HAMMER_DENT_DEPTH,
+		)));
+	}
 }
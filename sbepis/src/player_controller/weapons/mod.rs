@@ -3,8 +3,9 @@ use bevy::ecs::entity::EntityHashSet;
 use bevy::prelude::*;
 use bevy_butler::*;
 use bevy_rapier3d::prelude::*;
+use marching_cubes::TerrainChunk;
 
-use crate::entity::{EntityKilled, EntityKilledSet, GelViscosity};
+use crate::entity::{EntityKilled, EntityKilledSet, GelViscosity, Invulnerable};
 use crate::fray::FrayMusic;
 use crate::input::button_just_pressed;
 use crate::player_controller::{PlayerAction, PlayerControllerPlugin};
@@ -12,6 +13,7 @@ use crate::util::{find_in_ancestors, QuaternionEx};
 
 pub mod hammer;
 pub mod rifle;
+pub mod shovel;
 pub mod sword;
 
 #[derive(Event)]
@@ -98,6 +100,23 @@ pub struct DebugColliderVisualizer;
 #[derive(Component)]
 pub struct WeaponAnimation(pub AnimationNodeIndex);
 
+/// Where the camera's looking at terrain, if there's any within reach.
+pub fn aim_at_terrain(
+	rapier_context: &RapierContext,
+	camera: &GlobalTransform,
+	reach: f32,
+	terrain_chunks: &Query<(), With<TerrainChunk>>,
+) -> Option<Vec3> {
+	let (_, distance) = rapier_context.cast_ray(
+		camera.translation(),
+		camera.forward().into(),
+		reach,
+		false,
+		QueryFilter::new().predicate(&|entity| terrain_chunks.contains(entity)),
+	)?;
+	Some(camera.translation() + camera.forward() * distance)
+}
+
 #[system(
 	plugin = PlayerControllerPlugin, schedule = Update,
 	run_if = button_just_pressed(PlayerAction::Use),
@@ -212,12 +231,13 @@ fn sweep_dealers(
 fn hit_to_damage(
 	parents: Query<&Parent>,
 	healths: Query<Entity, With<GelViscosity>>,
+	invulnerables: Query<(), With<Invulnerable>>,
 	mut ev_hit: EventReader<EntityHit>,
 	mut ev_damage: EventWriter<EntityDamaged>,
 ) {
 	for event in ev_hit.read() {
 		let victim = find_in_ancestors(event.victim, &healths, &parents).unwrap_or(event.victim);
-		if !event.allies.contains(&victim) {
+		if !event.allies.contains(&victim) && !invulnerables.contains(victim) {
 			ev_damage.send(EntityDamaged {
 				victim,
 				damage: event.damage,
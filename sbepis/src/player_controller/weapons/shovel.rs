@@ -0,0 +1,210 @@
+use std::f32::consts::PI;
+
+use bevy::animation::{animated_field, AnimationTarget, AnimationTargetId};
+use bevy::prelude::*;
+use bevy::render::mesh::CapsuleUvProfile;
+use bevy_rapier3d::plugin::RapierContext;
+use marching_cubes::{Brush, DeformTerrain, TerrainChunk};
+
+use crate::camera::PlayerCamera;
+use crate::fray::FrayMusic;
+use crate::gridbox_material;
+use crate::grist::{GristAmounts, GristCache, GristType};
+use crate::player_controller::weapons::{aim_at_terrain, WeaponAnimation};
+
+#[derive(Component)]
+pub struct ShovelPivot {
+	blade: Entity,
+}
+
+/// How much build grist piling up one scoop of terrain costs.
+const BUILD_GRIST_PER_SCOOP: u32 = 1;
+
+/// Whether a shovel takes terrain away or puts it back.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShovelMode {
+	Dig,
+	/// Paid for with build grist out of the player's cache.
+	Build,
+}
+
+impl ShovelMode {
+	pub fn name(self) -> &'static str {
+		match self {
+			ShovelMode::Dig => "Shovel",
+			ShovelMode::Build => "Trowel",
+		}
+	}
+}
+
+/// Carves tunnels out of terrain, or fills them back in, instead of hurting anything.
+#[derive(Component)]
+pub struct Shovel {
+	pub mode: ShovelMode,
+	/// How far in front of the camera the shovel can dig.
+	pub reach: f32,
+	pub dig_radius: f32,
+	/// How deep each scoop goes, before the fray gets a say.
+	pub dig_depth: f32,
+	pub dig_sound: Handle<AudioSource>,
+}
+
+pub fn spawn_shovel(
+	commands: &mut Commands,
+	asset_server: &AssetServer,
+	materials: &mut Assets<StandardMaterial>,
+	meshes: &mut Assets<Mesh>,
+	animations: &mut Assets<AnimationClip>,
+	graphs: &mut Assets<AnimationGraph>,
+	body: Entity,
+	mode: ShovelMode,
+) -> (Entity, Entity) {
+	let shovel_pivot_name = format!("{} Pivot", mode.name());
+	let shovel_pivot_id = AnimationTargetId::from_iter([&shovel_pivot_name]);
+
+	let scoop_time = 0.25;
+	let recover_time = scoop_time + 0.5;
+
+	let mut attack_animation = AnimationClip::default();
+	attack_animation.add_curve_to_target(
+		shovel_pivot_id,
+		AnimatableCurve::new(
+			animated_field!(Transform::rotation),
+			EasingCurve::new(
+				Quat::from_rotation_x(0.0),
+				Quat::from_rotation_x(-PI * 0.25),
+				EaseFunction::QuadraticIn,
+			)
+			.reparametrize_linear(Interval::new(0.0, scoop_time).unwrap())
+			.unwrap()
+			.chain(
+				EasingCurve::new(
+					Quat::from_rotation_x(-PI * 0.25),
+					Quat::from_rotation_x(0.0),
+					EaseFunction::CubicOut,
+				)
+				.reparametrize_linear(Interval::new(scoop_time, recover_time).unwrap())
+				.unwrap(),
+			)
+			.unwrap(),
+		),
+	);
+	attack_animation.add_event(scoop_time, ShovelDig);
+
+	let (graph, animation_index) = AnimationGraph::from_clip(animations.add(attack_animation));
+
+	let shovel_blade = commands
+		.spawn((
+			Name::new(format!("{} Blade", mode.name())),
+			Transform::from_translation(Vec3::NEG_Z * 0.5)
+				.with_rotation(Quat::from_rotation_x(-PI / 2.)),
+			Mesh3d(
+				meshes.add(
+					Capsule3d::new(0.15, 0.3)
+						.mesh()
+						.rings(1)
+						.latitudes(8)
+						.longitudes(16)
+						.uv_profile(CapsuleUvProfile::Fixed),
+				),
+			),
+			MeshMaterial3d(gridbox_material(
+				match mode {
+					ShovelMode::Dig => "brown",
+					ShovelMode::Build => "blue1",
+				},
+				materials,
+				asset_server,
+			)),
+			Shovel {
+				mode,
+				reach: 3.0,
+				dig_radius: 1.5,
+				dig_depth: 1.0,
+				dig_sound: asset_server.load("concrete_break3.wav"),
+			},
+		))
+		.id();
+
+	let shovel_pivot = commands
+		.spawn((
+			Name::new(shovel_pivot_name),
+			Transform::from_translation(Vec3::new(0.25, -0.25, -0.25)),
+			Visibility::default(),
+			ShovelPivot {
+				blade: shovel_blade,
+			},
+			AnimationGraphHandle(graphs.add(graph)),
+			AnimationPlayer::default(),
+			WeaponAnimation(animation_index),
+		))
+		.set_parent(body)
+		.add_child(shovel_blade)
+		.observe(on_shovel_dig)
+		.id();
+	commands.entity(shovel_pivot).insert(AnimationTarget {
+		id: shovel_pivot_id,
+		player: shovel_pivot,
+	});
+
+	(shovel_pivot, shovel_blade)
+}
+
+#[derive(Event, Clone, Copy)]
+struct ShovelDig;
+
+fn on_shovel_dig(
+	trigger: Trigger<ShovelDig>,
+	shovel_pivots: Query<&ShovelPivot>,
+	shovels: Query<&Shovel>,
+	frays: Query<&FrayMusic>,
+	rapier_contexts: Query<&RapierContext>,
+	player_cameras: Query<&GlobalTransform, With<PlayerCamera>>,
+	terrain_chunks: Query<(), With<TerrainChunk>>,
+	parents: Query<&Parent>,
+	mut grist_caches: Query<&mut GristCache>,
+	mut commands: Commands,
+	mut ev_deform: EventWriter<DeformTerrain>,
+) {
+	let shovel_pivot = shovel_pivots
+		.get(trigger.entity())
+		.expect("Shovel pivot not found");
+	let shovel = shovels.get(shovel_pivot.blade).expect("Shovel not found");
+	let fray = frays.get_single().expect("Could not find fray");
+
+	let Some(point) = aim_at_terrain(
+		rapier_contexts.single(),
+		player_cameras.single(),
+		shovel.reach,
+		&terrain_chunks,
+	) else {
+		return;
+	};
+
+	let depth = fray.modify_fray_damage(shovel.dig_depth);
+	let brush = match shovel.mode {
+		ShovelMode::Dig => Brush::dig(point, shovel.dig_radius, depth),
+		ShovelMode::Build => {
+			let cost = GristAmounts::from([(GristType::Build, BUILD_GRIST_PER_SCOOP)]);
+			let body = parents
+				.get(trigger.entity())
+				.expect("Shovel pivot has no body")
+				.get();
+			let Ok(mut grist_cache) = grist_caches.get_mut(body) else {
+				return;
+			};
+			if !grist_cache.spend(&cost) {
+				info!("Not enough grist to build");
+				return;
+			}
+			Brush::build(point, shovel.dig_radius, depth)
+		}
+	};
+	ev_deform.send(DeformTerrain(brush));
+
+	commands.spawn((
+		Name::new("Shovel Dig SFX"),
+		AudioPlayer::new(shovel.dig_sound.clone()),
+		PlaybackSettings::DESPAWN,
+	));
+}
@@ -0,0 +1,306 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use bevy_butler::*;
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::player_commands::PlayNoteAction;
+use crate::questing::objectives::{Goal, Objective};
+use crate::questing::{CompletedQuests, Quest, QuestReward, QuestingPlugin, Quests};
+
+#[derive(Asset, Deserialize, TypePath)]
+pub struct QuestDefinitions {
+	quests: Vec<QuestDefinition>,
+}
+
+/// A template that quests get rolled from.
+///
+/// Any `{variable}` in the text gets filled in when the quest is rolled, from [`Self::variables`]
+/// and from `{giver}`, the name of whoever is handing it out.
+#[derive(Debug, Clone, Deserialize)]
+pub struct QuestDefinition {
+	pub id: String,
+	pub name: String,
+	pub description: String,
+	/// Inclusive ranges that get rolled once per quest. Backwards ranges get flipped around.
+	#[serde(default)]
+	pub variables: BTreeMap<String, (u32, u32)>,
+	pub objectives: Vec<ObjectiveDefinition>,
+	/// Quests that have to be completed before this one gets offered.
+	#[serde(default)]
+	pub prerequisites: Vec<String>,
+	#[serde(default)]
+	pub rewards: Vec<QuestReward>,
+	/// Whether this can be offered again after being completed.
+	#[serde(default)]
+	pub repeatable: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ObjectiveDefinition {
+	pub description: String,
+	pub goal: GoalDefinition,
+}
+
+/// A [`Goal`] that might still refer to variables.
+#[derive(Debug, Clone, Deserialize)]
+pub enum GoalDefinition {
+	Kill { target: String, amount: Amount },
+	Fetch { item: String },
+	Reach { location: Vec3, radius: f32 },
+	PlayNotes { pattern: Vec<PlayNoteAction> },
+	TalkTo { npc: String },
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub enum Amount {
+	Fixed(u32),
+	Var(String),
+}
+
+impl Amount {
+	fn resolve(&self, variables: &BTreeMap<String, u32>) -> u32 {
+		match self {
+			Amount::Fixed(amount) => *amount,
+			Amount::Var(name) => variables.get(name).copied().unwrap_or_else(|| {
+				warn!("Quest variable {name:?} isn't defined");
+				1
+			}),
+		}
+	}
+}
+
+impl QuestDefinition {
+	pub fn is_available(&self, completed: &CompletedQuests, active: &Quests) -> bool {
+		let prerequisites_met = self
+			.prerequisites
+			.iter()
+			.all(|prerequisite| completed.0.contains(prerequisite));
+		let already_active = active.0.values().any(|quest| quest.definition == self.id);
+		let already_completed = !self.repeatable && completed.0.contains(&self.id);
+
+		prerequisites_met && !already_active && !already_completed
+	}
+
+	pub fn roll(&self, rng: &mut impl Rng, giver: Option<&str>) -> Quest {
+		let variables = self
+			.variables
+			.iter()
+			.map(|(name, &(min, max))| (name.clone(), rng.gen_range(min.min(max)..=min.max(max))))
+			.collect::<BTreeMap<_, _>>();
+
+		let mut text_variables = variables
+			.iter()
+			.map(|(name, value)| (name.as_str(), value.to_string()))
+			.collect::<Vec<_>>();
+		text_variables.push(("giver", giver.unwrap_or("someone").to_owned()));
+
+		Quest {
+			id: rng.gen(),
+			definition: self.id.clone(),
+			name: interpolate(&self.name, &text_variables),
+			description: interpolate(&self.description, &text_variables),
+			objectives: self
+				.objectives
+				.iter()
+				.map(|objective| Objective {
+					description: interpolate(&objective.description, &text_variables),
+					goal: objective.goal.resolve(&variables),
+					progress: 0,
+				})
+				.collect(),
+			rewards: self.rewards.clone(),
+		}
+	}
+}
+
+impl GoalDefinition {
+	fn resolve(&self, variables: &BTreeMap<String, u32>) -> Goal {
+		match self {
+			GoalDefinition::Kill { target, amount } => Goal::Kill {
+				target: target.clone(),
+				amount: amount.resolve(variables),
+			},
+			GoalDefinition::Fetch { item } => Goal::Fetch { item: item.clone() },
+			GoalDefinition::Reach { location, radius } => Goal::Reach {
+				location: *location,
+				radius: *radius,
+			},
+			GoalDefinition::PlayNotes { pattern } => Goal::PlayNotes {
+				pattern: pattern.clone(),
+			},
+			GoalDefinition::TalkTo { npc } => Goal::TalkTo { npc: npc.clone() },
+		}
+	}
+}
+
+/// Replaces every `{name}` in the text with its value.
+fn interpolate(text: &str, variables: &[(&str, String)]) -> String {
+	variables
+		.iter()
+		.fold(text.to_owned(), |text, (name, value)| {
+			text.replace(&format!("{{{name}}}"), value)
+		})
+}
+
+#[derive(Resource, Default)]
+#[resource(plugin = QuestingPlugin)]
+pub struct QuestRegistry {
+	definitions: Vec<QuestDefinition>,
+}
+
+impl QuestRegistry {
+	pub fn new(definitions: Vec<QuestDefinition>) -> Self {
+		Self { definitions }
+	}
+
+	pub fn get(&self, id: &str) -> Option<&QuestDefinition> {
+		self.definitions
+			.iter()
+			.find(|definition| definition.id == id)
+	}
+
+	pub fn available<'a, 'b: 'a>(
+		&'b self,
+		completed: &'a CompletedQuests,
+		active: &'a Quests,
+	) -> impl Iterator<Item = &'b QuestDefinition> + 'a {
+		self.definitions
+			.iter()
+			.filter(move |definition| definition.is_available(completed, active))
+	}
+}
+
+#[derive(Resource)]
+pub struct QuestDefinitionAssets {
+	pub definitions: Handle<QuestDefinitions>,
+}
+
+#[system(
+	plugin = QuestingPlugin, schedule = Startup,
+)]
+fn load_quest_definitions(mut commands: Commands, asset_server: Res<AssetServer>) {
+	commands.insert_resource(QuestDefinitionAssets {
+		definitions: asset_server.load("consorts.quests.ron"),
+	});
+}
+
+#[system(
+	plugin = QuestingPlugin, schedule = Update,
+	run_if = on_event::<AssetEvent<QuestDefinitions>>,
+)]
+fn update_quest_registry(
+	mut ev_asset: EventReader<AssetEvent<QuestDefinitions>>,
+	assets: Res<Assets<QuestDefinitions>>,
+	quest_definition_assets: Res<QuestDefinitionAssets>,
+	mut registry: ResMut<QuestRegistry>,
+) {
+	for ev in ev_asset.read() {
+		if !ev.is_loaded_with_dependencies(&quest_definition_assets.definitions)
+			&& !ev.is_modified(&quest_definition_assets.definitions)
+		{
+			continue;
+		}
+
+		let definitions = assets
+			.get(&quest_definition_assets.definitions)
+			.expect("Quest definitions should be loaded by now");
+		*registry = QuestRegistry::new(definitions.quests.clone());
+
+		for definition in registry.definitions.iter() {
+			for (name, (min, max)) in definition.variables.iter() {
+				if min > max {
+					warn!(
+						"Quest \"{}\" rolls {} between {} and {}, which is backwards",
+						definition.id, name, min, max
+					);
+				}
+			}
+			for prerequisite in definition.prerequisites.iter() {
+				if registry.get(prerequisite).is_none() {
+					warn!(
+						"Quest \"{}\" needs \"{}\" first, but there's no such quest",
+						definition.id, prerequisite
+					);
+				}
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use rand::rngs::StdRng;
+	use rand::SeedableRng;
+
+	use super::*;
+
+	fn definition(id: &str, prerequisites: &[&str], repeatable: bool) -> QuestDefinition {
+		QuestDefinition {
+			id: id.to_owned(),
+			name: "Kill {amount}".to_owned(),
+			description: "{giver} wants {amount} imps gone".to_owned(),
+			variables: BTreeMap::from([("amount".to_owned(), (3, 3))]),
+			objectives: vec![ObjectiveDefinition {
+				description: "Kill {amount} imps".to_owned(),
+				goal: GoalDefinition::Kill {
+					target: "Imp".to_owned(),
+					amount: Amount::Var("amount".to_owned()),
+				},
+			}],
+			prerequisites: prerequisites.iter().map(|id| id.to_string()).collect(),
+			rewards: Vec::new(),
+			repeatable,
+		}
+	}
+
+	#[test]
+	fn interpolate_variables() {
+		let variables = [("amount", "3".to_owned()), ("giver", "Sam".to_owned())];
+		assert_eq!(
+			interpolate("{giver} needs {amount}, {amount}!", &variables),
+			"Sam needs 3, 3!"
+		);
+	}
+
+	#[test]
+	fn interpolate_leaves_unknown_variables() {
+		assert_eq!(interpolate("{unknown}", &[]), "{unknown}");
+	}
+
+	#[test]
+	fn roll_fills_in_text_and_amounts() {
+		let quest = definition("kill", &[], false).roll(&mut StdRng::seed_from_u64(0), Some("Sam"));
+		assert_eq!(quest.name, "Kill 3");
+		assert_eq!(quest.description, "Sam wants 3 imps gone");
+		assert_eq!(quest.objectives[0].description, "Kill 3 imps");
+		assert_eq!(quest.objectives[0].goal.max_progress(), 3);
+	}
+
+	#[test]
+	fn backwards_ranges_still_roll() {
+		let mut definition = definition("kill", &[], false);
+		definition.variables.insert("amount".to_owned(), (5, 2));
+		for seed in 0..20 {
+			let quest = definition.roll(&mut StdRng::seed_from_u64(seed), None);
+			assert!((2..=5).contains(&quest.objectives[0].goal.max_progress()));
+		}
+	}
+
+	#[test]
+	fn prerequisites_gate_availability() {
+		let completed = CompletedQuests(["first".to_owned()].into_iter().collect());
+		let active = Quests::default();
+		assert!(definition("second", &["first"], false).is_available(&completed, &active));
+		assert!(!definition("third", &["second"], false).is_available(&completed, &active));
+	}
+
+	#[test]
+	fn completed_quests_only_repeat_if_repeatable() {
+		let completed = CompletedQuests(["first".to_owned()].into_iter().collect());
+		let active = Quests::default();
+		assert!(!definition("first", &[], false).is_available(&completed, &active));
+		assert!(definition("first", &[], true).is_available(&completed, &active));
+	}
+}
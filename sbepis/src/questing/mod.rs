@@ -1,33 +1,46 @@
 use std::fmt::{self, Display, Formatter};
 
 use bevy::prelude::*;
-use bevy::utils::HashMap;
+use bevy::utils::{HashMap, HashSet};
 use bevy_butler::*;
-use bevy_rapier3d::prelude::Collider;
+use bevy_common_assets::ron::RonAssetPlugin;
+use definitions::*;
+use jack_noir::plugin::Allegiances;
+use jack_noir::FactionRegistry;
+use objectives::*;
 use proposal::*;
 use rand::distributions::{Distribution, Standard};
 use rand::Rng;
 use screen::QuestProgressUpdatedSet;
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::entity::{EntityKilled, EntityKilledSet};
+use crate::entity::{EntityKilled, EntityKilledSet, GelViscosity};
 use crate::input::{InputManagerReference, MapsToEvent};
-use crate::inventory::{Inventory, InventoryChanged, InventoryChangedSet, Item};
+use crate::inventory::{
+	Inventory, InventoryChanged, InventoryChangedSet, ItemRemoved, ItemRemovedSet, ItemSpawner,
+};
 use crate::menus::*;
 use crate::npcs::imp::Imp;
-use crate::prelude::InteractedWithSet;
-use crate::{gridbox_material, some_or_return, Box};
+use crate::prelude::{InteractedWithSet, PlayerBody};
+use crate::replay::GameRng;
+use crate::{some_or_continue, some_or_return};
 
+mod definitions;
+mod objectives;
 mod proposal;
 mod quest_markers;
 mod screen;
 
+pub use objectives::{Goal, Objective, QuestItem};
+pub use proposal::QuestProposalAction;
 pub use quest_markers::SpawnQuestMarker;
 
 pub struct QuestingPlugin;
 
 #[butler_plugin(build(
 	add_plugins(InputManagerMenuPlugin::<QuestProposalAction>::default()),
+	add_plugins(RonAssetPlugin::<QuestDefinitions>::new(&["quests.ron"])),
 ))]
 impl Plugin for QuestingPlugin {
 	fn build(&self, app: &mut App) {
@@ -41,16 +54,21 @@ impl Plugin for QuestingPlugin {
 #[resource(plugin = QuestingPlugin)]
 #[register_type(plugin = QuestingPlugin)]
 pub struct Quests(pub HashMap<QuestId, Quest>);
+impl Quests {
+	pub fn objectives(&self) -> impl Iterator<Item = &Objective> {
+		self.0.values().flat_map(|quest| quest.objectives.iter())
+	}
 
-#[derive(Clone, Copy, Hash, PartialEq, Eq, Debug, Reflect)]
-#[register_type(plugin = QuestingPlugin)]
-pub struct QuestId(Uuid);
-impl QuestId {
-	#[allow(clippy::new_without_default)]
-	pub fn new() -> Self {
-		Self(Uuid::new_v4())
+	pub fn objectives_mut(&mut self) -> impl Iterator<Item = &mut Objective> {
+		self.0
+			.values_mut()
+			.flat_map(|quest| quest.objectives.iter_mut())
 	}
 }
+
+#[derive(Clone, Copy, Hash, PartialEq, Eq, Debug, Reflect, Serialize, Deserialize)]
+#[register_type(plugin = QuestingPlugin)]
+pub struct QuestId(Uuid);
 impl Display for QuestId {
 	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
 		write!(f, "{}", self.0)
@@ -84,86 +102,42 @@ impl bevy_inspector_egui::inspector_egui_impls::InspectorPrimitive for QuestId {
 	}
 }
 
-#[derive(Debug, Reflect)]
-pub enum QuestType {
-	Fetch { done: bool },
-	Kill { amount: u32, done: u32 },
-}
-impl QuestType {
-	pub fn is_completed(&self) -> bool {
-		match self {
-			QuestType::Fetch { done } => *done,
-			QuestType::Kill { amount, done } => *done >= *amount,
-		}
-	}
-
-	pub fn min_progress(&self) -> u32 {
-		match self {
-			QuestType::Fetch { .. } => 0,
-			QuestType::Kill { .. } => 0,
-		}
-	}
-
-	pub fn max_progress(&self) -> u32 {
-		match self {
-			QuestType::Fetch { .. } => 1,
-			QuestType::Kill { amount, .. } => *amount,
-		}
-	}
-
-	pub fn progress(&self) -> u32 {
-		match self {
-			QuestType::Fetch { done } => *done as u32,
-			QuestType::Kill { done, amount } => (*done).min(*amount),
-		}
-	}
-
-	pub fn progress_range(&self) -> std::ops::Range<f32> {
-		self.min_progress() as f32..self.max_progress() as f32
-	}
-}
-impl Distribution<QuestType> for Standard {
-	fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> QuestType {
-		match rng.gen_range(0..=1) {
-			0 => QuestType::Fetch { done: false },
-			_ => QuestType::Kill {
-				amount: rng.gen_range(1..=5),
-				done: 0,
-			},
-		}
+impl Distribution<QuestId> for Standard {
+	fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> QuestId {
+		QuestId(uuid::Builder::from_random_bytes(rng.gen()).into_uuid())
 	}
 }
 
-#[derive(Debug, Reflect)]
+#[derive(Clone, Debug, Reflect, Serialize, Deserialize)]
 #[register_type(plugin = QuestingPlugin)]
 pub struct Quest {
 	pub id: QuestId,
-	pub quest_type: QuestType,
+	/// The id of the [`QuestDefinition`] this was rolled from.
+	pub definition: String,
 	pub name: String,
 	pub description: String,
+	pub objectives: Vec<Objective>,
+	pub rewards: Vec<QuestReward>,
 }
-impl Distribution<Quest> for Standard {
-	fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Quest {
-		let quest_type: QuestType = rng.gen();
-		match quest_type {
-			QuestType::Kill {
-				amount, ..
-			} => Quest {
-				id: QuestId::new(),
-				name: "Awesome Kill Quest".to_string(),
-				quest_type,
-				description: format!("imps killed my grandma... pwease go take revenge on those darn imps for me... kill {amount}!!"),
-			},
-			QuestType::Fetch { .. } => Quest {
-				id: QuestId::new(),
-				name: "Awesome Fetch Quest".to_string(),
-				quest_type,
-				description: "imps stole my orange cube... pwease go get it back!!".to_string(),
-			},
-		}
+impl Quest {
+	pub fn is_completed(&self) -> bool {
+		self.objectives.iter().all(Objective::is_completed)
 	}
 }
 
+#[derive(Clone, Debug, Reflect, Serialize, Deserialize)]
+pub enum QuestReward {
+	/// Heals the player by this much gel viscosity.
+	Viscosity(f32),
+	/// Drops a [`QuestItem`] with this item id next to whoever gave the quest.
+	Item(String),
+}
+
+/// The ids of every [`QuestDefinition`] that's been completed at least once.
+#[derive(Resource, Default, Debug)]
+#[resource(plugin = QuestingPlugin)]
+pub struct CompletedQuests(pub HashSet<String>);
+
 #[derive(Component, Default, Reflect)]
 #[register_type(plugin = QuestingPlugin)]
 pub struct QuestGiver {
@@ -255,7 +229,7 @@ fn complete_quest_if_done(
 		let quest_proposal = quest_givers.get(ev.0).expect("Quest giver not found");
 		let quest_id = some_or_return!(quest_proposal.given_quest);
 		let quest = quests.0.get(&quest_id).expect("Unknown quest");
-		if !quest.quest_type.is_completed() {
+		if !quest.is_completed() {
 			return;
 		}
 		ev_completed.send(QuestCompleted(quest_id));
@@ -292,10 +266,9 @@ fn remove_quest(
 	for ev in ev_ended.read() {
 		quests.0.remove(&ev.0);
 
-		let mut quest_giver = quest_givers
+		let mut quest_giver = some_or_continue!(quest_givers
 			.iter_mut()
-			.find(|qg| qg.given_quest == Some(ev.0))
-			.expect("Quest giver missing");
+			.find(|qg| qg.given_quest == Some(ev.0)));
 		quest_giver.given_quest = None;
 	}
 }
@@ -303,19 +276,40 @@ fn remove_quest(
 #[system(
 	plugin = QuestingPlugin, schedule = Update,
 	after = EntityKilledSet,
-	in_set = QuestProgressUpdatedSet,
 )]
-fn update_killed_imps(
+fn spawn_quest_drops(
 	mut ev_killed: EventReader<EntityKilled>,
-	mut quests: ResMut<Quests>,
-	imps: Query<(), With<Imp>>,
+	mut item_spawner: ItemSpawner,
+	quests: Res<Quests>,
+	imps: Query<&Transform, With<Imp>>,
+	quest_items: Query<&QuestItem>,
+	mut rng: GameRng,
 ) {
+	let rng = rng.stream("spawn_quest_drops");
+	// Every item that's being looked for but isn't lying around yet
+	let mut wanted_items = quests
+		.objectives()
+		.filter_map(|objective| match &objective.goal {
+			Goal::Fetch { item } if !objective.is_completed() => Some(item.clone()),
+			_ => None,
+		})
+		.filter(|item| quest_items.iter().all(|quest_item| quest_item.0 != *item))
+		.collect::<Vec<_>>();
+
 	for EntityKilled(entity) in ev_killed.read() {
-		if imps.get(*entity).is_ok() {
-			for (_, quest) in quests.0.iter_mut() {
-				if let QuestType::Kill { done, .. } = &mut quest.quest_type {
-					*done += 1;
-				}
+		if wanted_items.is_empty() {
+			break;
+		}
+
+		if let Ok(transform) = imps.get(*entity) {
+			if rng.gen() {
+				continue;
+			}
+
+			let item = wanted_items.remove(0);
+			let transform = Transform::from_translation(transform.translation + Vec3::Y * 0.2);
+			if let Some(mut drop) = item_spawner.spawn(&item, transform) {
+				drop.insert(QuestItem(item));
 			}
 		}
 	}
@@ -323,61 +317,81 @@ fn update_killed_imps(
 
 #[system(
 	plugin = QuestingPlugin, schedule = Update,
-	after = InventoryChangedSet,
-	in_set = QuestProgressUpdatedSet,
-	run_if = on_event::<InventoryChanged>,
+	after = QuestCompletedSet,
+	before = remove_quest,
+	in_set = ItemRemovedSet,
+	in_set = InventoryChangedSet,
 )]
-fn update_picked_up_items(inventories: Query<&Inventory>, mut quests: ResMut<Quests>) {
-	let num_items = inventories.iter().map(|inv| inv.items.len()).sum::<usize>();
-	for (_, quest) in quests.0.iter_mut() {
-		if let QuestType::Fetch { done } = &mut quest.quest_type {
-			*done = num_items > 0;
+fn consume_quest_items(
+	mut ev_completed: EventReader<QuestCompleted>,
+	mut inventories: Query<(Entity, &mut Inventory)>,
+	quest_items: Query<&QuestItem>,
+	mut commands: Commands,
+	quests: Res<Quests>,
+	mut ev_removed: EventWriter<ItemRemoved>,
+	mut ev_inventory_changed: EventWriter<InventoryChanged>,
+) {
+	for QuestCompleted(quest_id) in ev_completed.read() {
+		let quest = quests.0.get(quest_id).expect("Unknown quest");
+		let (inventory_entity, mut inventory) = inventories.single_mut();
+
+		for objective in quest.objectives.iter() {
+			let Goal::Fetch { item } = &objective.goal else {
+				continue;
+			};
+			let item = some_or_continue!(inventory.items().into_iter().find(|entity| {
+				quest_items
+					.get(*entity)
+					.is_ok_and(|quest_item| quest_item.0 == *item)
+			}));
+			inventory.remove(item);
+			commands.entity(item).despawn_recursive();
+			ev_removed.send(ItemRemoved(item));
+			ev_inventory_changed.send(InventoryChanged(inventory_entity));
 		}
 	}
 }
 
 #[system(
 	plugin = QuestingPlugin, schedule = Update,
-	after = EntityKilledSet,
+	after = QuestCompletedSet,
+	before = remove_quest,
 )]
-fn spawn_quest_drops(
-	mut ev_killed: EventReader<EntityKilled>,
-	mut commands: Commands,
+fn record_completed_quests(
+	mut ev_completed: EventReader<QuestCompleted>,
 	quests: Res<Quests>,
-	imps: Query<&Transform, With<Imp>>,
-	items: Query<&Item>,
-	mut meshes: ResMut<Assets<Mesh>>,
-	mut materials: ResMut<Assets<StandardMaterial>>,
-	asset_server: Res<AssetServer>,
+	mut completed_quests: ResMut<CompletedQuests>,
 ) {
-	let num_fetch_quests = quests
-		.0
-		.values()
-		.filter(|quest| matches!(quest.quest_type, QuestType::Fetch { .. }))
-		.count();
-	let mut num_items = items.iter().count();
+	for QuestCompleted(quest_id) in ev_completed.read() {
+		let quest = quests.0.get(quest_id).expect("Unknown quest");
+		completed_quests.0.insert(quest.definition.clone());
+	}
+}
 
-	for EntityKilled(entity) in ev_killed.read() {
-		if num_items >= num_fetch_quests {
-			break;
-		}
+/// How much a quest giver's factions warm up to whoever finished their quest.
+const QUEST_REPUTATION: i32 = 2;
 
-		if let Ok(transform) = imps.get(*entity) {
-			if rand::random() {
-				continue;
+#[system(
+	plugin = QuestingPlugin, schedule = Update,
+	after = QuestCompletedSet,
+	before = remove_quest,
+)]
+fn improve_quest_giver_reputation(
+	mut ev_completed: EventReader<QuestCompleted>,
+	quest_givers: Query<(&QuestGiver, &Allegiances)>,
+	player: Query<&Allegiances, With<PlayerBody>>,
+	mut factions: ResMut<FactionRegistry>,
+) {
+	for QuestCompleted(quest_id) in ev_completed.read() {
+		let (_, giver) = some_or_continue!(quest_givers
+			.iter()
+			.find(|(quest_giver, _)| quest_giver.given_quest == Some(*quest_id)));
+		let player = some_or_continue!(player.get_single().ok());
+
+		for giver_faction in giver.members() {
+			for player_faction in player.members() {
+				factions.change_reputation(giver_faction, player_faction, QUEST_REPUTATION);
 			}
-
-			commands.spawn((
-				Transform::from_translation(transform.translation + Vec3::Y * 0.2),
-				Mesh3d(meshes.add(Cuboid::from_size(Vec3::splat(0.2)))),
-				MeshMaterial3d(gridbox_material("orange", &mut materials, &asset_server)),
-				Box,
-				Collider::cuboid(0.1, 0.1, 0.1),
-				Item {
-					icon: asset_server.load("item.png"),
-				},
-			));
-			num_items += 1;
 		}
 	}
 }
@@ -385,20 +399,35 @@ fn spawn_quest_drops(
 #[system(
 	plugin = QuestingPlugin, schedule = Update,
 	after = QuestCompletedSet,
+	before = remove_quest,
 )]
-fn consume_quest_drop(
+fn grant_quest_rewards(
 	mut ev_completed: EventReader<QuestCompleted>,
-	mut inventories: Query<&mut Inventory>,
-	mut commands: Commands,
 	quests: Res<Quests>,
+	quest_givers: Query<(&QuestGiver, &Transform)>,
+	mut player: Query<&mut GelViscosity, With<PlayerBody>>,
+	mut item_spawner: ItemSpawner,
 ) {
 	for QuestCompleted(quest_id) in ev_completed.read() {
 		let quest = quests.0.get(quest_id).expect("Unknown quest");
-		if let QuestType::Fetch { .. } = &quest.quest_type {
-			if quest.quest_type.is_completed() {
-				let mut inventory = inventories.single_mut();
-				let item = inventory.items.pop().expect("No item to consume");
-				commands.entity(item).despawn_recursive();
+		let giver_position = quest_givers
+			.iter()
+			.find(|(quest_giver, _)| quest_giver.given_quest == Some(*quest_id))
+			.map(|(_, transform)| transform.translation)
+			.unwrap_or_default();
+
+		for reward in quest.rewards.iter() {
+			match reward {
+				QuestReward::Viscosity(amount) => {
+					let mut viscosity = player.single_mut();
+					viscosity.value = (viscosity.value + amount).min(viscosity.max);
+				}
+				QuestReward::Item(item) => {
+					let transform = Transform::from_translation(giver_position + Vec3::Y);
+					if let Some(mut drop) = item_spawner.spawn(item, transform) {
+						drop.insert(QuestItem(item.clone()));
+					}
+				}
 			}
 		}
 	}
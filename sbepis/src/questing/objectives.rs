@@ -0,0 +1,279 @@
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+use bevy_butler::*;
+use serde::{Deserialize, Serialize};
+use soundyrust::Note;
+
+use crate::entity::{EntityKilled, EntityKilledSet};
+use crate::inventory::{
+	Inventory, InventoryChangedSet, ItemPickedUp, ItemPickedUpSet, ItemRemoved, ItemRemovedSet,
+};
+use crate::npcs::name_tags::NameTagged;
+use crate::player_commands::{NotePlayed, NotePlayedSet, PlayNoteAction};
+use crate::prelude::{InteractedWith, PlayerBody};
+use crate::questing::screen::QuestProgressUpdatedSet;
+use crate::questing::{InteractedWithQuestGiverSet, QuestGiver, QuestingPlugin, Quests};
+use crate::{some_or_continue, some_or_return};
+
+#[derive(Clone, Debug, Reflect, Serialize, Deserialize)]
+pub struct Objective {
+	pub description: String,
+	pub goal: Goal,
+	pub progress: u32,
+}
+
+impl Objective {
+	pub fn is_completed(&self) -> bool {
+		self.progress >= self.goal.max_progress()
+	}
+
+	pub fn progress(&self) -> u32 {
+		self.progress.min(self.goal.max_progress())
+	}
+
+	pub fn progress_range(&self) -> std::ops::Range<f32> {
+		0.0..self.goal.max_progress() as f32
+	}
+}
+
+#[derive(Clone, Debug, Reflect, Serialize, Deserialize)]
+pub enum Goal {
+	/// Kill entities with this [`Name`].
+	Kill {
+		target: String,
+		amount: u32,
+	},
+	/// Have a [`QuestItem`] with this item id in the inventory.
+	Fetch {
+		item: String,
+	},
+	Reach {
+		location: Vec3,
+		radius: f32,
+	},
+	/// Play these notes in a row.
+	PlayNotes {
+		pattern: Vec<PlayNoteAction>,
+	},
+	/// Interact with a quest giver going by this name.
+	TalkTo {
+		npc: String,
+	},
+}
+
+impl Goal {
+	pub fn max_progress(&self) -> u32 {
+		match self {
+			Goal::Kill { amount, .. } => *amount,
+			Goal::Fetch { .. } => 1,
+			Goal::Reach { .. } => 1,
+			Goal::PlayNotes { pattern } => pattern.len() as u32,
+			Goal::TalkTo { .. } => 1,
+		}
+	}
+}
+
+/// An item that [`Goal::Fetch`] is looking for.
+#[derive(Component, Clone, Debug)]
+pub struct QuestItem(pub String);
+
+#[system(
+	plugin = QuestingPlugin, schedule = Update,
+	after = EntityKilledSet,
+	in_set = QuestProgressUpdatedSet,
+)]
+fn update_killed_targets(
+	mut ev_killed: EventReader<EntityKilled>,
+	mut quests: ResMut<Quests>,
+	names: Query<&Name>,
+) {
+	for EntityKilled(entity) in ev_killed.read() {
+		let name = some_or_continue!(names.get(*entity).ok());
+		for objective in quests.objectives_mut() {
+			if let Goal::Kill { target, .. } = &objective.goal {
+				if target == name.as_str() {
+					objective.progress += 1;
+				}
+			}
+		}
+	}
+}
+
+/// Recounts fetched items whenever a quest item goes into or comes out of an inventory.
+#[system(
+	plugin = QuestingPlugin, schedule = Update,
+	after = InventoryChangedSet,
+	after = ItemPickedUpSet,
+	after = ItemRemovedSet,
+	in_set = QuestProgressUpdatedSet,
+)]
+fn update_fetched_items(
+	mut ev_picked_up: EventReader<ItemPickedUp>,
+	mut ev_removed: EventReader<ItemRemoved>,
+	inventories: Query<&Inventory>,
+	quest_items: Query<&QuestItem>,
+	items: Query<Option<&QuestItem>>,
+	mut quests: ResMut<Quests>,
+) {
+	let mut moved_items = HashSet::new();
+	let mut recount_all = false;
+	for item in ev_picked_up
+		.read()
+		.map(|ev| ev.0)
+		.chain(ev_removed.read().map(|ev| ev.0))
+	{
+		match items.get(item) {
+			Ok(Some(quest_item)) => {
+				moved_items.insert(quest_item.0.clone());
+			}
+			Ok(None) => {}
+			// Already despawned, so there's no telling what it was
+			Err(_) => recount_all = true,
+		}
+	}
+	if moved_items.is_empty() && !recount_all {
+		return;
+	}
+
+	let held_items = inventories
+		.iter()
+		.flat_map(|inventory| inventory.items())
+		.filter_map(|item| quest_items.get(item).ok())
+		.collect::<Vec<_>>();
+
+	for objective in quests.objectives_mut() {
+		if let Goal::Fetch { item } = &objective.goal {
+			if recount_all || moved_items.contains(item) {
+				objective.progress = held_items.iter().any(|held| held.0 == *item) as u32;
+			}
+		}
+	}
+}
+
+#[system(
+	plugin = QuestingPlugin, schedule = Update,
+	in_set = QuestProgressUpdatedSet,
+)]
+fn update_reached_locations(
+	player: Query<&GlobalTransform, With<PlayerBody>>,
+	mut quests: ResMut<Quests>,
+) {
+	let position = some_or_return!(player.get_single().ok()).translation();
+
+	let reached = |objective: &Objective| match objective.goal {
+		Goal::Reach { location, radius } => {
+			!objective.is_completed() && position.distance(location) <= radius
+		}
+		_ => false,
+	};
+
+	// Only touch the quests when something changes, so the quest screen isn't redrawn every frame
+	let any_reached = quests.objectives().any(reached);
+	if !any_reached {
+		return;
+	}
+
+	for objective in quests.objectives_mut() {
+		if reached(objective) {
+			objective.progress = 1;
+		}
+	}
+}
+
+/// How much of `pattern` has been played after `note`, when the notes before it had played
+/// `progress` of it.
+///
+/// A wrong note can still be partway into the pattern, like the third C in C C C D against C C D,
+/// so this finds the longest start of the pattern that the last few notes end with.
+fn pattern_progress(pattern: &[PlayNoteAction], progress: u32, note: Note) -> u32 {
+	// Until the pattern's done, the last `progress` notes played were its start
+	let played = &pattern[..progress as usize];
+	(1..=played.len() + 1)
+		.rev()
+		.find(|&length| {
+			let earlier = &played[played.len() + 1 - length..];
+			pattern[length - 1].note() == note
+				&& pattern[..length - 1]
+					.iter()
+					.zip(earlier)
+					.all(|(expected, actual)| expected.note() == actual.note())
+		})
+		.unwrap_or(0) as u32
+}
+
+#[system(
+	plugin = QuestingPlugin, schedule = Update,
+	after = NotePlayedSet,
+	in_set = QuestProgressUpdatedSet,
+	run_if = on_event::<NotePlayed>,
+)]
+fn update_played_notes(mut ev_note_played: EventReader<NotePlayed>, mut quests: ResMut<Quests>) {
+	for ev in ev_note_played.read() {
+		for objective in quests.objectives_mut() {
+			if objective.is_completed() {
+				continue;
+			}
+
+			if let Goal::PlayNotes { pattern } = &objective.goal {
+				objective.progress = pattern_progress(pattern, objective.progress, ev.note);
+			}
+		}
+	}
+}
+
+#[system(
+	plugin = QuestingPlugin, schedule = Update,
+	after = InteractedWithQuestGiverSet::default(),
+	in_set = QuestProgressUpdatedSet,
+)]
+fn update_talked_to_npcs(
+	mut ev_interact: EventReader<InteractedWith<QuestGiver>>,
+	names: Query<(Option<&Name>, Option<&NameTagged>)>,
+	mut quests: ResMut<Quests>,
+) {
+	for ev in ev_interact.read() {
+		let (name, name_tagged) = some_or_continue!(names.get(ev.0).ok());
+		let goes_by = |npc: &str| {
+			name.is_some_and(|name| name.as_str() == npc)
+				|| name_tagged.is_some_and(|name_tagged| name_tagged.0.name() == npc)
+		};
+
+		for objective in quests.objectives_mut() {
+			if let Goal::TalkTo { npc } = &objective.goal {
+				if goes_by(npc) {
+					objective.progress = 1;
+				}
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn play(pattern: &[PlayNoteAction], notes: &[PlayNoteAction]) -> u32 {
+		notes.iter().fold(0, |progress, note| {
+			pattern_progress(pattern, progress, note.note())
+		})
+	}
+
+	#[test]
+	fn patterns_progress_note_by_note() {
+		use PlayNoteAction::*;
+		let pattern = [C4, D4, E4];
+		assert_eq!(play(&pattern, &[C4]), 1);
+		assert_eq!(play(&pattern, &[C4, D4]), 2);
+		assert_eq!(play(&pattern, &[C4, D4, E4]), 3);
+		assert_eq!(play(&pattern, &[C4, E4]), 0);
+		assert_eq!(play(&pattern, &[C4, D4, C4]), 1);
+	}
+
+	#[test]
+	fn wrong_notes_can_start_the_pattern_over_partway() {
+		use PlayNoteAction::*;
+		assert_eq!(play(&[C4, C4, D4], &[C4, C4, C4]), 2);
+		assert_eq!(play(&[C4, C4, D4], &[C4, C4, C4, D4]), 3);
+		assert_eq!(play(&[C4, D4, C4, E4], &[C4, D4, C4, D4, C4, E4]), 4);
+	}
+}
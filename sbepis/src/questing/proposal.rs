@@ -1,15 +1,20 @@
 use bevy::prelude::*;
 use bevy_butler::*;
 use leafwing_input_manager::prelude::*;
+use rand::seq::IteratorRandom;
+use serde::{Deserialize, Serialize};
 
 use crate::dialogue::spawn_dialogue;
 use crate::input::{ActionButtonEvent, InputManagerReference};
+use crate::keybinds::Keybinds;
 use crate::menus::*;
+use crate::npcs::name_tags::NameTagged;
 use crate::player_controller::camera_controls::InteractedWith;
 use crate::questing::{
-	InteractedWithQuestGiverSet, Quest, QuestAccepted, QuestAcceptedSet, QuestDeclined,
-	QuestDeclinedSet, QuestGiver, QuestId, QuestingPlugin, Quests,
+	CompletedQuests, InteractedWithQuestGiverSet, QuestAccepted, QuestAcceptedSet, QuestDeclined,
+	QuestDeclinedSet, QuestGiver, QuestId, QuestRegistry, QuestingPlugin, Quests,
 };
+use crate::replay::GameRng;
 
 #[derive(Component)]
 pub struct QuestProposal {
@@ -124,16 +129,27 @@ fn propose_quest_if_none(
 	mut ev_interact: EventReader<InteractedWith<QuestGiver>>,
 	mut commands: Commands,
 	mut quests: ResMut<Quests>,
-	mut quest_givers: Query<&mut QuestGiver>,
+	mut quest_givers: Query<(&mut QuestGiver, Option<&NameTagged>)>,
 	mut menu_stack: ResMut<MenuStack>,
+	keybinds: Res<Keybinds>,
+	registry: Res<QuestRegistry>,
+	completed_quests: Res<CompletedQuests>,
+	mut rng: GameRng,
 ) {
+	let rng = rng.stream("propose_quest_if_none");
+
 	for ev in ev_interact.read() {
-		let mut quest_giver = quest_givers.get_mut(ev.0).expect("Quest giver missing");
+		let (mut quest_giver, name_tagged) =
+			quest_givers.get_mut(ev.0).expect("Quest giver missing");
 		if quest_giver.given_quest.is_some() {
 			return;
 		}
 
-		let quest: Quest = rand::random();
+		let Some(definition) = registry.available(&completed_quests, &quests).choose(rng) else {
+			info!("No quests left to give out");
+			return;
+		};
+		let quest = definition.roll(rng, name_tagged.map(|name_tagged| name_tagged.0.name()));
 		let quest_id = quest.id;
 		quests.0.insert(quest_id, quest);
 		let quest = quests
@@ -148,20 +164,28 @@ fn propose_quest_if_none(
 			&mut menu_stack,
 			format!("{}\n\n{}", quest.name, quest.description),
 			QuestProposal { quest_id },
-			InputMap::default()
-				.with(QuestProposalAction::Accept, KeyCode::KeyE)
-				.with(QuestProposalAction::Decline, KeyCode::Space),
+			keybinds.quest_proposal.input_map(),
 		);
 		dialogue.add_option(
 			&mut commands,
-			"Accept [E]".to_owned(),
+			format!(
+				"Accept [{}]",
+				keybinds
+					.quest_proposal
+					.describe(QuestProposalAction::Accept)
+			),
 			QuestProposalAccept {
 				quest_proposal: dialogue.root,
 			},
 		);
 		dialogue.add_option(
 			&mut commands,
-			"Decline [Space]".to_owned(),
+			format!(
+				"Decline [{}]",
+				keybinds
+					.quest_proposal
+					.describe(QuestProposalAction::Decline)
+			),
 			QuestProposalDecline {
 				quest_proposal: dialogue.root,
 			},
@@ -169,7 +193,7 @@ fn propose_quest_if_none(
 	}
 }
 
-#[derive(Clone, Copy, Eq, PartialEq, Hash, Reflect, Debug)]
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Reflect, Debug, Serialize, Deserialize)]
 pub enum QuestProposalAction {
 	Accept,
 	Decline,
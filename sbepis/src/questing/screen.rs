@@ -2,14 +2,14 @@ use bevy::color::palettes::css;
 use bevy::prelude::*;
 use bevy::utils::hashbrown::HashSet;
 use bevy_butler::*;
-use leafwing_input_manager::prelude::InputMap;
 
 use crate::camera::PlayerCameraNode;
-use crate::input::input_manager_bundle;
+use crate::keybinds::{keybound_input_manager, Keybinds};
 use crate::menus::*;
 use crate::player_controller::PlayerAction;
 use crate::questing::{
-	QuestAccepted, QuestAcceptedSet, QuestEnded, QuestEndedSet, QuestId, QuestingPlugin, Quests,
+	Objective, QuestAccepted, QuestAcceptedSet, QuestEnded, QuestEndedSet, QuestId, QuestingPlugin,
+	Quests,
 };
 use crate::util::MapRange;
 
@@ -26,6 +26,11 @@ pub struct QuestScreenNodeDisplay(Option<Entity>);
 pub struct QuestScreenNode {
 	pub quest_id: QuestId,
 	pub display: Entity,
+	/// One for each of the quest's objectives, in the same order.
+	pub objectives: Vec<ObjectiveProgressNode>,
+}
+
+pub struct ObjectiveProgressNode {
 	pub progress_text: Entity,
 	pub progress_bar: Entity,
 }
@@ -49,7 +54,7 @@ use crate::menus::show_menu_on_action;
 #[system(
 	plugin = QuestingPlugin, schedule = Startup,
 )]
-fn spawn_quest_screen(mut commands: Commands) {
+fn spawn_quest_screen(mut commands: Commands, keybinds: Res<Keybinds>) {
 	commands
 		.spawn((
 			Node {
@@ -59,10 +64,7 @@ fn spawn_quest_screen(mut commands: Commands) {
 			},
 			BackgroundColor(bevy::color::palettes::css::GRAY.with_alpha(0.5).into()),
 			Visibility::Hidden,
-			input_manager_bundle(
-				InputMap::default().with(CloseMenuAction, KeyCode::KeyJ),
-				false,
-			),
+			keybound_input_manager(&keybinds, |keybinds| &keybinds.close_quest_screen, false),
 			PlayerCameraNode,
 			Menu,
 			MenuWithMouse,
@@ -115,13 +117,13 @@ fn add_quest_nodes(
 		let quest_id = ev.quest_id;
 		let quest = quests.0.get(&quest_id).expect("Unknown quest");
 
-		let mut progress_text: Option<Entity> = None;
-		let mut progress_bar: Option<Entity> = None;
+		let mut objectives = Vec::new();
 
 		let display = commands
 			.spawn(Node {
 				display: bevy::ui::Display::None,
 				flex_direction: FlexDirection::Column,
+				row_gap: Val::Px(10.0),
 				..default()
 			})
 			.with_children(|parent| {
@@ -133,45 +135,45 @@ fn add_quest_nodes(
 						..default()
 					},
 				));
-				progress_text = Some(
-					parent
+
+				for objective in quest.objectives.iter() {
+					let progress_text = parent
 						.spawn((
-							Text(format!(
-								"{}/{}",
-								quest.quest_type.progress(),
-								quest.quest_type.max_progress()
-							)),
+							Text(objective_progress_text(objective)),
 							TextColor(Color::WHITE),
 							TextFont {
 								font_size: 20.0,
 								..default()
 							},
 						))
-						.id(),
-				);
-				parent
-					.spawn((
-						Node {
-							height: Val::Px(30.0),
-							width: Val::Percent(100.0),
-							..default()
-						},
-						BackgroundColor(css::DARK_GRAY.into()),
-					))
-					.with_children(|parent| {
-						progress_bar = Some(
-							parent
+						.id();
+					let mut progress_bar = Entity::PLACEHOLDER;
+					parent
+						.spawn((
+							Node {
+								height: Val::Px(30.0),
+								width: Val::Percent(100.0),
+								..default()
+							},
+							BackgroundColor(css::DARK_GRAY.into()),
+						))
+						.with_children(|parent| {
+							progress_bar = parent
 								.spawn((
 									Node {
-										width: Val::Percent(0.0),
+										width: Val::Percent(objective_progress_percent(objective)),
 										height: Val::Percent(100.0),
 										..default()
 									},
 									BackgroundColor(css::LIGHT_GRAY.into()),
 								))
-								.id(),
-						);
+								.id();
+						});
+					objectives.push(ObjectiveProgressNode {
+						progress_text,
+						progress_bar,
 					});
+				}
 			})
 			.set_parent(quest_screen_node_display)
 			.id();
@@ -188,8 +190,7 @@ fn add_quest_nodes(
 				QuestScreenNode {
 					quest_id,
 					display,
-					progress_text: progress_text.unwrap(),
-					progress_bar: progress_bar.unwrap(),
+					objectives,
 				},
 			))
 			.set_parent(quest_screen_node_list)
@@ -273,19 +274,34 @@ fn update_quest_node_progress(
 
 	for quest_node in quest_nodes.iter_mut() {
 		let quest = quests.0.get(&quest_node.quest_id).expect("Unknown quest");
-		let mut progress_text = progress_texts.get_mut(quest_node.progress_text).unwrap();
-		let mut progress_bar = progress_bars.get_mut(quest_node.progress_bar).unwrap();
 
-		progress_text.0 = format!(
-			"{}/{}",
-			quest.quest_type.progress(),
-			quest.quest_type.max_progress()
-		);
-		progress_bar.width = Val::Percent(
-			(quest.quest_type.progress() as f32)
-				.map_range(quest.quest_type.progress_range(), 0.0..100.0),
-		);
+		for (objective, objective_node) in quest.objectives.iter().zip(&quest_node.objectives) {
+			let mut progress_text = progress_texts
+				.get_mut(objective_node.progress_text)
+				.unwrap();
+			let mut progress_bar = progress_bars.get_mut(objective_node.progress_bar).unwrap();
+
+			progress_text.0 = objective_progress_text(objective);
+			progress_bar.width = Val::Percent(objective_progress_percent(objective));
+		}
+	}
+}
+
+fn objective_progress_text(objective: &Objective) -> String {
+	format!(
+		"{} {}/{}",
+		objective.description,
+		objective.progress(),
+		objective.goal.max_progress()
+	)
+}
+
+fn objective_progress_percent(objective: &Objective) -> f32 {
+	// Also covers objectives with nothing to do, which would otherwise divide by zero
+	if objective.is_completed() {
+		return 100.0;
 	}
+	(objective.progress() as f32).map_range(objective.progress_range(), 0.0..100.0)
 }
 
 #[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
@@ -0,0 +1,469 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use bevy::core::FrameCount;
+use bevy::prelude::*;
+use bevy::time::{TimeSystem, TimeUpdateStrategy};
+use bevy::utils::HashMap;
+use bevy_butler::*;
+use leafwing_input_manager::plugin::InputManagerSystem;
+use leafwing_input_manager::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::entity::{EntityKilled, EntityKilledSet};
+use crate::fray::TrackSwitcherAction;
+use crate::keybinds::{Keybinds, Keybound};
+use crate::menus::{CloseMenuAction, MenuNavigationAction};
+use crate::player_commands::{CloseStaffAction, PlayNoteAction, RadialNoteAction};
+use crate::player_controller::PlayerAction;
+use crate::questing::{QuestCompleted, QuestCompletedSet, QuestId, QuestProposalAction};
+use crate::save::SaveAction;
+use crate::util::arg_value;
+use crate::{ok_or_continue, some_or_continue, some_or_return};
+
+mod rng;
+
+pub use self::rng::{GameRng, RngSeed};
+
+pub struct ReplayPlugin;
+
+#[butler_plugin(build(
+	add_plugins(ReplayActionPlugin::<PlayerAction>::default()),
+	add_plugins(ReplayActionPlugin::<PlayNoteAction>::default()),
+	add_plugins(ReplayActionPlugin::<RadialNoteAction>::default()),
+	add_plugins(ReplayActionPlugin::<CloseStaffAction>::default()),
+	add_plugins(ReplayActionPlugin::<CloseMenuAction>::default()),
+	add_plugins(ReplayActionPlugin::<MenuNavigationAction>::default()),
+	add_plugins(ReplayActionPlugin::<QuestProposalAction>::default()),
+	add_plugins(ReplayActionPlugin::<TrackSwitcherAction>::default()),
+	add_plugins(ReplayActionPlugin::<SaveAction>::default()),
+))]
+impl Plugin for ReplayPlugin {
+	fn build(&self, app: &mut App) {
+		if let Some(path) = arg_value("--replay") {
+			match Replay::read(&path) {
+				Ok(replay) => {
+					info!("Replaying {path} with seed {}", replay.seed);
+					app.insert_resource(RngSeed(replay.seed))
+						.insert_resource(ReplayPlayback::new(replay))
+						.init_resource::<EventLog>();
+					return;
+				}
+				Err(err) => error!("Couldn't read replay {path}: {err}"),
+			}
+		}
+
+		let seed = arg_value("--seed")
+			.and_then(|seed| seed.parse().ok())
+			.unwrap_or_else(rand::random);
+		app.insert_resource(RngSeed(seed));
+
+		if let Some(path) = arg_value("--record") {
+			info!("Recording to {path} with seed {seed}");
+			app.insert_resource(ReplayRecording {
+				path: PathBuf::from(path),
+				replay: Replay { seed, ..default() },
+			})
+			.init_resource::<EventLog>();
+		}
+	}
+}
+
+/// Records and replays one kind of action for every input manager that has it.
+pub struct ReplayActionPlugin<Action: Actionlike>(std::marker::PhantomData<Action>);
+impl<Action: Actionlike + Serialize + DeserializeOwned> Plugin for ReplayActionPlugin<Action> {
+	fn build(&self, app: &mut App) {
+		app.add_systems(
+			PreUpdate,
+			(
+				strip_input_maps::<Action>
+					.before(InputManagerSystem::Update)
+					.run_if(resource_exists::<ReplayPlayback>),
+				record_actions::<Action>
+					.after(InputManagerSystem::ManualControl)
+					.run_if(resource_exists::<ReplayRecording>),
+				replay_actions::<Action>
+					.after(InputManagerSystem::ManualControl)
+					.run_if(resource_exists::<ReplayPlayback>),
+			),
+		);
+	}
+}
+impl<Action: Actionlike> Default for ReplayActionPlugin<Action> {
+	fn default() -> Self {
+		Self(default())
+	}
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct Replay {
+	pub seed: u64,
+	pub inputs: Vec<RecordedInput>,
+	/// How long each frame took, by frame number, so a replay runs at the same speed whether or
+	/// not the recording was headless.
+	pub frame_times: Vec<Duration>,
+	/// What happened during the recording, for checking a replay against.
+	pub events: Vec<LoggedEvent>,
+}
+
+impl Replay {
+	pub fn read(path: impl AsRef<Path>) -> Result<Self, String> {
+		let file = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+		ron::from_str(&file).map_err(|err| err.to_string())
+	}
+
+	pub fn write(&self, path: impl AsRef<Path>) -> Result<(), String> {
+		let file = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+			.map_err(|err| err.to_string())?;
+		std::fs::write(path, file).map_err(|err| err.to_string())
+	}
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RecordedInput {
+	pub frame: u32,
+	/// Which kind of action this is, like `PlayerAction`.
+	pub actions: String,
+	/// The keybinds or [`Name`] of the input manager, if it has either. Entities don't spawn in
+	/// the same order every run, so they can't be told apart by that alone.
+	pub input_manager: Option<String>,
+	/// Which of the input managers going by that, ordered by entity, for when there's more than
+	/// one.
+	pub index: usize,
+	/// The action itself, as RON.
+	pub action: String,
+	pub change: ActionChange,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum ActionChange {
+	Pressed,
+	Released,
+	Value(f32),
+	AxisPair(Vec2),
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub enum LoggedEvent {
+	EntityKilled { frame: u32, name: Option<String> },
+	QuestCompleted { frame: u32, quest: QuestId },
+}
+
+#[derive(Resource)]
+pub struct ReplayRecording {
+	pub path: PathBuf,
+	pub replay: Replay,
+}
+
+#[derive(Resource)]
+pub struct ReplayPlayback {
+	inputs: HashMap<u32, Vec<RecordedInput>>,
+	frame_times: Vec<Duration>,
+	expected_events: Vec<LoggedEvent>,
+}
+
+impl ReplayPlayback {
+	pub fn new(replay: Replay) -> Self {
+		let mut inputs = HashMap::<u32, Vec<RecordedInput>>::new();
+		for input in replay.inputs {
+			inputs.entry(input.frame).or_default().push(input);
+		}
+		Self {
+			inputs,
+			frame_times: replay.frame_times,
+			expected_events: replay.events,
+		}
+	}
+}
+
+/// Gameplay events that a replay should reproduce exactly.
+#[derive(Resource, Default)]
+pub struct EventLog(pub Vec<LoggedEvent>);
+
+fn action_change<Action: Actionlike>(
+	action_state: &ActionState<Action>,
+	action: &Action,
+) -> Option<ActionChange> {
+	match action.input_control_kind() {
+		InputControlKind::Button => Some(if action_state.pressed(action) {
+			ActionChange::Pressed
+		} else {
+			ActionChange::Released
+		}),
+		InputControlKind::Axis => Some(ActionChange::Value(action_state.value(action))),
+		InputControlKind::DualAxis => Some(ActionChange::AxisPair(action_state.axis_pair(action))),
+		InputControlKind::TripleAxis => None,
+	}
+}
+
+fn default_change<Action: Actionlike>(action: &Action) -> Option<ActionChange> {
+	match action.input_control_kind() {
+		InputControlKind::Button => Some(ActionChange::Released),
+		InputControlKind::Axis => Some(ActionChange::Value(0.0)),
+		InputControlKind::DualAxis => Some(ActionChange::AxisPair(Vec2::ZERO)),
+		InputControlKind::TripleAxis => None,
+	}
+}
+
+/// Sorts input managers by what they're recorded as, and numbers the ones that share a name.
+fn name_input_managers<T>(
+	input_managers: impl IntoIterator<Item = (Entity, Option<String>, T)>,
+) -> Vec<(Option<String>, usize, Entity, T)> {
+	let mut input_managers = input_managers.into_iter().collect::<Vec<_>>();
+	input_managers.sort_by(|(a, a_name, _), (b, b_name, _)| a_name.cmp(b_name).then(a.cmp(b)));
+
+	let mut named = Vec::<(Option<String>, usize, Entity, T)>::new();
+	for (entity, name, item) in input_managers {
+		let index = match named.last() {
+			Some((last_name, last_index, ..)) if *last_name == name => last_index + 1,
+			_ => 0,
+		};
+		named.push((name, index, entity, item));
+	}
+	named
+}
+
+fn input_manager_name<Action: Actionlike>(
+	keybound: Option<&Keybound<Action>>,
+	name: Option<&Name>,
+	keybinds: &Keybinds,
+) -> Option<String> {
+	keybound
+		.and_then(|keybound| keybound.context(keybinds))
+		.map(|context| context.name().to_owned())
+		.or_else(|| name.map(|name| name.to_string()))
+}
+
+fn record_actions<Action: Actionlike + Serialize>(
+	input_managers: Query<(
+		Entity,
+		&ActionState<Action>,
+		Option<&Keybound<Action>>,
+		Option<&Name>,
+	)>,
+	keybinds: Res<Keybinds>,
+	mut previous: Local<HashMap<Entity, HashMap<Action, ActionChange>>>,
+	mut recording: ResMut<ReplayRecording>,
+	frame: Res<FrameCount>,
+) {
+	let input_managers = name_input_managers(input_managers.iter().map(
+		|(entity, action_state, keybound, name)| {
+			(
+				entity,
+				input_manager_name(keybound, name, &keybinds),
+				action_state,
+			)
+		},
+	));
+
+	previous.retain(|entity, _| {
+		input_managers
+			.iter()
+			.any(|(_, _, other, _)| entity == other)
+	});
+
+	for (name, index, entity, action_state) in input_managers {
+		let previous = previous.entry(entity).or_default();
+
+		for action in action_state.keys() {
+			let change = some_or_continue!(action_change(action_state, &action));
+			let previous_change = previous.get(&action).copied().or(default_change(&action));
+			if previous_change == Some(change) {
+				continue;
+			}
+
+			let serialized_action = match ron::to_string(&action) {
+				Ok(serialized_action) => serialized_action,
+				Err(err) => {
+					error!("Couldn't record {action:?}: {err}");
+					continue;
+				}
+			};
+			recording.replay.inputs.push(RecordedInput {
+				frame: frame.0,
+				actions: Action::short_type_path().to_owned(),
+				input_manager: name.clone(),
+				index,
+				action: serialized_action,
+				change,
+			});
+			previous.insert(action, change);
+		}
+	}
+}
+
+/// Without an input map, leafwing leaves the action state alone for the replay to drive.
+fn strip_input_maps<Action: Actionlike>(
+	mut commands: Commands,
+	input_maps: Query<Entity, With<InputMap<Action>>>,
+) {
+	for entity in input_maps.iter() {
+		commands.entity(entity).remove::<InputMap<Action>>();
+	}
+}
+
+fn replay_actions<Action: Actionlike + DeserializeOwned>(
+	mut input_managers: Query<(
+		Entity,
+		&mut ActionState<Action>,
+		Option<&Keybound<Action>>,
+		Option<&Name>,
+	)>,
+	keybinds: Res<Keybinds>,
+	playback: Res<ReplayPlayback>,
+	frame: Res<FrameCount>,
+) {
+	let inputs = some_or_return!(playback.inputs.get(&frame.0));
+
+	let mut input_managers = name_input_managers(input_managers.iter_mut().map(
+		|(entity, action_state, keybound, name)| {
+			(
+				entity,
+				input_manager_name(keybound, name, &keybinds),
+				action_state,
+			)
+		},
+	));
+
+	for input in inputs
+		.iter()
+		.filter(|input| input.actions == Action::short_type_path())
+	{
+		let Some((.., action_state)) = input_managers
+			.iter_mut()
+			.find(|(name, index, ..)| *name == input.input_manager && *index == input.index)
+		else {
+			warn!(
+				"Replay has input for {} {:?} #{} on frame {}, but it doesn't exist",
+				input.actions, input.input_manager, input.index, input.frame
+			);
+			continue;
+		};
+		let action: Action = ok_or_continue!(ron::from_str(&input.action));
+
+		match input.change {
+			ActionChange::Pressed => action_state.press(&action),
+			ActionChange::Released => action_state.release(&action),
+			ActionChange::Value(value) => action_state.set_value(&action, value),
+			ActionChange::AxisPair(pair) => action_state.set_axis_pair(&action, pair),
+		}
+	}
+}
+
+#[system(
+	plugin = ReplayPlugin, schedule = First,
+	after = TimeSystem,
+	run_if = resource_exists::<ReplayRecording>,
+)]
+fn record_frame_times(
+	time: Res<Time<Real>>,
+	frame: Res<FrameCount>,
+	mut recording: ResMut<ReplayRecording>,
+) {
+	let frame_times = &mut recording.replay.frame_times;
+	frame_times.resize(frame.0 as usize, Duration::ZERO);
+	frame_times.push(time.delta());
+}
+
+/// Windowed recordings go as fast as the machine did, so replays step the clock the same way.
+#[system(
+	plugin = ReplayPlugin, schedule = First,
+	before = TimeSystem,
+	run_if = resource_exists::<ReplayPlayback>,
+)]
+fn replay_frame_times(
+	playback: Res<ReplayPlayback>,
+	frame: Res<FrameCount>,
+	mut time_update_strategy: ResMut<TimeUpdateStrategy>,
+) {
+	let frame_time = some_or_return!(playback.frame_times.get(frame.0 as usize));
+	*time_update_strategy = TimeUpdateStrategy::ManualDuration(*frame_time);
+}
+
+#[system(
+	plugin = ReplayPlugin, schedule = Update,
+	after = EntityKilledSet,
+	run_if = resource_exists::<EventLog>,
+)]
+fn log_killed_entities(
+	mut ev_killed: EventReader<EntityKilled>,
+	names: Query<&Name>,
+	mut log: ResMut<EventLog>,
+	frame: Res<FrameCount>,
+) {
+	for EntityKilled(entity) in ev_killed.read() {
+		log.0.push(LoggedEvent::EntityKilled {
+			frame: frame.0,
+			name: names.get(*entity).ok().map(|name| name.to_string()),
+		});
+	}
+}
+
+#[system(
+	plugin = ReplayPlugin, schedule = Update,
+	after = QuestCompletedSet,
+	run_if = resource_exists::<EventLog>,
+)]
+fn log_completed_quests(
+	mut ev_completed: EventReader<QuestCompleted>,
+	mut log: ResMut<EventLog>,
+	frame: Res<FrameCount>,
+) {
+	for QuestCompleted(quest) in ev_completed.read() {
+		log.0.push(LoggedEvent::QuestCompleted {
+			frame: frame.0,
+			quest: *quest,
+		});
+	}
+}
+
+#[system(
+	plugin = ReplayPlugin, schedule = Last,
+	run_if = resource_exists::<EventLog>,
+)]
+fn finish_replay(
+	mut ev_exit: ResMut<Events<AppExit>>,
+	mut log: ResMut<EventLog>,
+	recording: Option<ResMut<ReplayRecording>>,
+	playback: Option<Res<ReplayPlayback>>,
+) {
+	if ev_exit.is_empty() {
+		return;
+	}
+
+	if let Some(mut recording) = recording {
+		recording.replay.events = std::mem::take(&mut log.0);
+		match recording.replay.write(&recording.path) {
+			Ok(()) => info!("Saved replay to {}", recording.path.display()),
+			Err(err) => error!(
+				"Couldn't save replay to {}: {err}",
+				recording.path.display()
+			),
+		}
+	}
+
+	if let Some(playback) = playback {
+		let divergence = playback
+			.expected_events
+			.iter()
+			.zip(log.0.iter())
+			.position(|(expected, actual)| expected != actual);
+		if let Some(index) = divergence {
+			error!(
+				"Replay diverged: expected {:?} but got {:?}",
+				playback.expected_events[index], log.0[index]
+			);
+			ev_exit.send(AppExit::error());
+		} else if playback.expected_events.len() != log.0.len() {
+			error!(
+				"Replay diverged: expected {} events but got {}",
+				playback.expected_events.len(),
+				log.0.len()
+			);
+			ev_exit.send(AppExit::error());
+		} else {
+			info!("Replay matched all {} events", log.0.len());
+		}
+	}
+}
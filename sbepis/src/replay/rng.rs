@@ -0,0 +1,84 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+/// What every [`GameRng`] is seeded from, so a run can be reproduced by reusing it.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct RngSeed(pub u64);
+
+/// Deterministic randomness for gameplay systems.
+///
+/// Each system keeps its own generator for every stream it asks for, seeded from [`RngSeed`] and
+/// the name of the stream, so the order that systems happen to run in doesn't change what any of
+/// them roll.
+#[derive(SystemParam)]
+pub struct GameRng<'w, 's> {
+	seed: Res<'w, RngSeed>,
+	streams: Local<'s, (u64, HashMap<String, StdRng>)>,
+}
+
+impl GameRng<'_, '_> {
+	pub fn stream(&mut self, name: &str) -> &mut StdRng {
+		let seed = self.seed.0;
+		let (streams_seed, streams) = &mut *self.streams;
+		// A new seed means a new run, so every stream starts over
+		if *streams_seed != seed {
+			*streams_seed = seed;
+			streams.clear();
+		}
+		streams
+			.entry(name.to_owned())
+			.or_insert_with(|| StdRng::seed_from_u64(seed ^ stream_hash(name)))
+	}
+}
+
+/// FNV-1a, since it needs to come out the same on every machine and every build.
+fn stream_hash(name: &str) -> u64 {
+	name.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+		(hash ^ byte as u64).wrapping_mul(0x100000001b3)
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use bevy::ecs::system::SystemState;
+	use rand::Rng;
+
+	use super::*;
+
+	fn roll(state: &mut SystemState<GameRng>, world: &mut World, stream: &str) -> u64 {
+		state.get_mut(world).stream(stream).gen()
+	}
+
+	#[test]
+	fn streams_are_kept_apart() {
+		let mut world = World::new();
+		world.insert_resource(RngSeed(7));
+		let mut both = SystemState::<GameRng>::new(&mut world);
+		let mut only_b = SystemState::<GameRng>::new(&mut world);
+
+		let first_a = roll(&mut both, &mut world, "a");
+		let first_b = roll(&mut both, &mut world, "b");
+		assert_ne!(first_a, first_b);
+		// Rolling on "a" first doesn't change what "b" comes up with
+		assert_eq!(first_b, roll(&mut only_b, &mut world, "b"));
+
+		let second_a = roll(&mut both, &mut world, "a");
+		assert_ne!(first_a, second_a);
+	}
+
+	#[test]
+	fn new_seeds_restart_streams() {
+		let mut world = World::new();
+		world.insert_resource(RngSeed(7));
+		let mut state = SystemState::<GameRng>::new(&mut world);
+		let first = roll(&mut state, &mut world, "a");
+
+		world.insert_resource(RngSeed(8));
+		roll(&mut state, &mut world, "a");
+		world.insert_resource(RngSeed(7));
+		assert_eq!(first, roll(&mut state, &mut world, "a"));
+	}
+}
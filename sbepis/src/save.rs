@@ -0,0 +1,763 @@
+use std::fmt::{self, Display};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_butler::*;
+use bevy_rapier3d::prelude::*;
+use jack_noir::FactionRegistry;
+use leafwing_input_manager::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::camera::PlayerCamera;
+use crate::entity::spawner::{EntitySpawnedSet, Spawner, SpawnerActivated, SpawnerActivatedSet};
+use crate::entity::GelViscosity;
+use crate::fray::{FrayTracks, Track};
+use crate::grist::GristCache;
+use crate::inventory::{
+	EquippedItem, Inventory, InventoryChanged, InventoryChangedSet, Item, ItemPickedUp,
+	ItemPickedUpSet, ItemRegistry, ItemRemoved, ItemRemovedSet, ItemSpawner,
+};
+use crate::keybinds::{keybound_input_manager, Keybinds};
+use crate::main_bundles::Mob;
+use crate::menus::InputManagerMenuPlugin;
+use crate::npcs::name_tags::{NameTag, NameTagged};
+use crate::player_controller::camera_controls::Pitch;
+use crate::prelude::PlayerBody;
+use crate::questing::{
+	CompletedQuests, Quest, QuestAccepted, QuestEnded, QuestEndedSet, QuestGiver, QuestId,
+	QuestItem, Quests,
+};
+use crate::some_or_continue;
+
+const SAVES_DIRECTORY: &str = "saves";
+/// Bump this whenever [`SaveData`] changes shape, and teach [`migrate`] how to upgrade the old one.
+pub const SAVE_VERSION: u32 = 1;
+pub const SAVE_SLOTS: u32 = 3;
+const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+#[butler_plugin(build(
+	add_plugins(InputManagerMenuPlugin::<SaveAction>::default()),
+))]
+pub struct SavePlugin;
+
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Reflect, Debug, Serialize, Deserialize)]
+pub enum SaveAction {
+	QuickSave,
+	QuickLoad,
+	NextSlot,
+	PreviousSlot,
+}
+impl Actionlike for SaveAction {
+	fn input_control_kind(&self) -> InputControlKind {
+		match self {
+			SaveAction::QuickSave => InputControlKind::Button,
+			SaveAction::QuickLoad => InputControlKind::Button,
+			SaveAction::NextSlot => InputControlKind::Button,
+			SaveAction::PreviousSlot => InputControlKind::Button,
+		}
+	}
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SaveSlot {
+	Autosave,
+	Manual(u32),
+}
+
+impl SaveSlot {
+	pub fn path(&self) -> PathBuf {
+		let file = match self {
+			SaveSlot::Autosave => "autosave.ron".to_owned(),
+			SaveSlot::Manual(slot) => format!("slot {slot}.ron"),
+		};
+		PathBuf::from(SAVES_DIRECTORY).join(file)
+	}
+}
+
+impl Display for SaveSlot {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			SaveSlot::Autosave => write!(f, "autosave"),
+			SaveSlot::Manual(slot) => write!(f, "slot {slot}"),
+		}
+	}
+}
+
+/// The manual slot that quick saving and loading use.
+#[derive(Resource)]
+#[resource(plugin = SavePlugin, init = SelectedSaveSlot(1))]
+pub struct SelectedSaveSlot(pub u32);
+
+#[derive(Resource)]
+#[resource(plugin = SavePlugin, init = Autosave(Timer::new(AUTOSAVE_INTERVAL, TimerMode::Repeating)))]
+pub struct Autosave(pub Timer);
+
+#[derive(Event)]
+#[event(plugin = SavePlugin)]
+pub struct SaveGame(pub SaveSlot);
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SaveGameSet;
+
+#[derive(Event)]
+#[event(plugin = SavePlugin)]
+pub struct LoadGame(pub SaveSlot);
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LoadGameSet;
+
+#[derive(Serialize, Deserialize)]
+struct SaveFile {
+	version: u32,
+	data: SaveData,
+}
+
+/// Only [`SaveFile::version`], so the rest can be parsed as whatever version the file says it is.
+#[derive(Deserialize)]
+struct SaveHeader {
+	version: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SaveData {
+	pub player: SavedPlayer,
+	pub player_track: Track,
+	pub quests: Vec<Quest>,
+	pub completed_quests: Vec<String>,
+	pub items: Vec<SavedItem>,
+	pub spawners: Vec<SavedSpawner>,
+	pub reputations: Vec<SavedReputation>,
+	pub grist: GristCache,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SavedPlayer {
+	pub transform: Transform,
+	pub pitch: f32,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SavedItem {
+	pub id: String,
+	pub quest_item: Option<String>,
+	/// Where it's lying in the world, or `None` if it's in the player's inventory.
+	pub transform: Option<Transform>,
+}
+
+/// Spawners come from the level blueprint, so they're matched back up by name.
+#[derive(Serialize, Deserialize)]
+pub struct SavedSpawner {
+	pub name: String,
+	pub spawn_timer: Duration,
+	pub entities: Vec<SavedEntity>,
+}
+
+/// How much one faction has come to like or dislike another. Faction ids depend on the order
+/// they're registered in, so they're matched back up by name.
+#[derive(Serialize, Deserialize)]
+pub struct SavedReputation {
+	pub from: String,
+	pub to: String,
+	pub reputation: i32,
+}
+
+#[derive(Serialize, Deserialize, Component, Clone)]
+pub struct SavedEntity {
+	pub transform: Transform,
+	pub viscosity: f32,
+	pub name_tag: Option<NameTag>,
+	pub given_quest: Option<QuestId>,
+}
+
+#[derive(Debug)]
+pub enum SaveError {
+	Io(std::io::Error),
+	Parse(ron::error::SpannedError),
+	Serialize(ron::Error),
+	UnknownVersion(u32),
+}
+
+impl Display for SaveError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			SaveError::Io(err) => write!(f, "{err}"),
+			SaveError::Parse(err) => write!(f, "{err}"),
+			SaveError::Serialize(err) => write!(f, "{err}"),
+			SaveError::UnknownVersion(version) => write!(
+				f,
+				"save is version {version}, but this build only knows up to {SAVE_VERSION}"
+			),
+		}
+	}
+}
+
+impl SaveData {
+	pub fn write(self, slot: SaveSlot) -> Result<(), SaveError> {
+		let save = SaveFile {
+			version: SAVE_VERSION,
+			data: self,
+		};
+		let file = ron::ser::to_string_pretty(&save, ron::ser::PrettyConfig::default())
+			.map_err(SaveError::Serialize)?;
+		std::fs::create_dir_all(SAVES_DIRECTORY).map_err(SaveError::Io)?;
+		std::fs::write(slot.path(), file).map_err(SaveError::Io)
+	}
+
+	pub fn read(slot: SaveSlot) -> Result<Self, SaveError> {
+		let file = std::fs::read_to_string(slot.path()).map_err(SaveError::Io)?;
+		Self::parse(&file)
+	}
+
+	pub fn parse(file: &str) -> Result<Self, SaveError> {
+		let header: SaveHeader = ron::from_str(file).map_err(SaveError::Parse)?;
+		migrate(header.version, file)
+	}
+}
+
+/// Parses a save written by any version up to [`SAVE_VERSION`].
+///
+/// When the format changes, the old [`SaveData`] gets kept around in a `v1` module and so on,
+/// and its arm here parses that and converts it forward one version at a time.
+fn migrate(version: u32, file: &str) -> Result<SaveData, SaveError> {
+	match version {
+		SAVE_VERSION => {
+			let save: SaveFile = ron::from_str(file).map_err(SaveError::Parse)?;
+			Ok(save.data)
+		}
+		_ => Err(SaveError::UnknownVersion(version)),
+	}
+}
+
+/// A save that's waiting to be applied on top of the level.
+#[derive(Resource)]
+pub struct PendingLoad {
+	data: SaveData,
+	stage: LoadStage,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum LoadStage {
+	/// The level blueprint hasn't made its spawners yet, or the item definitions haven't loaded.
+	WaitingForLevel,
+	/// Quests need to finish ending before their givers can be despawned.
+	EndingQuests,
+	Restoring,
+}
+
+fn load_stage(stage: LoadStage) -> impl Fn(Option<Res<PendingLoad>>) -> bool {
+	move |pending: Option<Res<PendingLoad>>| pending.is_some_and(|pending| pending.stage == stage)
+}
+
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RestoreSaveSet;
+
+#[system(
+	plugin = SavePlugin, schedule = Startup,
+)]
+fn spawn_save_input(mut commands: Commands, keybinds: Res<Keybinds>) {
+	// Not a menu, so saving works from anywhere
+	commands.spawn((
+		Name::new("Save Input"),
+		keybound_input_manager(&keybinds, |keybinds| &keybinds.saves, true),
+	));
+}
+
+#[system(
+	plugin = SavePlugin, schedule = Update,
+	in_set = SaveGameSet,
+	in_set = LoadGameSet,
+)]
+fn quick_save_and_load(
+	input: Query<&ActionState<SaveAction>>,
+	mut selected_slot: ResMut<SelectedSaveSlot>,
+	mut ev_save: EventWriter<SaveGame>,
+	mut ev_load: EventWriter<LoadGame>,
+) {
+	for input in input.iter() {
+		if input.just_pressed(&SaveAction::NextSlot) {
+			selected_slot.0 = selected_slot.0 % SAVE_SLOTS + 1;
+			info!("Selected save {}", SaveSlot::Manual(selected_slot.0));
+		}
+		if input.just_pressed(&SaveAction::PreviousSlot) {
+			selected_slot.0 = (selected_slot.0 + SAVE_SLOTS - 2) % SAVE_SLOTS + 1;
+			info!("Selected save {}", SaveSlot::Manual(selected_slot.0));
+		}
+
+		if input.just_pressed(&SaveAction::QuickSave) {
+			ev_save.send(SaveGame(SaveSlot::Manual(selected_slot.0)));
+		}
+		if input.just_pressed(&SaveAction::QuickLoad) {
+			ev_load.send(LoadGame(SaveSlot::Manual(selected_slot.0)));
+		}
+	}
+}
+
+#[system(
+	plugin = SavePlugin, schedule = Update,
+	in_set = SaveGameSet,
+)]
+fn autosave(
+	mut autosave: ResMut<Autosave>,
+	time: Res<Time>,
+	mut ev_save: EventWriter<SaveGame>,
+	pending: Option<Res<PendingLoad>>,
+) {
+	// A half-loaded world isn't worth saving
+	if pending.is_some() {
+		return;
+	}
+
+	if autosave.0.tick(time.delta()).just_finished() {
+		ev_save.send(SaveGame(SaveSlot::Autosave));
+	}
+}
+
+#[system(
+	plugin = SavePlugin, schedule = Update,
+	after = SaveGameSet,
+)]
+fn save_game(
+	mut ev_save: EventReader<SaveGame>,
+	player: Query<(&Transform, &Inventory, &GristCache), With<PlayerBody>>,
+	camera: Query<&Pitch, With<PlayerCamera>>,
+	fray_tracks: Res<FrayTracks>,
+	quests: Res<Quests>,
+	completed_quests: Res<CompletedQuests>,
+	items: Query<(Entity, &Item, &Transform, Option<&QuestItem>)>,
+	equipped_items: Query<Entity, With<EquippedItem>>,
+	spawners: Query<(&Spawner, Option<&Name>)>,
+	factions: Res<FactionRegistry>,
+	// Items get saved with the rest of the items
+	entities: Query<
+		(
+			&Transform,
+			&GelViscosity,
+			Option<&NameTagged>,
+			Option<&QuestGiver>,
+		),
+		Without<Item>,
+	>,
+) {
+	for SaveGame(slot) in ev_save.read() {
+		let (player_transform, inventory, grist) = player.single();
+
+		// Whatever's in hand goes back in the inventory, since its transform is relative to the camera
+		let inventory_items = inventory
+			.items()
+			.into_iter()
+			.chain(equipped_items.iter())
+			.filter_map(|entity| items.get(entity).ok())
+			.map(|(_, item, _, quest_item)| SavedItem {
+				id: item.id.clone(),
+				quest_item: quest_item.map(|quest_item| quest_item.0.clone()),
+				transform: None,
+			});
+		let world_items = items
+			.iter()
+			.filter(|(entity, _, _, _)| {
+				!inventory.contains(*entity) && !equipped_items.contains(*entity)
+			})
+			.map(|(_, item, transform, quest_item)| SavedItem {
+				id: item.id.clone(),
+				quest_item: quest_item.map(|quest_item| quest_item.0.clone()),
+				transform: Some(*transform),
+			});
+
+		let mut completed_quests = completed_quests.0.iter().cloned().collect::<Vec<_>>();
+		completed_quests.sort();
+
+		let data = SaveData {
+			player: SavedPlayer {
+				transform: *player_transform,
+				pitch: camera.single().0,
+			},
+			player_track: fray_tracks.player,
+			quests: quests.0.values().cloned().collect(),
+			completed_quests,
+			items: inventory_items.chain(world_items).collect(),
+			spawners: spawners
+				.iter()
+				.map(|(spawner, name)| SavedSpawner {
+					name: name.map(|name| name.to_string()).unwrap_or_default(),
+					spawn_timer: spawner.spawn_timer,
+					entities: spawner
+						.entities
+						.iter()
+						// Entities still waiting on their assets haven't got anything worth saving
+						.filter_map(|entity| entities.get(*entity).ok())
+						.map(
+							|(transform, viscosity, name_tag, quest_giver)| SavedEntity {
+								transform: *transform,
+								viscosity: viscosity.value,
+								name_tag: name_tag.map(|name_tag| name_tag.0.clone()),
+								given_quest: quest_giver
+									.and_then(|quest_giver| quest_giver.given_quest),
+							},
+						)
+						.collect(),
+				})
+				.collect(),
+			reputations: factions
+				.reputations()
+				.filter_map(|(from, to, reputation)| {
+					Some(SavedReputation {
+						from: factions.get(from)?.name.clone(),
+						to: factions.get(to)?.name.clone(),
+						reputation,
+					})
+				})
+				.collect(),
+			grist: grist.clone(),
+		};
+
+		match data.write(*slot) {
+			Ok(()) => info!("Saved to {slot}"),
+			Err(err) => error!("Couldn't save to {slot}: {err}"),
+		}
+	}
+}
+
+#[system(
+	plugin = SavePlugin, schedule = Update,
+	after = LoadGameSet,
+)]
+fn load_game(mut ev_load: EventReader<LoadGame>, mut commands: Commands) {
+	let LoadGame(slot) = match ev_load.read().last() {
+		Some(ev) => ev,
+		None => return,
+	};
+
+	match SaveData::read(*slot) {
+		Ok(data) => {
+			info!("Loading {slot}");
+			commands.insert_resource(PendingLoad {
+				data,
+				stage: LoadStage::WaitingForLevel,
+			});
+		}
+		Err(err) => error!("Couldn't load {slot}: {err}"),
+	}
+}
+
+#[system(
+	plugin = SavePlugin, schedule = Update,
+	in_set = QuestEndedSet,
+	run_if = load_stage(LoadStage::WaitingForLevel),
+)]
+fn end_quests_for_load(
+	mut pending: ResMut<PendingLoad>,
+	spawners: Query<(), With<Spawner>>,
+	item_registry: Res<ItemRegistry>,
+	quests: Res<Quests>,
+	mut ev_ended: EventWriter<QuestEnded>,
+) {
+	if spawners.is_empty() || item_registry.is_empty() {
+		return;
+	}
+
+	for quest_id in quests.0.keys() {
+		ev_ended.send(QuestEnded(*quest_id));
+	}
+	pending.stage = LoadStage::EndingQuests;
+}
+
+#[system(
+	plugin = SavePlugin, schedule = Update,
+	in_set = ItemRemovedSet,
+	in_set = InventoryChangedSet,
+	before = RestoreSaveSet,
+	run_if = load_stage(LoadStage::EndingQuests),
+)]
+fn clear_world_for_load(
+	mut pending: ResMut<PendingLoad>,
+	quests: Res<Quests>,
+	mut commands: Commands,
+	mut spawners: Query<&mut Spawner>,
+	mut inventories: Query<(Entity, &mut Inventory)>,
+	items: Query<Entity, With<Item>>,
+	mut ev_removed: EventWriter<ItemRemoved>,
+	mut ev_inventory_changed: EventWriter<InventoryChanged>,
+) {
+	if !quests.0.is_empty() {
+		return;
+	}
+
+	for mut spawner in spawners.iter_mut() {
+		for entity in spawner.entities.drain() {
+			if let Some(entity) = commands.get_entity(entity) {
+				entity.despawn_recursive();
+			}
+		}
+	}
+
+	for item in items.iter() {
+		commands.entity(item).despawn_recursive();
+	}
+	for (inventory_entity, mut inventory) in inventories.iter_mut() {
+		for item in inventory.items() {
+			ev_removed.send(ItemRemoved(item));
+		}
+		inventory.clear();
+		ev_inventory_changed.send(InventoryChanged(inventory_entity));
+	}
+
+	pending.stage = LoadStage::Restoring;
+}
+
+#[system(
+	plugin = SavePlugin, schedule = Update,
+	in_set = RestoreSaveSet,
+	run_if = load_stage(LoadStage::Restoring),
+)]
+fn restore_player(
+	pending: Res<PendingLoad>,
+	mut player: Query<(&mut Transform, &mut GristCache), With<PlayerBody>>,
+	mut camera: Query<(&mut Transform, &mut Pitch), (With<PlayerCamera>, Without<PlayerBody>)>,
+	mut fray_tracks: ResMut<FrayTracks>,
+) {
+	let (mut player_transform, mut grist) = player.single_mut();
+	*player_transform = pending.data.player.transform;
+	*grist = pending.data.grist.clone();
+
+	let (mut camera_transform, mut pitch) = camera.single_mut();
+	pitch.0 = pending.data.player.pitch;
+	camera_transform.rotation = Quat::from_rotation_x(-pitch.0);
+
+	fray_tracks.set_player_track(pending.data.player_track);
+}
+
+#[system(
+	plugin = SavePlugin, schedule = Update,
+	in_set = RestoreSaveSet,
+	run_if = load_stage(LoadStage::Restoring),
+)]
+fn restore_quests(
+	pending: Res<PendingLoad>,
+	mut quests: ResMut<Quests>,
+	mut completed_quests: ResMut<CompletedQuests>,
+	mut ev_accepted: EventWriter<QuestAccepted>,
+) {
+	completed_quests.0 = pending.data.completed_quests.iter().cloned().collect();
+
+	for quest in pending.data.quests.iter() {
+		quests.0.insert(quest.id, quest.clone());
+		// There's no proposal dialogue to close, this just puts it on the quest screen
+		ev_accepted.send(QuestAccepted {
+			quest_proposal: Entity::PLACEHOLDER,
+			quest_id: quest.id,
+		});
+	}
+}
+
+#[system(
+	plugin = SavePlugin, schedule = Update,
+	in_set = RestoreSaveSet,
+	run_if = load_stage(LoadStage::Restoring),
+)]
+fn restore_reputations(pending: Res<PendingLoad>, mut factions: ResMut<FactionRegistry>) {
+	factions.reset_reputation();
+
+	for saved in pending.data.reputations.iter() {
+		let (Some(from), Some(to)) = (factions.find(&saved.from), factions.find(&saved.to)) else {
+			warn!(
+				"No factions named {:?} and {:?} to restore reputation between",
+				saved.from, saved.to
+			);
+			continue;
+		};
+		factions.change_reputation(from, to, saved.reputation);
+	}
+}
+
+#[system(
+	plugin = SavePlugin, schedule = Update,
+	in_set = RestoreSaveSet,
+	before = ItemPickedUpSet,
+	run_if = load_stage(LoadStage::Restoring),
+)]
+fn restore_items(
+	pending: Res<PendingLoad>,
+	mut item_spawner: ItemSpawner,
+	mut inventories: Query<(Entity, &mut Inventory)>,
+	mut ev_picked_up: EventWriter<ItemPickedUp>,
+	mut ev_inventory_changed: EventWriter<InventoryChanged>,
+) {
+	let (inventory_entity, mut inventory) = inventories.single_mut();
+
+	for item in pending.data.items.iter() {
+		let transform = item.transform.unwrap_or_default();
+		let mut item_commands = some_or_continue!(item_spawner.spawn(&item.id, transform));
+
+		if let Some(quest_item) = &item.quest_item {
+			item_commands.insert(QuestItem(quest_item.clone()));
+		}
+
+		if item.transform.is_none() {
+			item_commands
+				.remove::<RigidBody>()
+				.insert((Visibility::Hidden, ColliderDisabled));
+			let item_entity = item_commands.id();
+			let name = item_spawner
+				.registry()
+				.get(&item.id)
+				.map_or("Item", |definition| definition.name.as_str());
+			inventory.captchalogue(item_entity, name);
+			ev_picked_up.send(ItemPickedUp(item_entity));
+		}
+	}
+
+	ev_inventory_changed.send(InventoryChanged(inventory_entity));
+}
+
+#[system(
+	plugin = SavePlugin, schedule = Update,
+	in_set = RestoreSaveSet,
+	in_set = SpawnerActivatedSet,
+	run_if = load_stage(LoadStage::Restoring),
+)]
+fn restore_spawners(
+	pending: Res<PendingLoad>,
+	mut commands: Commands,
+	mut spawners: Query<(Entity, &mut Spawner, Option<&Name>)>,
+	mut ev_spawned: EventWriter<SpawnerActivated>,
+) {
+	let mut unmatched = spawners.iter_mut().collect::<Vec<_>>();
+
+	for saved_spawner in pending.data.spawners.iter() {
+		let Some(index) = unmatched.iter().position(|(_, _, name)| {
+			name.map(|name| name.as_str()).unwrap_or_default() == saved_spawner.name
+		}) else {
+			warn!("No spawner named {:?} in the level", saved_spawner.name);
+			continue;
+		};
+		let (spawner_entity, mut spawner, _) = unmatched.swap_remove(index);
+
+		spawner.spawn_timer = saved_spawner.spawn_timer;
+
+		for saved_entity in saved_spawner.entities.iter() {
+			let mut entity_commands = commands.spawn(saved_entity.clone());
+			if let Some(name_tag) = &saved_entity.name_tag {
+				entity_commands.insert(NameTagged(name_tag.clone()));
+			}
+			let entity = entity_commands.id();
+
+			spawner.entities.insert(entity);
+			ev_spawned.send(SpawnerActivated {
+				entity,
+				spawner: spawner_entity,
+				position: saved_entity.transform.translation,
+			});
+		}
+	}
+}
+
+#[system(
+	plugin = SavePlugin, schedule = Update,
+	after = RestoreSaveSet,
+)]
+fn finish_loading(mut commands: Commands, pending: Option<Res<PendingLoad>>) {
+	if pending.is_some_and(|pending| pending.stage == LoadStage::Restoring) {
+		commands.remove_resource::<PendingLoad>();
+	}
+}
+
+#[system(
+	plugin = SavePlugin, schedule = Update,
+	after = EntitySpawnedSet,
+)]
+fn restore_spawned_entities(
+	mut commands: Commands,
+	mut entities: Query<
+		(
+			Entity,
+			&SavedEntity,
+			&mut Transform,
+			&mut GelViscosity,
+			Option<&mut QuestGiver>,
+		),
+		With<Mob>,
+	>,
+	mut quests: ResMut<Quests>,
+) {
+	for (entity, saved, mut transform, mut viscosity, quest_giver) in entities.iter_mut() {
+		*transform = saved.transform;
+		viscosity.value = saved.viscosity;
+
+		if let Some(mut quest_giver) = quest_giver {
+			quest_giver.given_quest = saved.given_quest;
+			// So the quest markers pick up the restored quest
+			quests.set_changed();
+		}
+
+		commands.entity(entity).remove::<SavedEntity>();
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::grist::GristType;
+
+	#[test]
+	fn saves_round_trip() {
+		let mut grist = GristCache {
+			tier: 2,
+			..default()
+		};
+		grist.add(GristType::Shale, 30);
+		let save = SaveData {
+			player: SavedPlayer {
+				transform: Transform::from_xyz(1.0, 2.0, 3.0),
+				pitch: 0.5,
+			},
+			player_track: Track::SixEight,
+			quests: Vec::new(),
+			completed_quests: vec!["kill_imps".to_owned()],
+			items: vec![SavedItem {
+				id: "orange_cube".to_owned(),
+				quest_item: None,
+				transform: Some(Transform::from_xyz(4.0, 0.0, 0.0)),
+			}],
+			spawners: vec![SavedSpawner {
+				name: "Consort Spawner".to_owned(),
+				spawn_timer: Duration::ZERO,
+				entities: Vec::new(),
+			}],
+			reputations: vec![SavedReputation {
+				from: "Consorts".to_owned(),
+				to: "Player".to_owned(),
+				reputation: 4,
+			}],
+			grist,
+		};
+		let file = ron::to_string(&SaveFile {
+			version: SAVE_VERSION,
+			data: save,
+		})
+		.unwrap();
+
+		let save = SaveData::parse(&file).unwrap();
+		assert_eq!(save.player.transform.translation, Vec3::new(1.0, 2.0, 3.0));
+		assert_eq!(save.player.pitch, 0.5);
+		assert!(matches!(save.player_track, Track::SixEight));
+		assert_eq!(save.completed_quests, ["kill_imps"]);
+		assert_eq!(save.items[0].id, "orange_cube");
+		assert!(save.items[0].transform.is_some());
+		assert_eq!(save.spawners[0].name, "Consort Spawner");
+		assert_eq!(save.reputations[0].from, "Consorts");
+		assert_eq!(save.reputations[0].to, "Player");
+		assert_eq!(save.reputations[0].reputation, 4);
+		assert_eq!(save.grist.tier, 2);
+		assert_eq!(save.grist.get(GristType::Shale), 30);
+		assert_eq!(save.grist.get(GristType::Build), 0);
+	}
+
+	#[test]
+	fn future_saves_are_rejected() {
+		let file = format!("(version: {}, data: ())", SAVE_VERSION + 1);
+		let version = SAVE_VERSION + 1;
+		assert!(matches!(
+			SaveData::parse(&file),
+			Err(SaveError::UnknownVersion(v)) if v == version
+		));
+	}
+}
@@ -26,26 +26,6 @@ impl MapRange<Real> for Real {
 	}
 }
 
-pub trait TransformEx {
-	fn transform_vector3(&self, vector: Vec3) -> Vec3;
-	fn inverse_transform_point(&self, point: Vec3) -> Vec3;
-	#[allow(dead_code)]
-	fn inverse_transform_vector3(&self, vector: Vec3) -> Vec3;
-}
-impl TransformEx for GlobalTransform {
-	fn transform_vector3(&self, vector: Vec3) -> Vec3 {
-		self.affine().transform_vector3(vector)
-	}
-
-	fn inverse_transform_point(&self, point: Vec3) -> Vec3 {
-		self.affine().inverse().transform_point3(point)
-	}
-
-	fn inverse_transform_vector3(&self, vector: Vec3) -> Vec3 {
-		self.affine().inverse().transform_vector3(vector)
-	}
-}
-
 pub trait IterElements<T, const N: usize> {
 	fn iter_elements(&self) -> IntoIter<T, N>;
 }
@@ -55,6 +35,11 @@ impl IterElements<f32, 3> for Vec3 {
 	}
 }
 
+/// The value after a command line flag, like `--seed 1234`.
+pub fn arg_value(flag: &str) -> Option<String> {
+	std::env::args().skip_while(|arg| arg != flag).nth(1)
+}
+
 #[derive(Component, Deref, DerefMut)]
 pub struct DespawnTimer(Timer);
 